@@ -0,0 +1,16 @@
+-- Health probes registered at runtime through the admin API
+CREATE TABLE health_probes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name TEXT NOT NULL,
+    kind JSONB NOT NULL,
+    interval_ms BIGINT NOT NULL CHECK (interval_ms > 0),
+    timeout_ms BIGINT NOT NULL CHECK (timeout_ms > 0),
+    failure_threshold INTEGER NOT NULL CHECK (failure_threshold > 0),
+    success_threshold INTEGER NOT NULL CHECK (success_threshold > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER health_probes_updated_at
+    BEFORE UPDATE ON health_probes
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at();
@@ -0,0 +1,17 @@
+-- Authorization policy, used when AUTHZ_SOURCE=database.
+-- Roles allowed to call a method: pattern is package.Service/Method,
+-- package.Service or *, the most specific match wins, and role * lets
+-- anyone call it.
+CREATE TABLE authz_rules (
+    pattern TEXT NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (pattern, role)
+);
+
+-- Roles granted to principals (token subjects, client certificate
+-- subjects, uid:N), on top of those in their token.
+CREATE TABLE authz_bindings (
+    principal TEXT NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (principal, role)
+);
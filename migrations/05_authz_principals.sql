@@ -0,0 +1,7 @@
+-- Principals are prefixed by how the caller authenticated, so that one
+-- kind can't pass for another: jwt:<token sub>, x509:<client certificate
+-- subject, e.g. O=Acme, CN=ops-bot> or uid:<Unix socket peer uid>.
+COMMENT ON COLUMN authz_bindings.principal IS
+    'jwt:<token sub>, x509:<client certificate subject> or uid:<Unix socket peer uid>';
+COMMENT ON COLUMN audit_log.principal IS
+    'anonymous, jwt:<token sub>, x509:<client certificate subject> or uid:<Unix socket peer uid>';
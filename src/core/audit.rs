@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sqlx::PgPool;
+
+use super::error::{AppError, AppResult};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Who made a request. Stored in the request extensions by whatever
+/// authenticates it; requests without one are audited as `anonymous`.
+/// The name is prefixed by how the caller was authenticated, so that a
+/// token whose subject is `uid:0` isn't taken for that Unix user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal(pub String);
+
+impl Principal {
+    pub fn anonymous() -> Self {
+        Self("anonymous".to_owned())
+    }
+
+    /// `jwt:<sub>`, for a bearer token.
+    pub fn token(subject: &str) -> Self {
+        Self(format!("jwt:{subject}"))
+    }
+
+    /// `x509:<subject>`, for a verified client certificate.
+    pub fn certificate(subject: &str) -> Self {
+        Self(format!("x509:{subject}"))
+    }
+
+    /// `uid:<n>`, for a Unix socket peer.
+    pub fn unix_peer(uid: u32) -> Self {
+        Self(format!("uid:{uid}"))
+    }
+}
+
+/// Lets a handler name the resource a mutating call acts on. The audit
+/// middleware puts one in the request extensions and reads it back once
+/// the handler has finished, whether it succeeded or not.
+#[derive(Debug, Clone, Default)]
+pub struct AuditScope(Arc<Mutex<Option<String>>>);
+
+impl AuditScope {
+    pub fn set_target(&self, target: impl Into<String>) {
+        *self.0.lock().unwrap() = Some(target.into());
+    }
+
+    pub fn target(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// The audit scope of `request`. Calls that aren't audited get a detached
+/// one, so handlers needn't check.
+pub fn scope<T>(request: &tonic::Request<T>) -> AuditScope {
+    request
+        .extensions()
+        .get::<AuditScope>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Records `target` for the audit entry of `request`, if it is audited.
+pub fn set_target<T>(request: &tonic::Request<T>, target: impl Into<String>) {
+    scope(request).set_target(target);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub occurred_at: SystemTime,
+    /// Full gRPC path, `/package.Service/Method`.
+    pub method: String,
+    pub principal: String,
+    pub request_id: String,
+    pub target: Option<String>,
+    /// gRPC status code name, e.g. `Ok` or `NotFound`.
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditQuery {
+    pub method: Option<String>,
+    pub principal: Option<String>,
+    pub target: Option<String>,
+    pub status: Option<String>,
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+    pub page_size: u32,
+    /// Only events older than this id; the cursor from the previous page.
+    pub before: Option<i64>,
+}
+
+impl AuditQuery {
+    /// Page size to fetch: the default when unset, capped at the maximum.
+    pub fn limit(&self) -> u32 {
+        match self.page_size {
+            0 => DEFAULT_PAGE_SIZE,
+            n => n.min(MAX_PAGE_SIZE),
+        }
+    }
+}
+
+/// Opaque to clients: the id of the last event on the page.
+pub fn encode_cursor(id: i64) -> String {
+    id.to_string()
+}
+
+pub fn decode_cursor(token: &str) -> AppResult<Option<i64>> {
+    if token.is_empty() {
+        return Ok(None);
+    }
+    token
+        .parse::<i64>()
+        .ok()
+        .filter(|id| *id > 0)
+        .map(Some)
+        .ok_or_else(|| AppError::InvalidArgument(format!("invalid page token: {token}")))
+}
+
+pub async fn record(
+    db: &PgPool,
+    method: &str,
+    principal: &Principal,
+    request_id: &str,
+    target: Option<&str>,
+    status: tonic::Code,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"INSERT INTO audit_log (method, principal, request_id, target, status)
+           VALUES ($1, $2, $3, $4, $5)"#,
+        method,
+        principal.0,
+        request_id,
+        target,
+        format!("{status:?}"),
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Newest first. Returns the page and the cursor for the next one, if
+/// there may be more.
+pub async fn list(db: &PgPool, query: &AuditQuery) -> AppResult<(Vec<AuditEvent>, Option<String>)> {
+    let limit = query.limit();
+    let rows = sqlx::query!(
+        r#"SELECT id, (EXTRACT(EPOCH FROM occurred_at) * 1000)::BIGINT AS "occurred_at_ms!",
+                  method, principal, request_id, target, status
+           FROM audit_log
+           WHERE ($1::TEXT IS NULL OR method = $1)
+             AND ($2::TEXT IS NULL OR principal = $2)
+             AND ($3::TEXT IS NULL OR target = $3)
+             AND ($4::TEXT IS NULL OR status = $4)
+             AND ($5::BIGINT IS NULL OR occurred_at >= to_timestamp($5 / 1000.0))
+             AND ($6::BIGINT IS NULL OR occurred_at < to_timestamp($6 / 1000.0))
+             AND ($7::BIGINT IS NULL OR id < $7)
+           ORDER BY id DESC
+           LIMIT $8"#,
+        query.method,
+        query.principal,
+        query.target,
+        query.status,
+        query.since.map(epoch_ms),
+        query.until.map(epoch_ms),
+        query.before,
+        i64::from(limit),
+    )
+    .fetch_all(db)
+    .await?;
+
+    let events: Vec<AuditEvent> = rows
+        .into_iter()
+        .map(|row| AuditEvent {
+            id: row.id,
+            occurred_at: UNIX_EPOCH + Duration::from_millis(row.occurred_at_ms.max(0) as u64),
+            method: row.method,
+            principal: row.principal,
+            request_id: row.request_id,
+            target: row.target,
+            status: row.status,
+        })
+        .collect();
+    let next = (events.len() == limit as usize)
+        .then(|| events.last().map(|e| encode_cursor(e.id)))
+        .flatten();
+    Ok((events, next))
+}
+
+fn epoch_ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/audit.rs"]
+mod tests;
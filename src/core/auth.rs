@@ -0,0 +1,305 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwapOption;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value};
+use tokio::sync::Mutex;
+
+use super::config::Config;
+use super::redact::{self, Secret};
+
+/// Allowed clock skew when checking `exp` and `nbf`.
+const LEEWAY_SECS: u64 = 60;
+
+/// A token signed with a key missing from the JWKS refetches it, at most
+/// this often, in case the key was rotated in since.
+const MIN_REFETCH: Duration = Duration::from_secs(30);
+
+const JWKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The verified claims of the caller's bearer token. Stored in the request
+/// extensions by `grpc::auth::authenticate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claims(pub Map<String, Value>);
+
+impl Claims {
+    pub fn subject(&self) -> Option<&str> {
+        self.0.get("sub").and_then(Value::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Public,
+    Authenticated,
+}
+
+/// Whether `path`, `/package.Service/Method`, can be called without a
+/// token. A method listed in `auth_public` or `auth_authenticated` wins
+/// over its service; anything unlisted needs one.
+pub fn access(config: &Config, path: &str) -> Access {
+    let method = path.trim_start_matches('/');
+    let service = method
+        .split_once('/')
+        .map_or(method, |(service, _)| service);
+    for name in [method, service] {
+        if config.auth_authenticated.iter().any(|e| e == name) {
+            return Access::Authenticated;
+        }
+        if config.auth_public.iter().any(|e| e == name) {
+            return Access::Public;
+        }
+    }
+    Access::Authenticated
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthSettings {
+    /// Key for HS256 tokens.
+    pub secret: Option<Secret<String>>,
+    /// Path or URL of the JWKS with RS256 and ES256 keys.
+    pub jwks: Option<String>,
+    pub jwks_refresh: Duration,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+}
+
+impl AuthSettings {
+    /// `None` unless a secret or a JWKS is configured, in which case every
+    /// RPC is open.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if config.auth_jwt_secret.is_none() && config.auth_jwks.is_none() {
+            return None;
+        }
+        Some(Self {
+            secret: config.auth_jwt_secret.clone(),
+            jwks: config.auth_jwks.as_ref().map(|jwks| jwks.expose().clone()),
+            jwks_refresh: Duration::from_secs(config.auth_jwks_refresh_secs),
+            issuer: config.auth_issuer.clone(),
+            audience: config.auth_audience.clone(),
+        })
+    }
+}
+
+/// Verifies bearer tokens, caching the JWKS between calls.
+pub struct Authenticator {
+    jwks: Arc<JwksCache>,
+}
+
+/// Callers read the cached keys without waiting; only a caller with no
+/// usable key waits for a fetch.
+struct JwksCache {
+    cached: ArcSwapOption<CachedJwks>,
+    /// Held while fetching, so that one fetch runs at a time.
+    fetching: Mutex<()>,
+    http: reqwest::Client,
+}
+
+#[derive(Clone)]
+struct CachedJwks {
+    source: String,
+    keys: JwkSet,
+    fetched_at: Instant,
+    /// When to fetch again; sooner than `jwks_refresh` after a failed
+    /// fetch left the previous keys in use.
+    expires_at: Instant,
+}
+
+#[allow(dead_code)]
+impl Authenticator {
+    pub fn new() -> Self {
+        Self {
+            jwks: Arc::new(JwksCache {
+                cached: ArcSwapOption::empty(),
+                fetching: Mutex::new(()),
+                http: reqwest::Client::builder()
+                    .timeout(JWKS_TIMEOUT)
+                    .build()
+                    .expect("failed to build JWKS client"),
+            }),
+        }
+    }
+
+    /// The claims of `token` if its signature, expiry, and issuer and
+    /// audience when configured, check out. The error says why not.
+    pub async fn verify(&self, settings: &AuthSettings, token: &str) -> Result<Claims, String> {
+        let header =
+            jsonwebtoken::decode_header(token).map_err(|e| format!("invalid token: {e}"))?;
+        let key = match (header.alg, &settings.secret, &settings.jwks) {
+            (Algorithm::HS256, Some(secret), _) => {
+                DecodingKey::from_secret(secret.expose().as_bytes())
+            }
+            (Algorithm::RS256 | Algorithm::ES256, _, Some(source)) => {
+                self.key(source, settings.jwks_refresh, header.kid.as_deref())
+                    .await?
+            }
+            (alg, _, _) => return Err(format!("{alg:?} tokens are not accepted")),
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = LEEWAY_SECS;
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(issuer) = &settings.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &settings.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        let data = jsonwebtoken::decode::<Map<String, Value>>(token, &key, &validation)
+            .map_err(|e| format!("invalid token: {e}"))?;
+        let claims = Claims(data.claims);
+        if claims.subject().is_none_or(str::is_empty) {
+            return Err("invalid token: sub must be a non-empty string".to_owned());
+        }
+        Ok(claims)
+    }
+
+    /// The key `kid` names, or the only key when the token names none.
+    /// Expired keys stay in use while they are refreshed in the
+    /// background.
+    async fn key(
+        &self,
+        source: &str,
+        refresh: Duration,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey, String> {
+        let cached = self.jwks.cached.load_full().filter(|c| c.source == source);
+        let Some(cached) = cached else {
+            let fetched = self.jwks.refresh(source, refresh, None).await?;
+            return find_key(&fetched.keys, kid)?.ok_or_else(|| missing_key(kid));
+        };
+        if Instant::now() >= cached.expires_at {
+            Arc::clone(&self.jwks).refresh_in_background(source, refresh, &cached);
+        }
+        if let Some(key) = find_key(&cached.keys, kid)? {
+            return Ok(key);
+        }
+        if cached.fetched_at.elapsed() >= MIN_REFETCH {
+            let fetched = self.jwks.refresh(source, refresh, Some(&cached)).await?;
+            if let Some(key) = find_key(&fetched.keys, kid)? {
+                return Ok(key);
+            }
+        }
+        Err(missing_key(kid))
+    }
+}
+
+impl JwksCache {
+    /// Fetches the JWKS from `source`, unless someone else did since
+    /// `seen` was read. If that fails, keys already fetched from `source`
+    /// stay in use and the fetch is retried after `MIN_REFETCH`.
+    async fn refresh(
+        &self,
+        source: &str,
+        refresh: Duration,
+        seen: Option<&Arc<CachedJwks>>,
+    ) -> Result<Arc<CachedJwks>, String> {
+        let _fetching = self.fetching.lock().await;
+        let current = self.cached.load_full().filter(|c| c.source == source);
+        if let Some(current) = &current
+            && seen.is_none_or(|seen| !Arc::ptr_eq(seen, current))
+        {
+            return Ok(Arc::clone(current));
+        }
+
+        let now = Instant::now();
+        let fetched = match self.fetch(source).await {
+            Ok(keys) => CachedJwks {
+                source: source.to_owned(),
+                keys,
+                fetched_at: now,
+                expires_at: now + refresh,
+            },
+            Err(err) => match current {
+                Some(current) => {
+                    tracing::warn!(%err, "JWKS not refreshed, using the keys fetched before");
+                    CachedJwks {
+                        expires_at: now + MIN_REFETCH,
+                        ..(*current).clone()
+                    }
+                }
+                None => {
+                    tracing::error!(%err, "JWKS unavailable");
+                    return Err("signing keys unavailable".to_owned());
+                }
+            },
+        };
+        let fetched = Arc::new(fetched);
+        self.cached.store(Some(Arc::clone(&fetched)));
+        Ok(fetched)
+    }
+
+    /// Refreshes the expired `seen` keys without holding up the caller,
+    /// unless a fetch is already running.
+    fn refresh_in_background(
+        self: Arc<Self>,
+        source: &str,
+        refresh: Duration,
+        seen: &Arc<CachedJwks>,
+    ) {
+        if self.fetching.try_lock().is_err() {
+            return;
+        }
+        let source = source.to_owned();
+        let seen = Arc::clone(seen);
+        tokio::spawn(async move {
+            let _ = self.refresh(&source, refresh, Some(&seen)).await;
+        });
+    }
+
+    /// Errors name a URL by its scheme and host only, as it may carry a
+    /// token.
+    async fn fetch(&self, source: &str) -> Result<JwkSet, String> {
+        let (text, shown) = if source.starts_with("http://") || source.starts_with("https://") {
+            let shown = redact::scheme_and_host(source);
+            let response = self
+                .http
+                .get(source)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| format!("cannot fetch {shown}: {}", e.without_url()))?;
+            let text = response
+                .text()
+                .await
+                .map_err(|e| format!("cannot fetch {shown}: {}", e.without_url()))?;
+            (text, shown)
+        } else {
+            let text = tokio::fs::read_to_string(source)
+                .await
+                .map_err(|e| format!("cannot read {source}: {e}"))?;
+            (text, source.to_owned())
+        };
+        serde_json::from_str(&text).map_err(|e| format!("invalid JWKS in {shown}: {e}"))
+    }
+}
+
+impl Default for Authenticator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn missing_key(kid: Option<&str>) -> String {
+    match kid {
+        Some(kid) => format!("no key {kid:?} in the JWKS"),
+        None => "token has no kid and the JWKS has more than one key".to_owned(),
+    }
+}
+
+fn find_key(keys: &JwkSet, kid: Option<&str>) -> Result<Option<DecodingKey>, String> {
+    let jwk = match kid {
+        Some(kid) => keys.find(kid),
+        None if keys.keys.len() == 1 => keys.keys.first(),
+        None => None,
+    };
+    jwk.map(|jwk| DecodingKey::from_jwk(jwk).map_err(|e| format!("unusable key in the JWKS: {e}")))
+        .transpose()
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/auth.rs"]
+mod tests;
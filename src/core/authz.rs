@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use sqlx::PgPool;
+
+use super::audit::Principal;
+use super::auth::Claims;
+use super::config::{Config, is_rpc_name};
+use super::error::AppResult;
+use super::state::AppState;
+
+/// Matches every method, for a rule that applies when no other does.
+pub const ANY_METHOD: &str = "*";
+
+/// A role every caller has, authenticated or not.
+pub const ANY_ROLE: &str = "*";
+
+/// One `pattern=role+role` entry of `authz_rules`: callers with any of
+/// `roles` may call the methods `pattern` matches, which is
+/// `package.Service/Method`, `package.Service` or `*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub pattern: String,
+    pub roles: Vec<String>,
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((pattern, roles)) = s.split_once('=') else {
+            return Err("expected pattern=role+role".to_owned());
+        };
+        let pattern = pattern.trim();
+        if pattern != ANY_METHOD && !is_rpc_name(pattern) {
+            return Err(format!(
+                "{pattern:?} is not *, a package.Service or package.Service/Method name"
+            ));
+        }
+        let roles: Vec<String> = roles
+            .split('+')
+            .map(|r| r.trim().to_owned())
+            .filter(|r| !r.is_empty())
+            .collect();
+        if roles.is_empty() {
+            return Err(format!("no roles for {pattern}"));
+        }
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            roles,
+        })
+    }
+}
+
+/// Why a call was denied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    /// No rule matches the method.
+    NoRule,
+    /// The caller has none of these roles.
+    MissingRole(Vec<String>),
+}
+
+impl fmt::Display for Denial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Denial::NoRule => f.write_str("no policy rule allows it"),
+            Denial::MissingRole(roles) => {
+                write!(f, "it needs one of the roles {}", roles.join(", "))
+            }
+        }
+    }
+}
+
+/// Which roles may call which methods, and the roles granted to
+/// principals. Methods without a matching rule are denied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    rules: HashMap<String, Vec<String>>,
+    bindings: HashMap<String, Vec<String>>,
+}
+
+#[allow(dead_code)]
+impl Policy {
+    pub fn new(
+        rules: impl IntoIterator<Item = Rule>,
+        bindings: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        let mut policy = Self::default();
+        for rule in rules {
+            policy
+                .rules
+                .entry(rule.pattern)
+                .or_default()
+                .extend(rule.roles);
+        }
+        for (principal, role) in bindings {
+            policy.bindings.entry(principal).or_default().push(role);
+        }
+        policy
+    }
+
+    /// The policy in `authz_rules`. Only valid once validated.
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.authz_rules.iter().filter_map(|r| r.parse().ok()), [])
+    }
+
+    /// The policy in the `authz_rules` and `authz_bindings` tables. Rows
+    /// with an invalid pattern are skipped with a warning.
+    pub async fn load(db: &PgPool) -> AppResult<Self> {
+        let rules = sqlx::query!("SELECT pattern, role FROM authz_rules ORDER BY pattern, role")
+            .fetch_all(db)
+            .await?;
+        let bindings =
+            sqlx::query!("SELECT principal, role FROM authz_bindings ORDER BY principal, role")
+                .fetch_all(db)
+                .await?;
+
+        let rules = rules.into_iter().filter_map(|row| {
+            match format!("{}={}", row.pattern, row.role).parse::<Rule>() {
+                Ok(rule) => Some(rule),
+                Err(err) => {
+                    tracing::warn!(pattern = %row.pattern, %err, "skipping authz rule");
+                    None
+                }
+            }
+        });
+        Ok(Self::new(
+            rules,
+            bindings.into_iter().map(|row| (row.principal, row.role)),
+        ))
+    }
+
+    /// Roles allowed to call `path`, `/package.Service/Method`, from the
+    /// most specific rule that matches it.
+    pub fn required(&self, path: &str) -> Option<&[String]> {
+        let method = path.trim_start_matches('/');
+        let service = method
+            .split_once('/')
+            .map_or(method, |(service, _)| service);
+        [method, service, ANY_METHOD]
+            .into_iter()
+            .find_map(|pattern| self.rules.get(pattern))
+            .map(Vec::as_slice)
+    }
+
+    /// Allows the call if `principal`, with `token_roles` and the roles
+    /// bound to it, has one of the roles `path` needs.
+    pub fn check(
+        &self,
+        path: &str,
+        principal: &Principal,
+        token_roles: &[String],
+    ) -> Result<(), Denial> {
+        let required = self.required(path).ok_or(Denial::NoRule)?;
+        let bound = self.bindings.get(&principal.0).map(Vec::as_slice);
+        let allowed = required.iter().any(|role| {
+            role == ANY_ROLE
+                || token_roles.contains(role)
+                || bound.is_some_and(|bound| bound.contains(role))
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(Denial::MissingRole(required.to_vec()))
+        }
+    }
+}
+
+/// The roles in `claims`: each named claim may be an array of strings or
+/// a space-separated string, as OAuth's `scope` is.
+pub fn token_roles(claims: Option<&Claims>, names: &[String]) -> Vec<String> {
+    let Some(claims) = claims else {
+        return Vec::new();
+    };
+    let mut roles = Vec::new();
+    for name in names {
+        match claims.0.get(name) {
+            Some(Value::Array(items)) => {
+                roles.extend(items.iter().filter_map(Value::as_str).map(str::to_owned));
+            }
+            Some(Value::String(s)) => roles.extend(s.split_whitespace().map(str::to_owned)),
+            _ => {}
+        }
+    }
+    roles
+}
+
+/// Loads the policy from the database when that is the source, failing
+/// startup if it can't, then keeps it fresh: every `authz_refresh_secs`
+/// and whenever `AppState::reload_policy` asks.
+pub async fn init(state: &Arc<AppState>) -> AppResult<()> {
+    if state.config().authz_source == "database" {
+        state.set_policy(Policy::load(state.db()).await?);
+    }
+    let state = Arc::clone(state);
+    tokio::spawn(async move {
+        loop {
+            let every = Duration::from_secs(state.config().authz_refresh_secs);
+            tokio::select! {
+                _ = tokio::time::sleep(every) => {}
+                _ = state.policy_reload_requested() => {}
+            }
+            if state.config().authz_source != "database" {
+                continue;
+            }
+            match Policy::load(state.db()).await {
+                Ok(policy) => {
+                    if *state.policy() != policy {
+                        tracing::info!("authorization policy updated");
+                        state.set_policy(policy);
+                    }
+                }
+                Err(err) => {
+                    tracing::error!(%err, "authorization policy not reloaded, keeping the current one")
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/authz.rs"]
+mod tests;
@@ -1,41 +1,957 @@
-#[derive(Debug, Clone)]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use http::HeaderValue;
+use serde::Serialize;
+use sqlx::postgres::PgConnectOptions;
+use tracing_subscriber::EnvFilter;
+
+use super::authz::Rule;
+use super::listen::ListenerSpec;
+use super::redact::{self, Secret};
+use super::secrets::{self, SecretProvider};
+
+/// Services callable without a token by default: health checks and
+/// reflection.
+pub const DEFAULT_AUTH_PUBLIC: &str = "midnight.HealthService,grpc.health.v1.Health,\
+     grpc.reflection.v1.ServerReflection,grpc.reflection.v1alpha.ServerReflection";
+
+/// Rules letting anyone call the services in `DEFAULT_AUTH_PUBLIC`.
+pub const DEFAULT_AUTHZ_RULES: &str = "midnight.HealthService=*,grpc.health.v1.Health=*,\
+     grpc.reflection.v1.ServerReflection=*,grpc.reflection.v1alpha.ServerReflection=*";
+
+/// Environment variable naming the config file when `--config` isn't given.
+pub const CONFIG_FILE_ENV: &str = "CONFIG_FILE";
+
+/// Every setting: its name in the config file, which with underscores
+/// turned into dashes is also its command-line flag, and its environment
+/// variable.
+pub const KEYS: &[(&str, &str)] = &[
+    ("listen_addr", "LISTEN_ADDR"),
+    ("admin_listen_addr", "ADMIN_LISTEN_ADDR"),
+    ("tls_cert", "TLS_CERT"),
+    ("tls_key", "TLS_KEY"),
+    ("tls_client_ca", "TLS_CLIENT_CA"),
+    ("tls_min_version", "TLS_MIN_VERSION"),
+    ("auth_jwt_secret", "AUTH_JWT_SECRET"),
+    ("auth_jwks", "AUTH_JWKS"),
+    ("auth_jwks_refresh_secs", "AUTH_JWKS_REFRESH_SECS"),
+    ("auth_issuer", "AUTH_ISSUER"),
+    ("auth_audience", "AUTH_AUDIENCE"),
+    ("auth_public", "AUTH_PUBLIC"),
+    ("auth_authenticated", "AUTH_AUTHENTICATED"),
+    ("authz_source", "AUTHZ_SOURCE"),
+    ("authz_rules", "AUTHZ_RULES"),
+    ("authz_role_claims", "AUTHZ_ROLE_CLAIMS"),
+    ("authz_refresh_secs", "AUTHZ_REFRESH_SECS"),
+    ("log_level", "LOG_LEVEL"),
+    ("log_style", "LOG_STYLE"),
+    ("log_file", "LOG_FILE"),
+    ("log_file_rotation", "LOG_FILE_ROTATION"),
+    ("log_file_max_size_mb", "LOG_FILE_MAX_SIZE_MB"),
+    ("log_file_max_files", "LOG_FILE_MAX_FILES"),
+    ("log_file_compress", "LOG_FILE_COMPRESS"),
+    ("log_redact_fields", "LOG_REDACT_FIELDS"),
+    ("cors_origins", "CORS_ORIGINS"),
+    ("database_url", "DATABASE_URL"),
+    ("db_max_connections", "DB_MAX_CONNECTIONS"),
+    ("request_timeout_secs", "REQUEST_TIMEOUT_SECS"),
+    ("health_history_size", "HEALTH_HISTORY_SIZE"),
+    ("health_flap_window_secs", "HEALTH_FLAP_WINDOW_SECS"),
+    ("health_flap_threshold", "HEALTH_FLAP_THRESHOLD"),
+    ("shutdown_drain_secs", "SHUTDOWN_DRAIN_SECS"),
+    ("notify_webhooks", "NOTIFY_WEBHOOKS"),
+    ("notify_services", "NOTIFY_SERVICES"),
+    ("notify_min_interval_secs", "NOTIFY_MIN_INTERVAL_SECS"),
+    ("notify_max_retries", "NOTIFY_MAX_RETRIES"),
+    ("notify_retry_backoff_ms", "NOTIFY_RETRY_BACKOFF_MS"),
+    ("otlp_endpoint", "OTEL_EXPORTER_OTLP_ENDPOINT"),
+    ("otel_service_name", "OTEL_SERVICE_NAME"),
+];
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
-    pub listen_addr: String,
+    /// Listener specs, see `ListenerSpec`.
+    pub listen_addr: Vec<String>,
+    pub admin_listen_addr: Option<String>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub tls_client_ca: Option<String>,
+    pub tls_min_version: String,
+    /// HS256 signing key; with `auth_jwks`, either kind of token is accepted.
+    pub auth_jwt_secret: Option<Secret<String>>,
+    /// JWKS file path or http(s) URL with RS256/ES256 keys. A URL may
+    /// carry a token.
+    pub auth_jwks: Option<Secret<String>>,
+    pub auth_jwks_refresh_secs: u64,
+    pub auth_issuer: Option<String>,
+    pub auth_audience: Option<String>,
+    /// Services (`package.Service`) and methods (`package.Service/Method`)
+    /// callable without a token.
+    pub auth_public: Vec<String>,
+    /// Exceptions to `auth_public`; a method entry wins over its service.
+    pub auth_authenticated: Vec<String>,
+    /// Where the authorization policy comes from: `off`, `config` or
+    /// `database`.
+    pub authz_source: String,
+    /// `pattern=role+role` entries, see `authz::Rule`.
+    pub authz_rules: Vec<String>,
+    /// Token claims holding the caller's roles.
+    pub authz_role_claims: Vec<String>,
+    pub authz_refresh_secs: u64,
     pub log_level: String,
     pub log_style: String,
+    pub log_file: Option<String>,
+    pub log_file_rotation: String,
+    pub log_file_max_size_mb: u64,
+    pub log_file_max_files: usize,
+    pub log_file_compress: bool,
+    pub log_redact_fields: Vec<String>,
     pub cors_origins: Vec<String>,
-    pub database_url: String,
+    pub database_url: Secret<String>,
     pub db_max_connections: u32,
     pub request_timeout_secs: u64,
+    pub health_history_size: usize,
+    pub health_flap_window_secs: u64,
+    pub health_flap_threshold: usize,
+    pub shutdown_drain_secs: u64,
+    /// Webhook URLs often carry a token in the path or query.
+    pub notify_webhooks: Vec<Secret<String>>,
+    pub notify_services: Vec<String>,
+    pub notify_min_interval_secs: u64,
+    pub notify_max_retries: u32,
+    pub notify_retry_backoff_ms: u64,
+    /// May carry credentials.
+    pub otlp_endpoint: Option<Secret<String>>,
+    pub otel_service_name: String,
 }
 
+#[allow(dead_code)]
 impl Config {
-    pub fn from_env() -> Self {
-        Self {
-            listen_addr: env_or("LISTEN_ADDR", "0.0.0.0:50051"),
-            log_level: env_or("LOG_LEVEL", "info"),
-            log_style: env_or("LOG_STYLE", "auto"),
-            cors_origins: env_or("CORS_ORIGINS", "*")
-                .split(',')
-                .map(|s| s.trim().to_owned())
+    /// Defaults, overridden by the config file (`--config` or
+    /// `CONFIG_FILE`), then the environment, then `*_FILE` secrets, then
+    /// `args`. Reports every problem found, not just the first.
+    pub fn load(args: &Args) -> Result<Self, ConfigErrors> {
+        Self::load_with(args, &secrets::defaults())
+    }
+
+    /// Like `load`, with `providers` in place of the default secret
+    /// providers. Later providers win.
+    pub fn load_with(
+        args: &Args,
+        providers: &[Arc<dyn SecretProvider>],
+    ) -> Result<Self, ConfigErrors> {
+        let mut settings = Settings::default();
+        let mut errors = Vec::new();
+        let file = args.config_file.clone().or_else(|| {
+            std::env::var_os(CONFIG_FILE_ENV)
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+        });
+        if let Some(path) = file
+            && let Err(ConfigErrors(file_errors)) = settings.merge_file(&path)
+        {
+            errors.extend(file_errors);
+        }
+        settings.merge_env();
+        if let Err(ConfigErrors(secret_errors)) = settings.merge_secrets(providers) {
+            errors.extend(secret_errors);
+        }
+        for (key, value) in &args.overrides {
+            if let Err(err) = settings.set(key, value, Source::Args) {
+                errors.push(err);
+            }
+        }
+        match Self::from_settings(&settings) {
+            Ok(config) if errors.is_empty() => Ok(config),
+            Ok(_) => Err(ConfigErrors(errors)),
+            Err(ConfigErrors(more)) => {
+                errors.extend(more);
+                Err(ConfigErrors(errors))
+            }
+        }
+    }
+
+    /// Defaults overridden by the environment only.
+    pub fn from_env() -> Result<Self, ConfigErrors> {
+        let mut settings = Settings::default();
+        settings.merge_env();
+        Self::from_settings(&settings)
+    }
+
+    pub fn from_settings(s: &Settings) -> Result<Self, ConfigErrors> {
+        let mut c = Checker::new(s);
+        let config = Self {
+            listen_addr: s.list("listen_addr", "0.0.0.0:50051"),
+            admin_listen_addr: s.optional("admin_listen_addr"),
+            tls_cert: s.optional("tls_cert"),
+            tls_key: s.optional("tls_key"),
+            tls_client_ca: s.optional("tls_client_ca"),
+            tls_min_version: s.string("tls_min_version", "1.2"),
+            auth_jwt_secret: s.optional("auth_jwt_secret").map(Secret::new),
+            auth_jwks: s.optional("auth_jwks").map(Secret::new),
+            auth_jwks_refresh_secs: c.parse("auth_jwks_refresh_secs", 300),
+            auth_issuer: s.optional("auth_issuer"),
+            auth_audience: s.optional("auth_audience"),
+            auth_public: s.list("auth_public", DEFAULT_AUTH_PUBLIC),
+            auth_authenticated: s.list("auth_authenticated", ""),
+            authz_source: s.keyword("authz_source", "off"),
+            authz_rules: s.list("authz_rules", DEFAULT_AUTHZ_RULES),
+            authz_role_claims: s.list("authz_role_claims", "roles"),
+            authz_refresh_secs: c.parse("authz_refresh_secs", 30),
+            log_level: s.string("log_level", "info"),
+            log_style: s.keyword("log_style", "auto"),
+            log_file: s.optional("log_file"),
+            log_file_rotation: s.keyword("log_file_rotation", "daily"),
+            log_file_max_size_mb: c.parse("log_file_max_size_mb", 100),
+            log_file_max_files: c.parse("log_file_max_files", 7),
+            log_file_compress: c.parse("log_file_compress", false),
+            log_redact_fields: s.list("log_redact_fields", redact::DEFAULT_FIELDS),
+            cors_origins: s.list("cors_origins", "*"),
+            database_url: c.required("database_url").into(),
+            db_max_connections: c.parse("db_max_connections", 20),
+            request_timeout_secs: c.parse("request_timeout_secs", 30),
+            health_history_size: c.parse("health_history_size", 100),
+            health_flap_window_secs: c.parse("health_flap_window_secs", 300),
+            health_flap_threshold: c.parse("health_flap_threshold", 4),
+            shutdown_drain_secs: c.parse("shutdown_drain_secs", 0),
+            notify_webhooks: s
+                .list("notify_webhooks", "")
+                .into_iter()
+                .map(Secret::new)
                 .collect(),
-            database_url: std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
-            db_max_connections: env_or("DB_MAX_CONNECTIONS", "20")
-                .parse()
-                .expect("DB_MAX_CONNECTIONS must be a valid integer"),
-            request_timeout_secs: env_or("REQUEST_TIMEOUT_SECS", "30")
-                .parse()
-                .expect("REQUEST_TIMEOUT_SECS must be a valid integer"),
+            notify_services: s.list("notify_services", ""),
+            notify_min_interval_secs: c.parse("notify_min_interval_secs", 60),
+            notify_max_retries: c.parse("notify_max_retries", 3),
+            notify_retry_backoff_ms: c.parse("notify_retry_backoff_ms", 500),
+            otlp_endpoint: s.optional("otlp_endpoint").map(Secret::new),
+            otel_service_name: s.string("otel_service_name", "midnight-server"),
+        };
+        config.validate(&mut c);
+        c.finish(config)
+    }
+
+    /// Checks what parsing alone doesn't: addresses, origins, URLs, names
+    /// from a fixed set, and that limits are within sane ranges.
+    fn validate(&self, c: &mut Checker) {
+        if self.listen_addr.is_empty() {
+            c.invalid(
+                "listen_addr",
+                "at least one listener is required".to_owned(),
+            );
+        }
+        for spec in &self.listen_addr {
+            if let Err(e) = spec.parse::<ListenerSpec>() {
+                c.invalid("listen_addr", format!("{spec:?}: {e}"));
+            }
+        }
+        if let Some(addr) = &self.admin_listen_addr {
+            c.socket_addr("admin_listen_addr", addr);
+        }
+
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(_), None) => c.invalid("tls_cert", "needs tls_key as well".to_owned()),
+            (None, Some(_)) => c.invalid("tls_key", "needs tls_cert as well".to_owned()),
+            _ => {}
+        }
+        if self.tls_client_ca.is_some() && self.tls_cert.is_none() {
+            c.invalid(
+                "tls_client_ca",
+                "client certificates need TLS: set tls_cert and tls_key".to_owned(),
+            );
+        }
+        c.one_of("tls_min_version", &self.tls_min_version, &["1.2", "1.3"]);
+
+        if let Some(secret) = &self.auth_jwt_secret
+            && secret.expose().len() < 32
+        {
+            c.invalid(
+                "auth_jwt_secret",
+                "must be at least 32 bytes long".to_owned(),
+            );
+        }
+        if let Some(jwks) = &self.auth_jwks
+            && jwks.expose().contains("://")
+        {
+            c.http_url("auth_jwks", jwks.expose());
+        }
+        c.range(
+            "auth_jwks_refresh_secs",
+            self.auth_jwks_refresh_secs,
+            1..=86_400,
+        );
+        for entry in &self.auth_public {
+            c.rpc_name("auth_public", entry);
+        }
+        for entry in &self.auth_authenticated {
+            c.rpc_name("auth_authenticated", entry);
+            if self.auth_public.contains(entry) {
+                c.invalid(
+                    "auth_authenticated",
+                    format!("{entry:?} is in auth_public as well"),
+                );
+            }
+        }
+
+        c.one_of(
+            "authz_source",
+            &self.authz_source,
+            &["off", "config", "database"],
+        );
+        for rule in &self.authz_rules {
+            if let Err(e) = rule.parse::<Rule>() {
+                c.invalid("authz_rules", format!("{rule:?}: {e}"));
+            }
+        }
+        c.range("authz_refresh_secs", self.authz_refresh_secs, 1..=86_400);
+
+        if let Err(e) = EnvFilter::try_new(&self.log_level) {
+            c.invalid(
+                "log_level",
+                format!("invalid filter {:?}: {e}", self.log_level),
+            );
+        }
+        c.one_of(
+            "log_style",
+            &self.log_style,
+            &["auto", "plain", "compact", "pretty", "json"],
+        );
+        c.one_of(
+            "log_file_rotation",
+            &self.log_file_rotation,
+            &["daily", "size", "never"],
+        );
+        c.range(
+            "log_file_max_size_mb",
+            self.log_file_max_size_mb,
+            1..=1024 * 1024,
+        );
+        c.range("log_file_max_files", self.log_file_max_files, 0..=10_000);
+
+        for origin in &self.cors_origins {
+            if origin != "*" && HeaderValue::from_str(origin).is_err() {
+                c.invalid(
+                    "cors_origins",
+                    format!("{origin:?} is not a valid header value"),
+                );
+            }
+        }
+
+        match url::Url::parse(self.database_url.expose()) {
+            Ok(url) if matches!(url.scheme(), "postgres" | "postgresql") => {
+                if let Err(e) = PgConnectOptions::from_str(url.as_str()) {
+                    c.invalid("database_url", format!("invalid connection options: {e}"));
+                }
+            }
+            Ok(url) => c.invalid(
+                "database_url",
+                format!("unsupported scheme {:?}, expected postgres", url.scheme()),
+            ),
+            // The URL may hold a password, so only the parse error is shown.
+            Err(e) if !self.database_url.expose().is_empty() => {
+                c.invalid("database_url", format!("invalid URL: {e}"))
+            }
+            Err(_) => {}
+        }
+        c.range("db_max_connections", self.db_max_connections, 1..=1000);
+        c.range("request_timeout_secs", self.request_timeout_secs, 1..=3600);
+
+        c.range("health_history_size", self.health_history_size, 1..=100_000);
+        c.range(
+            "health_flap_window_secs",
+            self.health_flap_window_secs,
+            1..=86_400,
+        );
+        c.range(
+            "health_flap_threshold",
+            self.health_flap_threshold,
+            0..=1000,
+        );
+        c.range("shutdown_drain_secs", self.shutdown_drain_secs, 0..=3600);
+
+        for webhook in &self.notify_webhooks {
+            c.http_url("notify_webhooks", webhook.expose());
         }
+        c.range(
+            "notify_min_interval_secs",
+            self.notify_min_interval_secs,
+            0..=86_400,
+        );
+        c.range("notify_max_retries", self.notify_max_retries, 0..=100);
+        c.range(
+            "notify_retry_backoff_ms",
+            self.notify_retry_backoff_ms,
+            0..=600_000,
+        );
+
+        if let Some(endpoint) = &self.otlp_endpoint {
+            c.http_url("otlp_endpoint", endpoint.expose());
+        }
+    }
+
+    /// The parsed `listen_addr` entries. Only valid once validated.
+    pub fn listeners(&self) -> Vec<ListenerSpec> {
+        self.listen_addr
+            .iter()
+            .filter_map(|spec| spec.parse().ok())
+            .collect()
     }
 
     pub fn cors_is_permissive(&self) -> bool {
         self.cors_origins.iter().any(|o| o == "*")
     }
+
+    pub fn cors_allows(&self, origin: &HeaderValue) -> bool {
+        self.cors_is_permissive()
+            || self
+                .cors_origins
+                .iter()
+                .any(|o| o.as_bytes() == origin.as_bytes())
+    }
+
+    /// The config in config file form. Settings that can hold credentials
+    /// are `Secret`s, which serialize masked.
+    pub fn to_redacted_toml(&self) -> String {
+        toml::to_string(self).expect("config serializes to TOML")
+    }
+
+    /// Settings whose values differ in `new`, in `KEYS` order. Values are
+    /// shown as in `to_redacted_toml`; an unset one is empty.
+    pub fn diff(&self, new: &Config) -> Vec<ConfigChange> {
+        let old_values = self.to_table();
+        let new_values = new.to_table();
+        let show = |value: Option<&toml::Value>| value.map(|v| v.to_string()).unwrap_or_default();
+        KEYS.iter()
+            .filter(|(key, _)| match *key {
+                // Secrets serialize redacted, so compare them directly.
+                "database_url" => self.database_url != new.database_url,
+                "auth_jwt_secret" => self.auth_jwt_secret != new.auth_jwt_secret,
+                "auth_jwks" => self.auth_jwks != new.auth_jwks,
+                "notify_webhooks" => self.notify_webhooks != new.notify_webhooks,
+                "otlp_endpoint" => self.otlp_endpoint != new.otlp_endpoint,
+                key => old_values.get(key) != new_values.get(key),
+            })
+            .map(|(key, _)| ConfigChange {
+                key,
+                old: show(old_values.get(*key)),
+                new: show(new_values.get(*key)),
+            })
+            .collect()
+    }
+
+    fn to_table(&self) -> toml::Table {
+        toml::Table::try_from(self).expect("config serializes to TOML")
+    }
+}
+
+#[cfg(test)]
+impl Config {
+    /// A valid config for unit tests: authentication and authorization
+    /// off, plain logs, and nothing public.
+    pub fn for_tests() -> Self {
+        Self {
+            listen_addr: vec!["127.0.0.1:50051".to_owned()],
+            admin_listen_addr: None,
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            tls_min_version: "1.2".to_owned(),
+            auth_jwt_secret: None,
+            auth_jwks: None,
+            auth_jwks_refresh_secs: 300,
+            auth_issuer: None,
+            auth_audience: None,
+            auth_public: vec![],
+            auth_authenticated: vec![],
+            authz_source: "off".to_owned(),
+            authz_rules: vec![],
+            authz_role_claims: vec!["roles".to_owned()],
+            authz_refresh_secs: 30,
+            log_level: "info".to_owned(),
+            log_style: "plain".to_owned(),
+            log_file: None,
+            log_file_rotation: "daily".to_owned(),
+            log_file_max_size_mb: 100,
+            log_file_max_files: 7,
+            log_file_compress: false,
+            log_redact_fields: vec!["password".to_owned()],
+            cors_origins: vec!["*".to_owned()],
+            database_url: "postgres://localhost/test".to_owned().into(),
+            db_max_connections: 5,
+            request_timeout_secs: 30,
+            health_history_size: 100,
+            health_flap_window_secs: 300,
+            health_flap_threshold: 4,
+            shutdown_drain_secs: 0,
+            notify_webhooks: vec![],
+            notify_services: vec![],
+            notify_min_interval_secs: 60,
+            notify_max_retries: 3,
+            notify_retry_backoff_ms: 500,
+            otlp_endpoint: None,
+            otel_service_name: "midnight-server".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub key: &'static str,
+    pub old: String,
+    pub new: String,
 }
 
-fn env_or(key: &str, default: &str) -> String {
-    std::env::var(key).unwrap_or_else(|_| default.to_owned())
+/// Where a setting's value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The built-in default.
+    Default,
+    File(PathBuf),
+    Env,
+    /// A secret provider, described by where it read the value.
+    Secret(String),
+    Args,
+}
+
+impl Source {
+    /// `key` as it is spelled in this source.
+    fn describe(&self, key: &str) -> String {
+        match self {
+            Source::Default => key.to_owned(),
+            Source::File(path) => format!("{key} in {}", path.display()),
+            Source::Env => format!("environment variable {}", env_name(key)),
+            Source::Secret(from) => from.clone(),
+            Source::Args => format!("--{}", flag_name(key)),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => f.write_str("default"),
+            Source::File(path) => write!(f, "config file {}", path.display()),
+            Source::Env => f.write_str("environment"),
+            Source::Secret(from) => f.write_str(from),
+            Source::Args => f.write_str("command line"),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("{}: {message}", origin.describe(key))]
+    Invalid {
+        key: &'static str,
+        origin: Source,
+        message: String,
+    },
+
+    #[error(
+        "{key} is required: set it in the config file, {} or --{}",
+        env_name(key),
+        flag_name(key)
+    )]
+    Missing { key: &'static str },
+
+    #[error("unknown setting {}", origin.describe(key))]
+    Unknown { key: String, origin: Source },
+
+    #[error("config file {}: {message}", path.display())]
+    File { path: PathBuf, message: String },
+
+    #[error("{0}")]
+    Args(String),
+}
+
+/// Every problem found while loading config, in the order found.
+#[derive(Debug)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid configuration:")?;
+        for err in &self.0 {
+            write!(f, "\n  {err}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Raw setting values by name. Later values replace earlier ones, so
+/// sources are merged lowest precedence first. Not `Debug`: values may be
+/// secrets.
+#[derive(Clone, Default)]
+pub struct Settings {
+    values: BTreeMap<&'static str, (String, Source)>,
+}
+
+#[allow(dead_code)]
+impl Settings {
+    pub fn set(&mut self, key: &str, value: &str, origin: Source) -> Result<(), ConfigError> {
+        let Some((key, _)) = KEYS.iter().find(|(k, _)| *k == key) else {
+            return Err(ConfigError::Unknown {
+                key: key.to_owned(),
+                origin,
+            });
+        };
+        self.values.insert(key, (value.to_owned(), origin));
+        Ok(())
+    }
+
+    pub fn merge_env(&mut self) {
+        for (key, env) in KEYS {
+            if let Ok(value) = std::env::var(env) {
+                self.values.insert(key, (value, Source::Env));
+            }
+        }
+    }
+
+    /// Asks each provider for every setting.
+    pub fn merge_secrets(
+        &mut self,
+        providers: &[Arc<dyn SecretProvider>],
+    ) -> Result<(), ConfigErrors> {
+        let mut errors = Vec::new();
+        for provider in providers {
+            for (key, _) in KEYS {
+                match provider.fetch(key) {
+                    Ok(Some(value)) => {
+                        let origin = Source::Secret(provider.describe(key));
+                        self.values.insert(key, (value.expose().clone(), origin));
+                    }
+                    Ok(None) => {}
+                    Err(message) => errors.push(ConfigError::Invalid {
+                        key,
+                        origin: Source::Secret(provider.describe(key)),
+                        message,
+                    }),
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(errors))
+        }
+    }
+
+    /// Reads a TOML file. Tables nest setting names, so `[log_file]` with
+    /// `max_files = 3` sets `log_file_max_files`; arrays become lists.
+    pub fn merge_file(&mut self, path: &Path) -> Result<(), ConfigErrors> {
+        let file_error = |message: String| {
+            ConfigErrors(vec![ConfigError::File {
+                path: path.to_owned(),
+                message,
+            }])
+        };
+        let text = std::fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e: toml::de::Error| file_error(e.message().to_owned()))?;
+        let mut values = Vec::new();
+        flatten("", table, &mut values).map_err(file_error)?;
+        let errors: Vec<ConfigError> = values
+            .into_iter()
+            .filter_map(|(key, value)| self.set(&key, &value, Source::File(path.to_owned())).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(errors))
+        }
+    }
+
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.values.get(key).map(|(_, origin)| origin)
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|(value, _)| value.as_str())
+    }
+
+    fn string(&self, key: &str, default: &str) -> String {
+        self.get(key).unwrap_or(default).to_owned()
+    }
+
+    /// One of a fixed set of words, lowercased so that code reading the
+    /// config can compare it exactly.
+    fn keyword(&self, key: &str, default: &str) -> String {
+        self.get(key).unwrap_or(default).trim().to_lowercase()
+    }
+
+    /// Unset or blank gives `None`.
+    fn optional(&self, key: &str) -> Option<String> {
+        self.get(key)
+            .filter(|s| !s.trim().is_empty())
+            .map(str::to_owned)
+    }
+
+    /// Comma-separated; empty entries are skipped.
+    fn list(&self, key: &str, default: &str) -> Vec<String> {
+        self.get(key)
+            .unwrap_or(default)
+            .split(',')
+            .map(|s| s.trim().to_owned())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+/// Parses and checks settings, collecting every problem instead of
+/// stopping at the first. Errors name the source the bad value came from.
+struct Checker<'a> {
+    settings: &'a Settings,
+    errors: Vec<ConfigError>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(settings: &'a Settings) -> Self {
+        Self {
+            settings,
+            errors: Vec::new(),
+        }
+    }
+
+    fn invalid(&mut self, key: &'static str, message: String) {
+        let origin = self
+            .settings
+            .source(key)
+            .cloned()
+            .unwrap_or(Source::Default);
+        self.errors.push(ConfigError::Invalid {
+            key,
+            origin,
+            message,
+        });
+    }
+
+    /// The parsed value, or `default` after recording why it didn't parse.
+    fn parse<T>(&mut self, key: &'static str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let Some(value) = self.settings.get(key) else {
+            return default;
+        };
+        match value.trim().parse() {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.invalid(key, format!("invalid value {value:?}: {e}"));
+                default
+            }
+        }
+    }
+
+    fn required(&mut self, key: &'static str) -> String {
+        self.settings.optional(key).unwrap_or_else(|| {
+            self.errors.push(ConfigError::Missing { key });
+            String::new()
+        })
+    }
+
+    fn range<T>(&mut self, key: &'static str, value: T, range: RangeInclusive<T>)
+    where
+        T: PartialOrd + fmt::Display,
+    {
+        if !range.contains(&value) {
+            self.invalid(
+                key,
+                format!(
+                    "must be between {} and {}, got {value}",
+                    range.start(),
+                    range.end()
+                ),
+            );
+        }
+    }
+
+    fn one_of(&mut self, key: &'static str, value: &str, allowed: &[&str]) {
+        if !allowed.contains(&value) {
+            self.invalid(
+                key,
+                format!("must be one of {}, got {value:?}", allowed.join(", ")),
+            );
+        }
+    }
+
+    fn socket_addr(&mut self, key: &'static str, value: &str) {
+        if let Err(e) = value.parse::<SocketAddr>() {
+            self.invalid(key, format!("invalid address {value:?}: {e}"));
+        }
+    }
+
+    fn rpc_name(&mut self, key: &'static str, value: &str) {
+        if !is_rpc_name(value) {
+            self.invalid(
+                key,
+                format!("{value:?} is not a package.Service or package.Service/Method name"),
+            );
+        }
+    }
+
+    fn http_url(&mut self, key: &'static str, value: &str) {
+        match url::Url::parse(value) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => self.invalid(
+                key,
+                format!(
+                    "unsupported scheme {:?}, expected http or https",
+                    url.scheme()
+                ),
+            ),
+            // Not echoed: the URL may carry a token.
+            Err(e) => self.invalid(key, format!("invalid URL: {e}")),
+        }
+    }
+
+    fn finish(self, config: Config) -> Result<Config, ConfigErrors> {
+        if self.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigErrors(self.errors))
+        }
+    }
+}
+
+/// Whether `value` is `package.Service` or `package.Service/Method`.
+pub fn is_rpc_name(value: &str) -> bool {
+    let (service, method) = match value.split_once('/') {
+        Some((service, method)) => (service, Some(method)),
+        None => (value, None),
+    };
+    let valid = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+    };
+    valid(service) && method.is_none_or(|m| valid(m) && !m.contains('.'))
+}
+
+/// Command-line options. Any setting can be given as a flag, e.g.
+/// `--log-level debug` or `--log-level=debug`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Args {
+    pub config_file: Option<PathBuf>,
+    pub print_config: bool,
+    pub help: bool,
+    /// Setting overrides by setting name, in order.
+    pub overrides: Vec<(String, String)>,
+}
+
+#[allow(dead_code)]
+impl Args {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, ConfigError> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                if arg == "-h" {
+                    parsed.help = true;
+                    continue;
+                }
+                return Err(ConfigError::Args(format!("unexpected argument {arg:?}")));
+            };
+            let (name, inline) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (flag, None),
+            };
+            match name {
+                "help" => parsed.help = true,
+                "print-config" => parsed.print_config = true,
+                _ => {
+                    let Some(value) = inline.or_else(|| args.next()) else {
+                        return Err(ConfigError::Args(format!("--{name} needs a value")));
+                    };
+                    if name == "config" {
+                        parsed.config_file = Some(PathBuf::from(value));
+                        continue;
+                    }
+                    let key = name.replace('-', "_");
+                    if !KEYS.iter().any(|(k, _)| *k == key) {
+                        return Err(ConfigError::Unknown {
+                            key,
+                            origin: Source::Args,
+                        });
+                    }
+                    parsed.overrides.push((key, value));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn usage() -> String {
+        let mut usage = format!(
+            "Usage: {} [--config <file>] [--print-config] [--<setting> <value>]...\n\n\
+             Settings come from the config file ({CONFIG_FILE_ENV}), then the environment,\n\
+             then flags; later sources win. Each variable X can instead be given as\n\
+             X_FILE, naming a file to read the value from.\n\n",
+            env!("CARGO_PKG_NAME"),
+        );
+        for (key, env) in KEYS {
+            usage.push_str(&format!("  --{:<28} {env}\n", flag_name(key)));
+        }
+        usage
+    }
+}
+
+pub fn env_name(key: &str) -> String {
+    KEYS.iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, env)| (*env).to_owned())
+        .unwrap_or_else(|| key.to_uppercase())
+}
+
+fn flag_name(key: &str) -> String {
+    key.replace('_', "-")
+}
+
+fn flatten(
+    prefix: &str,
+    table: toml::Table,
+    out: &mut Vec<(String, String)>,
+) -> Result<(), String> {
+    for (name, value) in table {
+        let key = format!("{prefix}{}", name.to_lowercase().replace('-', "_"));
+        match value {
+            toml::Value::Table(table) => flatten(&format!("{key}_"), table, out)?,
+            toml::Value::Array(items) => {
+                let items = items
+                    .into_iter()
+                    .map(|item| scalar(&key, item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                out.push((key, items.join(",")));
+            }
+            value => {
+                let value = scalar(&key, value)?;
+                out.push((key, value));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scalar(key: &str, value: toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(s) => Ok(s),
+        toml::Value::Integer(n) => Ok(n.to_string()),
+        toml::Value::Float(n) => Ok(n.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        toml::Value::Datetime(d) => Ok(d.to_string()),
+        _ => Err(format!(
+            "{key} must be a string, number, boolean or list of them"
+        )),
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,7 @@
+use std::str::FromStr;
+
 use anyhow::{Context, Result};
-use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions};
 
 pub async fn create_pool(database_url: &str, max_connections: u32) -> Result<PgPool> {
     ensure_database_exists(database_url).await?;
@@ -13,6 +15,15 @@ pub async fn create_pool(database_url: &str, max_connections: u32) -> Result<PgP
     Ok(pool)
 }
 
+/// Makes new pool connections use `database_url`, e.g. after a password
+/// rotation. Open connections are kept.
+pub fn set_database_url(pool: &PgPool, database_url: &str) -> Result<()> {
+    let options = PgConnectOptions::from_str(database_url).context("invalid DATABASE_URL")?;
+    pool.set_connect_options(options);
+    tracing::info!("database connection options updated");
+    Ok(())
+}
+
 pub async fn run_migrations(pool: &PgPool) -> Result<()> {
     sqlx::migrate!().run(pool).await?;
     tracing::info!("database migrations applied");
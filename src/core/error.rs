@@ -1,4 +1,7 @@
 use tonic::Status;
+use tonic_types::{ErrorDetails, StatusExt};
+
+use super::request_id::RequestId;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
@@ -20,6 +23,9 @@ pub enum AppError {
     #[error("already exists: {0}")]
     AlreadyExists(String),
 
+    #[error("failed precondition: {0}")]
+    FailedPrecondition(String),
+
     #[error("database: {0}")]
     Sqlx(#[from] sqlx::Error),
 
@@ -29,30 +35,60 @@ pub enum AppError {
 
 impl From<AppError> for Status {
     fn from(err: AppError) -> Self {
-        match &err {
+        let request_id = RequestId::current();
+        err.into_status(request_id.as_ref())
+    }
+}
+
+impl AppError {
+    /// Converts to a gRPC status whose details carry the request id as a
+    /// `google.rpc.RequestInfo`, when there is one.
+    pub fn into_status(self, request_id: Option<&RequestId>) -> Status {
+        let status = self.to_status();
+        match request_id {
+            Some(id) => Status::with_error_details(
+                status.code(),
+                status.message(),
+                ErrorDetails::with_request_info(id.as_str(), ""),
+            ),
+            None => status,
+        }
+    }
+
+    fn to_status(&self) -> Status {
+        match self {
             AppError::NotFound(_) => {
-                tracing::warn!(%err);
-                Status::not_found(err.to_string())
+                tracing::warn!(%self);
+                Status::not_found(self.to_string())
             }
             AppError::InvalidArgument(_) => {
-                tracing::warn!(%err);
-                Status::invalid_argument(err.to_string())
+                tracing::warn!(%self);
+                Status::invalid_argument(self.to_string())
             }
             AppError::Unauthenticated(_) => {
-                tracing::warn!(%err);
-                Status::unauthenticated(err.to_string())
+                tracing::warn!(%self);
+                Status::unauthenticated(self.to_string())
             }
             AppError::PermissionDenied(_) => {
-                tracing::warn!(%err);
-                Status::permission_denied(err.to_string())
+                tracing::warn!(%self);
+                Status::permission_denied(self.to_string())
             }
             AppError::AlreadyExists(_) => {
-                tracing::warn!(%err);
-                Status::already_exists(err.to_string())
+                tracing::warn!(%self);
+                Status::already_exists(self.to_string())
+            }
+            AppError::FailedPrecondition(_) => {
+                tracing::warn!(%self);
+                Status::failed_precondition(self.to_string())
+            }
+            // Database errors can quote query values; only the log gets them.
+            AppError::Sqlx(_) => {
+                tracing::error!(%self);
+                Status::internal("internal: database error")
             }
-            AppError::Internal(_) | AppError::Sqlx(_) | AppError::Anyhow(_) => {
-                tracing::error!(%err);
-                Status::internal(err.to_string())
+            AppError::Internal(_) | AppError::Anyhow(_) => {
+                tracing::error!(%self);
+                Status::internal(self.to_string())
             }
         }
     }
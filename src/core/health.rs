@@ -1,22 +1,36 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant, SystemTime};
 
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, broadcast};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+use super::error::{AppError, AppResult};
+
 pub type HealthCheckFn =
     Box<dyn Fn() -> Pin<Box<dyn Future<Output = Result<(), String>> + Send>> + Send + Sync>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Ordered from healthiest to least healthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ServiceStatus {
     Serving,
+    Degraded,
     NotServing,
 }
 
+impl ServiceStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Serving => "serving",
+            Self::Degraded => "degraded",
+            Self::NotServing => "not_serving",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ServiceHealth {
     pub id: Uuid,
@@ -26,6 +40,10 @@ pub struct ServiceHealth {
     pub registered_at: Instant,
     pub version: Option<String>,
     pub message: Option<String>,
+    pub flapping: bool,
+    pub timeout: Duration,
+    pub next_probe_at: SystemTime,
+    pub manual_override: Option<HealthOverride>,
 }
 
 impl ServiceHealth {
@@ -34,22 +52,447 @@ impl ServiceHealth {
     }
 }
 
-const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Status forced by an operator regardless of probe results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthOverride {
+    pub status: ServiceStatus,
+    pub reason: String,
+    pub set_at: SystemTime,
+    /// `None` keeps the override until it is cleared.
+    pub expires_at: Option<SystemTime>,
+}
+
+impl HealthOverride {
+    pub fn new(status: ServiceStatus, reason: impl Into<String>, ttl: Option<Duration>) -> Self {
+        let set_at = SystemTime::now();
+        Self {
+            status,
+            reason: reason.into(),
+            set_at,
+            expires_at: ttl.map(|ttl| set_at + ttl),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        format!("manual override: {}", self.reason)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Success,
+    Failure,
+    Timeout,
+}
+
+impl ProbeOutcome {
+    pub fn is_success(self) -> bool {
+        self == Self::Success
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeRecord {
+    pub timestamp: SystemTime,
+    pub latency: Duration,
+    pub outcome: ProbeOutcome,
+    pub error: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum HealthEvent {
+    Registered(ServiceHealth),
+    Changed(ServiceHealth),
+    Deregistered(ServiceHealth),
+    /// The server-wide override was set or cleared.
+    ServerOverride(Option<HealthOverride>),
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthSettings {
+    /// Number of probe results kept per service.
+    pub history_capacity: usize,
+    /// Window in which outcome transitions are counted for flap detection.
+    pub flap_window: Duration,
+    /// Transitions within `flap_window` at which a service is flapping.
+    pub flap_threshold: usize,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            history_capacity: 100,
+            flap_window: Duration::from_secs(300),
+            flap_threshold: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    /// Factor applied to the probe interval for each consecutive failure
+    /// after the first.
+    pub multiplier: f64,
+    /// Upper bound on the delay between probes.
+    pub max_interval: Duration,
+    /// Random spread applied to each delay, as a fraction (0.0 to 1.0).
+    pub jitter: f64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            multiplier: 2.0,
+            max_interval: Duration::from_secs(300),
+            jitter: 0.1,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyKind {
+    /// A failing critical dependency takes its status to the dependent.
+    Critical,
+    /// A failing optional dependency only degrades the dependent.
+    Optional,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub kind: DependencyKind,
+}
+
+#[allow(dead_code)]
+impl Dependency {
+    pub fn critical(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: DependencyKind::Critical,
+        }
+    }
+
+    pub fn optional(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: DependencyKind::Optional,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DependencyEdge {
+    pub service_id: Uuid,
+    pub service: String,
+    pub dependency: String,
+    pub kind: DependencyKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatedHealth {
+    pub status: ServiceStatus,
+    /// Name of the service whose own failure explains `status`. This may
+    /// be a dependency that is not registered at all.
+    pub root_cause: Option<String>,
+}
+
+/// Point-in-time view of every service, its dependencies and the status
+/// each one has once failures are propagated up the graph.
+#[derive(Debug, Clone)]
+pub struct HealthGraph {
+    pub services: Vec<ServiceHealth>,
+    pub edges: Vec<DependencyEdge>,
+    pub aggregated: HashMap<Uuid, AggregatedHealth>,
+    /// Combined status of the services nothing else depends on, or the
+    /// server-wide override when one is set.
+    pub overall: AggregatedHealth,
+    pub server_override: Option<HealthOverride>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegisterOptions {
+    /// Consecutive failed probes before the service is marked not serving.
+    /// Failures below this threshold report the service as degraded.
+    pub failure_threshold: u32,
+    /// Consecutive successful probes before a not serving service is
+    /// marked serving again.
+    pub success_threshold: u32,
+    /// Time a single probe may take before it counts as timed out.
+    pub timeout: Duration,
+    /// Delay before the first probe. When zero, `register` runs the first
+    /// probe before returning; otherwise the service reports not serving
+    /// until the first probe completes.
+    pub initial_delay: Duration,
+    /// Backoff applied to the probe interval while the service is failing.
+    pub backoff: Option<Backoff>,
+    /// Services, by name, this service needs. They do not have to be
+    /// registered yet; a missing dependency counts as not serving.
+    pub dependencies: Vec<Dependency>,
+    /// Id to register under, e.g. to keep a persisted probe's id across
+    /// restarts. A random id is used when unset.
+    pub id: Option<Uuid>,
+}
+
+impl Default for RegisterOptions {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 1,
+            success_threshold: 1,
+            timeout: DEFAULT_PROBE_TIMEOUT,
+            initial_delay: Duration::ZERO,
+            backoff: None,
+            dependencies: Vec::new(),
+            id: None,
+        }
+    }
+}
+
+const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const EVENT_CAPACITY: usize = 256;
+
+struct ProbeState {
+    options: RegisterOptions,
+    results: VecDeque<ProbeRecord>,
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+    /// Status and message from probes alone, kept while an override hides
+    /// them so they can be restored when it ends.
+    status: ServiceStatus,
+    message: Option<String>,
+}
+
+impl ProbeState {
+    fn new(options: RegisterOptions) -> Self {
+        Self {
+            options,
+            results: VecDeque::new(),
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            status: ServiceStatus::Serving,
+            message: None,
+        }
+    }
+
+    /// Status and message to report, taking an override into account.
+    fn effective(&self, manual: Option<&HealthOverride>) -> (ServiceStatus, Option<String>) {
+        match manual {
+            Some(manual) => (manual.status, Some(manual.message())),
+            None => (self.status, self.message.clone()),
+        }
+    }
+
+    fn push(&mut self, record: ProbeRecord, capacity: usize) {
+        if self.results.len() >= capacity.max(1) {
+            self.results.pop_front();
+        }
+        self.results.push_back(record);
+    }
+
+    fn next_delay(&self, interval: Duration) -> Duration {
+        let Some(backoff) = &self.options.backoff else {
+            return interval;
+        };
+        if self.consecutive_failures == 0 {
+            return interval;
+        }
+
+        let exponent = (self.consecutive_failures - 1).min(32) as i32;
+        let max = backoff.max_interval.max(interval).as_secs_f64();
+        let delay = (interval.as_secs_f64() * backoff.multiplier.max(1.0).powi(exponent)).min(max);
+        let jitter = backoff.jitter.clamp(0.0, 1.0);
+        let spread = 1.0 + jitter * (2.0 * rand::random::<f64>() - 1.0);
+        Duration::from_secs_f64(delay * spread)
+    }
+
+    fn next_status(
+        &mut self,
+        current: ServiceStatus,
+        record: &ProbeRecord,
+    ) -> (ServiceStatus, Option<String>) {
+        if record.outcome.is_success() {
+            self.consecutive_failures = 0;
+            self.consecutive_successes += 1;
+            let threshold = self.options.success_threshold.max(1);
+            if current == ServiceStatus::NotServing && self.consecutive_successes < threshold {
+                let message = format!(
+                    "recovering ({}/{threshold} successful probes)",
+                    self.consecutive_successes
+                );
+                return (ServiceStatus::NotServing, Some(message));
+            }
+            (ServiceStatus::Serving, None)
+        } else {
+            self.consecutive_successes = 0;
+            self.consecutive_failures += 1;
+            let threshold = self.options.failure_threshold.max(1);
+            let status =
+                if current == ServiceStatus::NotServing || self.consecutive_failures >= threshold {
+                    ServiceStatus::NotServing
+                } else {
+                    ServiceStatus::Degraded
+                };
+            (status, record.error.clone())
+        }
+    }
+}
+
+struct Shared {
+    services: RwLock<HashMap<Uuid, ServiceHealth>>,
+    probes: RwLock<HashMap<Uuid, ProbeState>>,
+    server_override: RwLock<Option<HealthOverride>>,
+    events: broadcast::Sender<HealthEvent>,
+    settings: HealthSettings,
+}
+
+impl Shared {
+    /// Applies a probe result and returns the delay until the next probe,
+    /// or `None` if the service is no longer registered.
+    async fn record(&self, id: Uuid, record: ProbeRecord) -> Option<Duration> {
+        let mut probes = self.probes.write().await;
+        let mut services = self.services.write().await;
+        let (Some(probe), Some(svc)) = (probes.get_mut(&id), services.get_mut(&id)) else {
+            return None;
+        };
+
+        // The first probe after an initial delay starts from a clean slate
+        // rather than the placeholder status.
+        let current = if probe.results.is_empty() {
+            ServiceStatus::Serving
+        } else {
+            probe.status
+        };
+        (probe.status, probe.message) = probe.next_status(current, &record);
+        probe.push(record, self.settings.history_capacity);
+        let flapping = is_flapping(&probe.results, &self.settings);
+        let delay = probe.next_delay(svc.interval);
+        svc.next_probe_at = SystemTime::now() + delay;
+        let (status, message) = probe.effective(svc.manual_override.as_ref());
+
+        if svc.status == status && svc.message == message && svc.flapping == flapping {
+            return Some(delay);
+        }
+        if flapping && !svc.flapping {
+            tracing::warn!(service = %svc.name, %id, "health service is flapping");
+        }
+        if svc.status != status {
+            tracing::info!(service = %svc.name, %id, from = ?svc.status, to = ?status, "health status changed");
+        }
+        svc.status = status;
+        svc.message = message;
+        svc.flapping = flapping;
+        let _ = self.events.send(HealthEvent::Changed(svc.clone()));
+        Some(delay)
+    }
+
+    /// Replaces a service's override and recomputes what it reports.
+    async fn apply_override(
+        &self,
+        id: &Uuid,
+        manual: Option<HealthOverride>,
+    ) -> AppResult<Option<HealthOverride>> {
+        let probes = self.probes.read().await;
+        let mut services = self.services.write().await;
+        let (Some(probe), Some(svc)) = (probes.get(id), services.get_mut(id)) else {
+            return Err(AppError::NotFound(format!("unknown service: {id}")));
+        };
+
+        let previous = std::mem::replace(&mut svc.manual_override, manual);
+        (svc.status, svc.message) = probe.effective(svc.manual_override.as_ref());
+        match &svc.manual_override {
+            Some(manual) => tracing::warn!(
+                service = %svc.name, %id, status = ?manual.status, reason = %manual.reason,
+                "manual health override set"
+            ),
+            None => tracing::info!(service = %svc.name, %id, "manual health override cleared"),
+        }
+        let _ = self.events.send(HealthEvent::Changed(svc.clone()));
+        Ok(previous)
+    }
+
+    async fn apply_server_override(
+        &self,
+        manual: Option<HealthOverride>,
+    ) -> Option<HealthOverride> {
+        let previous = std::mem::replace(&mut *self.server_override.write().await, manual.clone());
+        match &manual {
+            Some(manual) => tracing::warn!(
+                status = ?manual.status, reason = %manual.reason,
+                "server health override set"
+            ),
+            None => tracing::info!("server health override cleared"),
+        }
+        let _ = self.events.send(HealthEvent::ServerOverride(manual));
+        previous
+    }
+
+    /// Clears `manual` once it expires, unless it has been replaced by then.
+    fn expire_later(self: &Arc<Self>, id: Option<Uuid>, manual: &HealthOverride) {
+        let Some(expires_at) = manual.expires_at else {
+            return;
+        };
+        let shared: Weak<Self> = Arc::downgrade(self);
+        let manual = manual.clone();
+        tokio::spawn(async move {
+            let remaining = expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO);
+            tokio::time::sleep(remaining).await;
+            let Some(shared) = shared.upgrade() else {
+                return;
+            };
+            match id {
+                Some(id) => {
+                    let current = shared
+                        .services
+                        .read()
+                        .await
+                        .get(&id)
+                        .and_then(|svc| svc.manual_override.clone());
+                    if current.as_ref() == Some(&manual) {
+                        let _ = shared.apply_override(&id, None).await;
+                    }
+                }
+                None => {
+                    let current = shared.server_override.read().await.clone();
+                    if current.as_ref() == Some(&manual) {
+                        shared.apply_server_override(None).await;
+                    }
+                }
+            }
+        });
+    }
+}
 
 pub struct HealthRegistry {
-    services: Arc<RwLock<HashMap<Uuid, ServiceHealth>>>,
+    shared: Arc<Shared>,
     tasks: RwLock<HashMap<Uuid, JoinHandle<()>>>,
 }
 
 #[allow(dead_code)]
 impl HealthRegistry {
     pub fn new() -> Self {
+        Self::with_settings(HealthSettings::default())
+    }
+
+    pub fn with_settings(settings: HealthSettings) -> Self {
         Self {
-            services: Arc::new(RwLock::new(HashMap::new())),
+            shared: Arc::new(Shared {
+                services: RwLock::new(HashMap::new()),
+                probes: RwLock::new(HashMap::new()),
+                server_override: RwLock::new(None),
+                events: broadcast::channel(EVENT_CAPACITY).0,
+                settings,
+            }),
             tasks: RwLock::new(HashMap::new()),
         }
     }
 
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthEvent> {
+        self.shared.events.subscribe()
+    }
+
     pub async fn register(
         &self,
         name: impl Into<String>,
@@ -57,34 +500,88 @@ impl HealthRegistry {
         version: Option<String>,
         check: HealthCheckFn,
     ) -> Uuid {
-        let id = Uuid::new_v4();
+        self.register_with_options(name, interval, version, check, RegisterOptions::default())
+            .await
+            .expect("a registration without dependencies or a fixed id cannot fail")
+    }
+
+    pub async fn register_with_options(
+        &self,
+        name: impl Into<String>,
+        interval: Duration,
+        version: Option<String>,
+        check: HealthCheckFn,
+        options: RegisterOptions,
+    ) -> AppResult<Uuid> {
+        let id = options.id.unwrap_or_else(Uuid::new_v4);
         let name = name.into();
+        let timeout = options.timeout;
+        let initial_delay = options.initial_delay;
+
+        let mut probe = ProbeState::new(options);
+        let (status, message, first_delay) = if initial_delay.is_zero() {
+            let initial_probe = run_probe(&name, &check, timeout).await;
+            (probe.status, probe.message) =
+                probe.next_status(ServiceStatus::Serving, &initial_probe);
+            probe.push(initial_probe, self.shared.settings.history_capacity);
+            (
+                probe.status,
+                probe.message.clone(),
+                probe.next_delay(interval),
+            )
+        } else {
+            probe.status = ServiceStatus::NotServing;
+            probe.message = Some("awaiting first probe".to_owned());
+            (probe.status, probe.message.clone(), initial_delay)
+        };
 
-        let initial_probe = run_probe(&name, &check).await;
         let health = ServiceHealth {
             id,
             name: name.clone(),
-            status: initial_probe.0,
+            status,
             interval,
             registered_at: Instant::now(),
             version,
-            message: initial_probe.1,
+            message,
+            flapping: false,
+            timeout,
+            next_probe_at: SystemTime::now() + first_delay,
+            manual_override: None,
         };
 
-        self.services.write().await.insert(id, health);
+        // Checked and inserted under both locks, so that concurrent
+        // registrations with the same id can't both pass the check.
+        {
+            let mut probes = self.shared.probes.write().await;
+            let mut services = self.shared.services.write().await;
+            if services.contains_key(&id) {
+                return Err(AppError::AlreadyExists(format!(
+                    "health service already registered: {id}"
+                )));
+            }
+            if let Some(cycle) = find_cycle(&services, &probes, &name, &probe.options.dependencies)
+            {
+                return Err(AppError::InvalidArgument(format!(
+                    "dependency cycle: {}",
+                    cycle.join(" -> ")
+                )));
+            }
+            probes.insert(id, probe);
+            services.insert(id, health.clone());
+        }
+        let _ = self.shared.events.send(HealthEvent::Registered(health));
 
-        let services = Arc::clone(&self.services);
+        let shared = Arc::clone(&self.shared);
         let probe_name = name.clone();
 
         let handle = tokio::spawn(async move {
-            let mut ticker = tokio::time::interval(interval);
-            ticker.tick().await;
+            let mut delay = first_delay;
             loop {
-                ticker.tick().await;
-                let (status, message) = run_probe(&probe_name, &check).await;
-                if let Some(svc) = services.write().await.get_mut(&id) {
-                    svc.status = status;
-                    svc.message = message;
+                tokio::time::sleep(delay).await;
+                let record = run_probe(&probe_name, &check, timeout).await;
+                match shared.record(id, record).await {
+                    Some(next) => delay = next,
+                    None => break,
                 }
             }
         });
@@ -92,22 +589,26 @@ impl HealthRegistry {
         self.tasks.write().await.insert(id, handle);
 
         tracing::info!(service = %name, %id, "health service registered");
-        id
+        Ok(id)
     }
 
     pub async fn deregister(&self, id: &Uuid) {
         if let Some(handle) = self.tasks.write().await.remove(id) {
             handle.abort();
         }
-        self.services.write().await.remove(id);
+        self.shared.probes.write().await.remove(id);
+        if let Some(health) = self.shared.services.write().await.remove(id) {
+            let _ = self.shared.events.send(HealthEvent::Deregistered(health));
+        }
     }
 
     pub async fn get(&self, id: &Uuid) -> Option<ServiceHealth> {
-        self.services.read().await.get(id).cloned()
+        self.shared.services.read().await.get(id).cloned()
     }
 
     pub async fn get_by_name(&self, name: &str) -> Option<ServiceHealth> {
-        self.services
+        self.shared
+            .services
             .read()
             .await
             .values()
@@ -116,24 +617,302 @@ impl HealthRegistry {
     }
 
     pub async fn list(&self) -> Vec<ServiceHealth> {
-        self.services.read().await.values().cloned().collect()
+        self.shared
+            .services
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Recent probe results for a service, oldest first.
+    pub async fn history(&self, id: &Uuid) -> Option<Vec<ProbeRecord>> {
+        self.shared
+            .probes
+            .read()
+            .await
+            .get(id)
+            .map(|probe| probe.results.iter().cloned().collect())
+    }
+
+    /// Forces a service's status until the override is cleared or
+    /// expires, whatever its probes report. Returns the override it
+    /// replaced, if any.
+    pub async fn set_override(
+        &self,
+        id: &Uuid,
+        manual: HealthOverride,
+    ) -> AppResult<Option<HealthOverride>> {
+        let previous = self.shared.apply_override(id, Some(manual.clone())).await?;
+        self.shared.expire_later(Some(*id), &manual);
+        Ok(previous)
+    }
+
+    /// Returns the service to its probe-driven status.
+    pub async fn clear_override(&self, id: &Uuid) -> AppResult<Option<HealthOverride>> {
+        self.shared.apply_override(id, None).await
+    }
+
+    /// Forces the overall status, as used for readiness and the empty
+    /// `grpc.health.v1` service name, e.g. to drain the server on purpose.
+    pub async fn set_server_override(&self, manual: HealthOverride) -> Option<HealthOverride> {
+        let previous = self
+            .shared
+            .apply_server_override(Some(manual.clone()))
+            .await;
+        self.shared.expire_later(None, &manual);
+        previous
     }
+
+    pub async fn clear_server_override(&self) -> Option<HealthOverride> {
+        self.shared.apply_server_override(None).await
+    }
+
+    pub async fn server_override(&self) -> Option<HealthOverride> {
+        self.shared.server_override.read().await.clone()
+    }
+
+    pub async fn graph(&self) -> HealthGraph {
+        let probes = self.shared.probes.read().await;
+        let services = self.shared.services.read().await;
+        let server_override = self.shared.server_override.read().await;
+        build_graph(&services, &probes, server_override.as_ref())
+    }
+
+    /// Status of a service once its dependencies are taken into account.
+    pub async fn aggregated(&self, id: &Uuid) -> Option<AggregatedHealth> {
+        self.graph().await.aggregated.remove(id)
+    }
+
+    /// Worst aggregated status of every service registered under `name`,
+    /// or `None` if no such service exists.
+    pub async fn status_by_name(&self, name: &str) -> Option<ServiceStatus> {
+        let graph = self.graph().await;
+        graph
+            .services
+            .iter()
+            .filter(|h| h.name == name)
+            .map(|h| graph.aggregated[&h.id].status)
+            .max()
+    }
+
+    /// Combined aggregated status of the services nothing else depends on,
+    /// unless the server is overridden. An empty registry is considered
+    /// serving.
+    pub async fn overall_status(&self) -> ServiceStatus {
+        self.graph().await.overall.status
+    }
+}
+
+fn dependencies_by_name<'a>(
+    services: &'a HashMap<Uuid, ServiceHealth>,
+    probes: &'a HashMap<Uuid, ProbeState>,
+) -> HashMap<&'a str, Vec<&'a str>> {
+    let mut graph: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (id, svc) in services {
+        let deps = graph.entry(svc.name.as_str()).or_default();
+        if let Some(probe) = probes.get(id) {
+            deps.extend(probe.options.dependencies.iter().map(|d| d.name.as_str()));
+        }
+    }
+    graph
+}
+
+/// Returns the path of the cycle that registering `name` with `deps` would
+/// create, starting and ending at `name`.
+fn find_cycle(
+    services: &HashMap<Uuid, ServiceHealth>,
+    probes: &HashMap<Uuid, ProbeState>,
+    name: &str,
+    deps: &[Dependency],
+) -> Option<Vec<String>> {
+    fn visit<'a>(
+        graph: &HashMap<&'a str, Vec<&'a str>>,
+        target: &str,
+        node: &'a str,
+        path: &mut Vec<&'a str>,
+        seen: &mut HashSet<&'a str>,
+    ) -> bool {
+        path.push(node);
+        if node == target {
+            return true;
+        }
+        if seen.insert(node) {
+            for next in graph.get(node).into_iter().flatten() {
+                if visit(graph, target, next, path, seen) {
+                    return true;
+                }
+            }
+        }
+        path.pop();
+        false
+    }
+
+    let graph = dependencies_by_name(services, probes);
+    let mut seen = HashSet::new();
+    for dep in deps {
+        let mut path = vec![name];
+        if visit(&graph, name, &dep.name, &mut path, &mut seen) {
+            return Some(path.into_iter().map(str::to_owned).collect());
+        }
+    }
+    None
 }
 
-async fn run_probe(name: &str, check: &HealthCheckFn) -> (ServiceStatus, Option<String>) {
-    match tokio::time::timeout(PROBE_TIMEOUT, check()).await {
-        Ok(Ok(())) => (ServiceStatus::Serving, None),
+fn build_graph(
+    services: &HashMap<Uuid, ServiceHealth>,
+    probes: &HashMap<Uuid, ProbeState>,
+    server_override: Option<&HealthOverride>,
+) -> HealthGraph {
+    let mut by_name: HashMap<&str, Vec<Uuid>> = HashMap::new();
+    for (id, svc) in services {
+        by_name.entry(svc.name.as_str()).or_default().push(*id);
+    }
+
+    let mut edges = Vec::new();
+    for (id, svc) in services {
+        for dep in probes
+            .get(id)
+            .into_iter()
+            .flat_map(|p| &p.options.dependencies)
+        {
+            edges.push(DependencyEdge {
+                service_id: *id,
+                service: svc.name.clone(),
+                dependency: dep.name.clone(),
+                kind: dep.kind,
+            });
+        }
+    }
+
+    let mut aggregated = HashMap::new();
+    for id in services.keys() {
+        aggregate(*id, services, probes, &by_name, &mut aggregated);
+    }
+
+    let depended_on: HashSet<&str> = edges.iter().map(|e| e.dependency.as_str()).collect();
+    let mut roots: Vec<&ServiceHealth> = services
+        .values()
+        .filter(|svc| !depended_on.contains(svc.name.as_str()))
+        .collect();
+    roots.sort_by(|a, b| a.name.cmp(&b.name));
+    let overall = match server_override {
+        Some(manual) => AggregatedHealth {
+            status: manual.status,
+            root_cause: None,
+        },
+        None => roots
+            .iter()
+            .map(|svc| &aggregated[&svc.id])
+            .fold(None, |worst: Option<&AggregatedHealth>, agg| match worst {
+                Some(w) if w.status >= agg.status => Some(w),
+                _ => Some(agg),
+            })
+            .cloned()
+            .unwrap_or(AggregatedHealth {
+                status: ServiceStatus::Serving,
+                root_cause: None,
+            }),
+    };
+
+    let mut services: Vec<ServiceHealth> = services.values().cloned().collect();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+
+    HealthGraph {
+        services,
+        edges,
+        aggregated,
+        overall,
+        server_override: server_override.cloned(),
+    }
+}
+
+fn aggregate(
+    id: Uuid,
+    services: &HashMap<Uuid, ServiceHealth>,
+    probes: &HashMap<Uuid, ProbeState>,
+    by_name: &HashMap<&str, Vec<Uuid>>,
+    memo: &mut HashMap<Uuid, AggregatedHealth>,
+) -> AggregatedHealth {
+    if let Some(agg) = memo.get(&id) {
+        return agg.clone();
+    }
+
+    let svc = &services[&id];
+    let mut agg = AggregatedHealth {
+        status: svc.status,
+        root_cause: (svc.status != ServiceStatus::Serving).then(|| svc.name.clone()),
+    };
+
+    for dep in probes
+        .get(&id)
+        .into_iter()
+        .flat_map(|p| &p.options.dependencies)
+    {
+        let dep_agg = match by_name.get(dep.name.as_str()) {
+            Some(ids) => ids
+                .iter()
+                .map(|dep_id| aggregate(*dep_id, services, probes, by_name, memo))
+                .max_by_key(|a| a.status)
+                .expect("names in by_name always have at least one id"),
+            None => AggregatedHealth {
+                status: ServiceStatus::NotServing,
+                root_cause: Some(dep.name.clone()),
+            },
+        };
+        let propagated = match dep.kind {
+            DependencyKind::Critical => dep_agg.status,
+            DependencyKind::Optional => dep_agg.status.min(ServiceStatus::Degraded),
+        };
+        if propagated > agg.status {
+            agg = AggregatedHealth {
+                status: propagated,
+                root_cause: dep_agg.root_cause,
+            };
+        }
+    }
+
+    memo.insert(id, agg.clone());
+    agg
+}
+
+fn is_flapping(results: &VecDeque<ProbeRecord>, settings: &HealthSettings) -> bool {
+    if settings.flap_threshold == 0 {
+        return false;
+    }
+    let cutoff = SystemTime::now()
+        .checked_sub(settings.flap_window)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let recent: Vec<bool> = results
+        .iter()
+        .filter(|r| r.timestamp >= cutoff)
+        .map(|r| r.outcome.is_success())
+        .collect();
+    let transitions = recent.windows(2).filter(|w| w[0] != w[1]).count();
+    transitions >= settings.flap_threshold
+}
+
+async fn run_probe(name: &str, check: &HealthCheckFn, timeout: Duration) -> ProbeRecord {
+    let timestamp = SystemTime::now();
+    let started = Instant::now();
+    let (outcome, error) = match tokio::time::timeout(timeout, check()).await {
+        Ok(Ok(())) => (ProbeOutcome::Success, None),
         Ok(Err(err)) => {
             tracing::warn!(service = %name, %err, "health probe failed");
-            (ServiceStatus::NotServing, Some(err))
+            (ProbeOutcome::Failure, Some(err))
         }
         Err(_) => {
             tracing::warn!(service = %name, "health probe timed out");
-            (
-                ServiceStatus::NotServing,
-                Some("probe timed out".to_owned()),
-            )
+            (ProbeOutcome::Timeout, Some("probe timed out".to_owned()))
         }
+    };
+
+    ProbeRecord {
+        timestamp,
+        latency: started.elapsed(),
+        outcome,
+        error,
     }
 }
 
@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Connecting to the database and running migrations.
+    Starting,
+    Ready,
+    /// Shutdown signal received; in-flight requests are finishing.
+    Draining,
+}
+
+impl Phase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Starting => "starting",
+            Self::Ready => "ready",
+            Self::Draining => "draining",
+        }
+    }
+}
+
+/// Process-wide startup/shutdown phase. Created before the database so
+/// readiness can be reported while migrations run.
+pub struct Lifecycle {
+    phase: watch::Sender<Phase>,
+    started_at: Instant,
+}
+
+#[allow(dead_code)]
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            phase: watch::channel(Phase::Starting).0,
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        *self.phase.borrow()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.phase() == Phase::Ready
+    }
+
+    pub fn set_ready(&self) {
+        self.transition(Phase::Ready);
+    }
+
+    pub fn set_draining(&self) {
+        self.transition(Phase::Draining);
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Phase> {
+        self.phase.subscribe()
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    fn transition(&self, to: Phase) {
+        let from = self.phase.send_replace(to);
+        if from != to {
+            tracing::info!(
+                from = from.as_str(),
+                to = to.as_str(),
+                "lifecycle phase changed"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/lifecycle.rs"]
+mod tests;
@@ -0,0 +1,374 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::fd::{FromRawFd, IntoRawFd, RawFd};
+#[cfg(unix)]
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tonic::transport::server::UdsConnectInfo;
+
+use super::audit::Principal;
+
+/// Groups of endpoints a listener can serve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    /// `midnight.HealthService` and the standard `grpc.health.v1.Health`.
+    Health,
+    /// `midnight.AdminService`.
+    Admin,
+    /// gRPC server reflection.
+    Reflection,
+    /// The HTTP health endpoints, unless `ADMIN_LISTEN_ADDR` moves them.
+    Web,
+}
+
+impl Service {
+    pub const ALL: [Service; 4] = [
+        Service::Health,
+        Service::Admin,
+        Service::Reflection,
+        Service::Web,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Health => "health",
+            Service::Admin => "admin",
+            Service::Reflection => "reflection",
+            Service::Web => "web",
+        }
+    }
+}
+
+impl FromStr for Service {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Service::ALL
+            .into_iter()
+            .find(|service| service.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Service::ALL.iter().map(|s| s.name()).collect();
+                format!("unknown service {s:?}, expected {}", names.join(", "))
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Address {
+    Tcp(SocketAddr),
+    /// `unix:<path>`.
+    Unix(PathBuf),
+    /// `systemd` for every socket passed by systemd socket activation, or
+    /// `systemd:<name>` for those named `<name>` with
+    /// `FileDescriptorName=`.
+    Systemd(Option<String>),
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Tcp(addr) => write!(f, "{addr}"),
+            Address::Unix(path) => write!(f, "unix:{}", path.display()),
+            Address::Systemd(None) => f.write_str("systemd"),
+            Address::Systemd(Some(name)) => write!(f, "systemd:{name}"),
+        }
+    }
+}
+
+/// One entry of `LISTEN_ADDR`: an address, then optional space-separated
+/// `services=<a>+<b>` and, for Unix sockets, `mode=<octal>` and
+/// `auth=peer`, e.g.
+/// `unix:/run/midnight/admin.sock services=admin mode=0600 auth=peer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerSpec {
+    pub address: Address,
+    pub services: Vec<Service>,
+    /// Permissions given to a Unix socket file.
+    pub mode: u32,
+    /// Whether a Unix socket peer's user id authenticates its requests.
+    pub peer_auth: bool,
+}
+
+impl ListenerSpec {
+    pub const DEFAULT_MODE: u32 = 0o660;
+
+    pub fn serves(&self, service: Service) -> bool {
+        self.services.contains(&service)
+    }
+}
+
+impl FromStr for ListenerSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let address = match parts.next() {
+            None => return Err("empty listener".to_owned()),
+            Some("systemd") => Address::Systemd(None),
+            Some(addr) => {
+                if let Some(name) = addr.strip_prefix("systemd:") {
+                    Address::Systemd(Some(name.to_owned()))
+                } else if let Some(path) = addr.strip_prefix("unix:") {
+                    if path.is_empty() {
+                        return Err("unix: needs a socket path".to_owned());
+                    }
+                    Address::Unix(PathBuf::from(path))
+                } else {
+                    Address::Tcp(
+                        addr.parse()
+                            .map_err(|e| format!("invalid address {addr:?}: {e}"))?,
+                    )
+                }
+            }
+        };
+
+        let mut spec = ListenerSpec {
+            address,
+            services: Service::ALL.to_vec(),
+            mode: Self::DEFAULT_MODE,
+            peer_auth: false,
+        };
+        for option in parts {
+            match option.split_once('=') {
+                Some(("services", names)) => {
+                    spec.services = names.split('+').map(str::parse).collect::<Result<_, _>>()?;
+                }
+                Some(("mode", mode)) => {
+                    if !matches!(spec.address, Address::Unix(_)) {
+                        return Err("mode only applies to unix: sockets".to_owned());
+                    }
+                    spec.mode = u32::from_str_radix(mode, 8)
+                        .ok()
+                        .filter(|m| *m <= 0o777)
+                        .ok_or_else(|| {
+                            format!("invalid mode {mode:?}, expected octal like 0660")
+                        })?;
+                }
+                Some(("auth", "peer")) => {
+                    if matches!(spec.address, Address::Tcp(_)) {
+                        return Err(
+                            "auth=peer only applies to unix: and systemd sockets".to_owned()
+                        );
+                    }
+                    spec.peer_auth = true;
+                }
+                _ => return Err(format!("unknown listener option {option:?}")),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for ListenerSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let services: Vec<_> = self.services.iter().map(|s| s.name()).collect();
+        write!(f, "{} ({})", self.address, services.join(", "))?;
+        if self.peer_auth {
+            f.write_str(" with auth=peer")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    /// With the path of a socket file this process created, removed on
+    /// shutdown.
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, Option<PathBuf>),
+}
+
+/// Binds every spec in order. A socket inherited from systemd goes to the
+/// first spec that matches it; a spec matching none is an error.
+pub fn bind_all(specs: &[ListenerSpec]) -> Result<Vec<(Listener, ListenerSpec)>> {
+    #[cfg(unix)]
+    let inherited = take_inherited_fds();
+    let mut claimed = HashSet::new();
+    let mut listeners = Vec::new();
+    for spec in specs {
+        match &spec.address {
+            Address::Tcp(addr) => {
+                let listener = std::net::TcpListener::bind(addr)
+                    .with_context(|| format!("failed to bind {addr}"))?;
+                listeners.push((tcp(listener)?, spec.clone()));
+            }
+            #[cfg(unix)]
+            Address::Unix(path) => listeners.push((bind_unix(path, spec.mode)?, spec.clone())),
+            #[cfg(unix)]
+            Address::Systemd(name) => {
+                let fds: Vec<_> = inherited
+                    .iter()
+                    .filter(|(fd, fd_name)| {
+                        !claimed.contains(fd) && name.as_ref().is_none_or(|n| n == fd_name)
+                    })
+                    .collect();
+                if fds.is_empty() {
+                    bail!("no unclaimed sockets from systemd match {}", spec.address);
+                }
+                for &(fd, _) in fds {
+                    claimed.insert(fd);
+                    listeners.push((adopt(fd)?, spec.clone()));
+                }
+            }
+            #[cfg(not(unix))]
+            address => bail!("{address} is not supported on this platform"),
+        }
+    }
+    Ok(listeners)
+}
+
+fn tcp(listener: std::net::TcpListener) -> Result<Listener> {
+    listener.set_nonblocking(true)?;
+    Ok(Listener::Tcp(TcpListener::from_std(listener)?))
+}
+
+/// The first descriptor systemd passes, `SD_LISTEN_FDS_START`.
+#[cfg(unix)]
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Binds a socket file, replacing one left behind by a process that is
+/// gone, and sets its permissions.
+#[cfg(unix)]
+fn bind_unix(path: &Path, mode: u32) -> Result<Listener> {
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            bail!("{} exists and is not a socket", path.display());
+        }
+        if UnixStream::connect(path).is_ok() {
+            bail!("{} is in use by another process", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("failed to remove stale socket {}", path.display()))?;
+    }
+    let listener =
+        UnixListener::bind(path).with_context(|| format!("failed to bind {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("failed to set permissions on {}", path.display()))?;
+    listener.set_nonblocking(true)?;
+    Ok(Listener::Unix(
+        tokio::net::UnixListener::from_std(listener)?,
+        Some(path.to_owned()),
+    ))
+}
+
+#[cfg(unix)]
+const LISTEN_VARS: [&str; 3] = ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"];
+
+/// Sockets passed by systemd socket activation, with their names. The
+/// variables are unset so that child processes don't take the sockets
+/// for their own.
+#[cfg(unix)]
+fn take_inherited_fds() -> Vec<(RawFd, String)> {
+    let [pid, count, names] = LISTEN_VARS.map(|var| std::env::var(var).ok());
+    let fds = parse_listen_fds(
+        pid.as_deref(),
+        count.as_deref(),
+        names.as_deref(),
+        std::process::id(),
+    );
+    if pid.is_some() {
+        for var in LISTEN_VARS {
+            // SAFETY: listeners are bound once, at startup, before anything
+            // else that reads the environment runs.
+            unsafe { std::env::remove_var(var) };
+        }
+    }
+    fds
+}
+
+/// Reads the `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` protocol. The
+/// sockets are only ours if `LISTEN_PID` names this process.
+#[cfg(unix)]
+fn parse_listen_fds(
+    pid: Option<&str>,
+    count: Option<&str>,
+    names: Option<&str>,
+    our_pid: u32,
+) -> Vec<(RawFd, String)> {
+    if pid.and_then(|p| p.trim().parse::<u32>().ok()) != Some(our_pid) {
+        return Vec::new();
+    }
+    let count: RawFd = count.and_then(|c| c.trim().parse().ok()).unwrap_or(0);
+    let names: Vec<&str> = names.map(|n| n.split(':').collect()).unwrap_or_default();
+    (0..count)
+        .map(|i| {
+            let name = names.get(i as usize).copied().unwrap_or("unknown");
+            (LISTEN_FDS_START + i, name.to_owned())
+        })
+        .collect()
+}
+
+/// Takes ownership of an inherited listening socket, TCP or Unix.
+#[cfg(unix)]
+fn adopt(fd: RawFd) -> Result<Listener> {
+    if !is_listening(fd) {
+        bail!("inherited descriptor {fd} is not a listening socket");
+    }
+    // SAFETY: `fd` is a listening socket systemd passed to this process,
+    // and `bind_all` adopts each descriptor at most once.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    if listener.local_addr().is_ok() {
+        return tcp(listener);
+    }
+    // SAFETY: as above; the descriptor was released from the TCP wrapper.
+    let listener = unsafe { UnixListener::from_raw_fd(listener.into_raw_fd()) };
+    if listener.local_addr().is_err() {
+        bail!("inherited descriptor {fd} is not a TCP or Unix socket");
+    }
+    listener.set_nonblocking(true)?;
+    Ok(Listener::Unix(
+        tokio::net::UnixListener::from_std(listener)?,
+        None,
+    ))
+}
+
+/// Whether `fd` is a socket that `listen` was called on.
+#[cfg(unix)]
+fn is_listening(fd: RawFd) -> bool {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: `value` and `len` outlive the call and `len` is its size.
+    let rc = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_ACCEPTCONN,
+            (&raw mut value).cast(),
+            &mut len,
+        )
+    };
+    rc == 0 && value != 0
+}
+
+/// Names a request over a Unix socket after the peer's user id,
+/// `uid:<n>`, for listeners with `auth=peer`: file permissions are what
+/// let it connect, and the principal counts as authenticated.
+#[cfg(unix)]
+pub async fn peer_principal(mut req: Request, next: Next) -> Response {
+    let uid = req
+        .extensions()
+        .get::<UdsConnectInfo>()
+        .and_then(|info| info.peer_cred)
+        .map(|cred| cred.uid());
+    if let Some(uid) = uid {
+        req.extensions_mut().insert(Principal::unix_peer(uid));
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/listen.rs"]
+mod tests;
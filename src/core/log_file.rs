@@ -0,0 +1,307 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use super::config::Config;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// At the first write after midnight UTC.
+    Daily,
+    /// Before a write that would take the file past this many bytes.
+    Size(u64),
+    Never,
+}
+
+impl Rotation {
+    /// `daily`, `size` or `never`; anything else is daily.
+    pub fn from_str(s: &str, max_bytes: u64) -> Self {
+        match s.to_lowercase().as_str() {
+            "size" => Self::Size(max_bytes),
+            "never" => Self::Never,
+            _ => Self::Daily,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileSettings {
+    pub path: PathBuf,
+    pub rotation: Rotation,
+    /// Rotated files to keep; 0 keeps all of them.
+    pub max_files: usize,
+    /// Gzip rotated files in the background.
+    pub compress: bool,
+}
+
+impl FileSettings {
+    /// `None` unless `LOG_FILE` is set.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let path = config.log_file.as_ref()?;
+        Some(Self {
+            path: PathBuf::from(path),
+            rotation: Rotation::from_str(
+                &config.log_file_rotation,
+                config.log_file_max_size_mb.saturating_mul(1024 * 1024),
+            ),
+            max_files: config.log_file_max_files,
+            compress: config.log_file_compress,
+        })
+    }
+}
+
+/// A log file that rotates itself. Rotated files are renamed to
+/// `<name>.<UTC timestamp>`, then gzipped if enabled and pruned down to
+/// `max_files` by a worker thread, so that writers never wait for either.
+pub struct RollingFile {
+    settings: FileSettings,
+    file: File,
+    size: u64,
+    day: u64,
+    worker: Sender<Job>,
+}
+
+enum Job {
+    Rotated(PathBuf),
+    /// Answered once the jobs sent before it are done.
+    Idle(Sender<()>),
+}
+
+#[allow(dead_code)]
+impl RollingFile {
+    pub fn open(settings: FileSettings) -> io::Result<Self> {
+        if let Some(dir) = settings.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = open_append(&settings.path)?;
+        let meta = file.metadata()?;
+        let (worker, jobs) = mpsc::channel();
+        let worker_settings = settings.clone();
+        std::thread::Builder::new()
+            .name("log-rotation".to_owned())
+            .spawn(move || run_worker(&worker_settings, jobs))?;
+        Ok(Self {
+            size: meta.len(),
+            // A file left over from an earlier day is rotated on first write.
+            day: day_of(meta.modified().unwrap_or_else(|_| SystemTime::now())),
+            settings,
+            file,
+            worker,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.settings.path
+    }
+
+    /// Writes `buf` as if at `now`, rotating first if due.
+    pub fn write_at(&mut self, buf: &[u8], now: SystemTime) -> io::Result<usize> {
+        if self.should_rotate(buf.len() as u64, now) {
+            self.rotate(now)?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn should_rotate(&self, incoming: u64, now: SystemTime) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        match self.settings.rotation {
+            Rotation::Daily => day_of(now) != self.day,
+            Rotation::Size(max) => self.size + incoming > max,
+            Rotation::Never => false,
+        }
+    }
+
+    fn rotate(&mut self, now: SystemTime) -> io::Result<()> {
+        self.file.flush()?;
+        let rotated = self.rotated_path(now);
+        fs::rename(&self.settings.path, &rotated)?;
+        self.file = open_append(&self.settings.path)?;
+        self.size = 0;
+        self.day = day_of(now);
+        let _ = self.worker.send(Job::Rotated(rotated));
+        Ok(())
+    }
+
+    /// Blocks until every rotated file so far has been compressed and
+    /// pruned.
+    pub fn wait_for_worker(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.worker.send(Job::Idle(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+
+    /// Unique even when several rotations happen within a second.
+    fn rotated_path(&self, now: SystemTime) -> PathBuf {
+        let base = format!("{}.{}", self.settings.path.display(), utc_stamp(now));
+        let mut candidate = PathBuf::from(&base);
+        let mut n = 1;
+        while candidate.exists() || gz_path(&candidate).exists() {
+            candidate = PathBuf::from(format!("{base}-{n}"));
+            n += 1;
+        }
+        candidate
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_at(buf, SystemTime::now())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// Compresses and prunes rotated files one at a time, so a burst of
+/// rotations can't pile up threads. Failures are logged from here rather
+/// than by the writer, which the log file's own events go through.
+fn run_worker(settings: &FileSettings, jobs: Receiver<Job>) {
+    for job in jobs {
+        match job {
+            Job::Rotated(rotated) => {
+                if settings.compress
+                    && let Err(err) = compress(&rotated)
+                {
+                    tracing::warn!(file = %rotated.display(), %err, "failed to compress rotated log file");
+                }
+                prune(settings);
+            }
+            Job::Idle(done) => {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let gz = gz_path(path);
+    let mut encoder = GzEncoder::new(File::create(&gz)?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(path)
+}
+
+/// Rotated files of the log file at `path`, oldest first.
+pub fn rotated_files(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let name = name.to_string_lossy();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| is_rotated(&e.file_name().to_string_lossy(), &name))
+        .map(|e| e.path())
+        .collect();
+    // Timestamps sort chronologically; `.gz` doesn't change the order.
+    files.sort_by_key(|p| p.to_string_lossy().trim_end_matches(".gz").to_owned());
+    files
+}
+
+/// Whether `file_name` is `name` rotated: `<name>.YYYYMMDD-HHMMSS`, then
+/// `-<n>` when several rotations share a second, then `.gz` if compressed.
+fn is_rotated(file_name: &str, name: &str) -> bool {
+    let Some(rest) = file_name
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('.'))
+    else {
+        return false;
+    };
+    let rest = rest.strip_suffix(".gz").unwrap_or(rest);
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let mut parts = rest.split('-');
+    let (Some(date), Some(time)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    date.len() == 8
+        && digits(date)
+        && time.len() == 6
+        && digits(time)
+        && parts.next().is_none_or(digits)
+        && parts.next().is_none()
+}
+
+fn prune(settings: &FileSettings) {
+    if settings.max_files == 0 {
+        return;
+    }
+    let files = rotated_files(&settings.path);
+    let excess = files.len().saturating_sub(settings.max_files);
+    for old in &files[..excess] {
+        if let Err(err) = fs::remove_file(old) {
+            tracing::warn!(file = %old.display(), %err, "failed to remove old log file");
+        }
+    }
+}
+
+fn day_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / SECS_PER_DAY
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC.
+fn utc_stamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((secs / SECS_PER_DAY) as i64);
+    let rem = secs % SECS_PER_DAY;
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian date, after Howard
+/// Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/log_file.rs"]
+mod tests;
@@ -1,8 +1,17 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context as _;
 use tracing_subscriber::fmt::format::FmtSpan;
-use tracing_subscriber::fmt::time::SystemTime;
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
 
 use super::config::Config;
+use super::error::{AppError, AppResult};
+use super::log_file::{FileSettings, RollingFile};
+use super::redact::{self, Redactor};
+use super::telemetry::Telemetry;
 
 #[derive(Debug, Clone, Copy)]
 pub enum LogStyle {
@@ -25,66 +34,238 @@ impl LogStyle {
     }
 }
 
-pub fn init(config: &Config) {
-    let style = LogStyle::from_str(&config.log_style);
+pub type FilterLayer = reload::Layer<EnvFilter, Registry>;
 
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.log_level));
+/// The filter directive in effect, e.g. `info,sqlx=warn`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterState {
+    pub directive: String,
+    /// Set while a temporary directive is in effect.
+    pub expires_at: Option<SystemTime>,
+    /// Directive restored once a temporary one expires.
+    pub revert_to: Option<String>,
+}
 
-    let subscriber = tracing_subscriber::registry().with(env_filter);
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Temporary {
+    directive: String,
+    expires_at: SystemTime,
+}
 
-    match style {
-        LogStyle::Plain => {
-            subscriber
-                .with(
-                    fmt::layer()
-                        .with_ansi(false)
-                        .with_span_events(FmtSpan::CLOSE),
-                )
-                .init();
-        }
-        LogStyle::Compact => {
-            subscriber
-                .with(
-                    fmt::layer()
-                        .compact()
-                        .with_target(false)
-                        .with_span_events(FmtSpan::CLOSE),
-                )
-                .init();
+struct FilterInner {
+    handle: reload::Handle<EnvFilter, Registry>,
+    base: String,
+    temporary: Option<Temporary>,
+}
+
+/// Handle for changing the running log filter without a restart.
+#[derive(Clone)]
+pub struct LogFilter {
+    inner: Arc<Mutex<FilterInner>>,
+}
+
+#[allow(dead_code)]
+impl LogFilter {
+    /// The layer must be added to the subscriber for changes to apply.
+    pub fn new(directive: &str) -> (FilterLayer, Self) {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new(directive));
+        let filter = Self {
+            inner: Arc::new(Mutex::new(FilterInner {
+                handle,
+                base: directive.to_owned(),
+                temporary: None,
+            })),
+        };
+        (layer, filter)
+    }
+
+    pub fn state(&self) -> FilterState {
+        self.inner.lock().unwrap().state()
+    }
+
+    /// Replaces the filter. With a `ttl` the change is temporary and the
+    /// previous directive comes back once it runs out.
+    pub fn set(&self, directive: &str, ttl: Option<Duration>) -> AppResult<FilterState> {
+        let filter = parse(directive)?;
+        let mut inner = self.inner.lock().unwrap();
+        inner.reload(filter)?;
+        match ttl {
+            Some(ttl) => {
+                let temporary = Temporary {
+                    directive: directive.to_owned(),
+                    expires_at: SystemTime::now() + ttl,
+                };
+                inner.temporary = Some(temporary.clone());
+                self.revert_later(temporary);
+            }
+            None => {
+                inner.base = directive.to_owned();
+                inner.temporary = None;
+            }
         }
-        LogStyle::Pretty => {
-            subscriber
-                .with(
-                    fmt::layer()
-                        .pretty()
-                        .with_target(true)
-                        .with_thread_ids(true)
-                        .with_thread_names(true)
-                        .with_span_events(FmtSpan::CLOSE),
-                )
-                .init();
+        tracing::info!(directive, ?ttl, "log filter changed");
+        Ok(inner.state())
+    }
+
+    /// Changes the directive restored after a temporary one, applying it
+    /// now if none is in effect.
+    pub fn set_base(&self, directive: &str) -> AppResult<()> {
+        let filter = parse(directive)?;
+        let mut inner = self.inner.lock().unwrap();
+        if inner.temporary.is_none() {
+            inner.reload(filter)?;
         }
-        LogStyle::Json => {
-            subscriber
-                .with(
-                    fmt::layer()
-                        .json()
-                        .with_current_span(true)
-                        .with_span_list(true)
-                        .with_target(true)
-                        .with_thread_ids(true)
-                        .with_file(true)
-                        .with_line_number(true)
-                        .with_timer(SystemTime)
-                        .flatten_event(true)
-                        .with_span_events(FmtSpan::CLOSE),
-                )
-                .init();
+        inner.base = directive.to_owned();
+        Ok(())
+    }
+
+    /// Restores the base directive once `temporary` expires, unless it has
+    /// been replaced by then.
+    fn revert_later(&self, temporary: Temporary) {
+        let inner: Weak<Mutex<FilterInner>> = Arc::downgrade(&self.inner);
+        tokio::spawn(async move {
+            let remaining = temporary
+                .expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO);
+            tokio::time::sleep(remaining).await;
+            let Some(inner) = inner.upgrade() else {
+                return;
+            };
+            let mut inner = inner.lock().unwrap();
+            if inner.temporary.as_ref() != Some(&temporary) {
+                return;
+            }
+            inner.temporary = None;
+            let base = inner.base.clone();
+            match inner.reload(EnvFilter::new(&base)) {
+                Ok(()) => tracing::info!(directive = %base, "temporary log filter expired"),
+                Err(err) => tracing::warn!(%err, "failed to restore log filter"),
+            }
+        });
+    }
+}
+
+impl FilterInner {
+    fn state(&self) -> FilterState {
+        match &self.temporary {
+            Some(t) => FilterState {
+                directive: t.directive.clone(),
+                expires_at: Some(t.expires_at),
+                revert_to: Some(self.base.clone()),
+            },
+            None => FilterState {
+                directive: self.base.clone(),
+                expires_at: None,
+                revert_to: None,
+            },
         }
     }
 
-    tracing::info!(log_level = %config.log_level, log_style = ?style, "logging initialized");
+    fn reload(&self, filter: EnvFilter) -> AppResult<()> {
+        self.handle
+            .reload(filter)
+            .map_err(|e| AppError::Internal(format!("failed to reload log filter: {e}")))
+    }
+}
+
+fn parse(directive: &str) -> AppResult<EnvFilter> {
+    if directive.trim().is_empty() {
+        return Err(AppError::InvalidArgument("log filter is required".into()));
+    }
+    EnvFilter::try_new(directive)
+        .map_err(|e| AppError::InvalidArgument(format!("invalid log filter: {e}")))
+}
+
+/// Installs the global subscriber. `RUST_LOG` takes precedence over
+/// `LOG_LEVEL` for the initial filter. With `LOG_FILE` set, logs are also
+/// written to that file as JSON, whatever the stdout style. Every output,
+/// traces included, masks `LOG_REDACT_FIELDS` values and URL credentials.
+pub fn init(config: &Config, telemetry: &Telemetry) -> anyhow::Result<LogFilter> {
+    let style = LogStyle::from_str(&config.log_style);
+    let redactor = Redactor::new(&config.log_redact_fields);
+
+    let directive = std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|d| EnvFilter::try_new(d).is_ok())
+        .unwrap_or_else(|| config.log_level.clone());
+    let (filter_layer, log_filter) = LogFilter::new(&directive);
+
+    let file_layer = match FileSettings::from_config(config) {
+        Some(settings) => {
+            let path = settings.path.clone();
+            let file = RollingFile::open(settings)
+                .with_context(|| format!("failed to open log file {}", path.display()))?;
+            Some(
+                fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_target(true)
+                    .with_thread_ids(true)
+                    .with_file(true)
+                    .with_line_number(true)
+                    .with_timer(fmt::time::SystemTime)
+                    .flatten_event(true)
+                    .with_span_events(FmtSpan::CLOSE)
+                    .with_writer(Mutex::new(file)),
+            )
+        }
+        None => None,
+    };
+
+    let stdout_layer = match style {
+        LogStyle::Plain => fmt::layer()
+            .with_ansi(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        LogStyle::Compact => fmt::layer()
+            .compact()
+            .with_target(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        LogStyle::Pretty => fmt::layer()
+            .pretty()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_thread_names(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+        LogStyle::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_timer(fmt::time::SystemTime)
+            .flatten_event(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .boxed(),
+    };
+
+    // Every output, the OTLP exporter included, gets masked values.
+    let outputs = telemetry
+        .layer()
+        .and_then(file_layer)
+        .and_then(stdout_layer);
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(redactor.layer(outputs))
+        .init();
+
+    tracing::info!(
+        log_filter = %directive,
+        log_style = ?style,
+        log_file = config.log_file.as_deref(),
+        otlp_endpoint = config
+            .otlp_endpoint
+            .as_ref()
+            .map(|endpoint| redact::scheme_and_host(endpoint.expose())),
+        "logging initialized"
+    );
+    Ok(log_filter)
 }
 
 #[cfg(test)]
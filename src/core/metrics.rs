@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+use super::health::{HealthRegistry, ServiceStatus};
+
+const STATUSES: [ServiceStatus; 3] = [
+    ServiceStatus::Serving,
+    ServiceStatus::Degraded,
+    ServiceStatus::NotServing,
+];
+
+/// Prometheus metrics for the server. gRPC metrics are recorded as
+/// requests complete; pool and health gauges are refreshed on each scrape.
+pub struct Metrics {
+    registry: Registry,
+    grpc_handled: IntCounterVec,
+    grpc_handling_seconds: HistogramVec,
+    grpc_in_flight: IntGauge,
+    db_connections: IntGauge,
+    db_idle_connections: IntGauge,
+    db_acquired_connections: IntGauge,
+    db_max_connections: IntGauge,
+    health_status: IntGaugeVec,
+    health_probe_latency: GaugeVec,
+    health_flapping: IntGaugeVec,
+}
+
+/// Decrements the in-flight gauge when the request finishes, including
+/// when its future is dropped.
+pub struct InFlight<'a>(&'a IntGauge);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[allow(dead_code)]
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let grpc_labels = ["grpc_service", "grpc_method", "grpc_code"];
+        let health_labels = ["service", "id"];
+
+        let grpc_handled = IntCounterVec::new(
+            Opts::new(
+                "grpc_server_handled_total",
+                "gRPC requests completed, by method and status code.",
+            ),
+            &grpc_labels,
+        )
+        .unwrap();
+        let grpc_handling_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "grpc_server_handling_seconds",
+                "Time until the gRPC response headers were sent.",
+            ),
+            &grpc_labels,
+        )
+        .unwrap();
+        let grpc_in_flight = IntGauge::new(
+            "grpc_server_in_flight_requests",
+            "gRPC requests currently being handled.",
+        )
+        .unwrap();
+        let db_connections = IntGauge::new(
+            "midnight_db_pool_connections",
+            "Open connections in the database pool.",
+        )
+        .unwrap();
+        let db_idle_connections = IntGauge::new(
+            "midnight_db_pool_idle_connections",
+            "Idle connections in the database pool.",
+        )
+        .unwrap();
+        let db_acquired_connections = IntGauge::new(
+            "midnight_db_pool_acquired_connections",
+            "Database connections currently checked out of the pool.",
+        )
+        .unwrap();
+        let db_max_connections = IntGauge::new(
+            "midnight_db_pool_max_connections",
+            "Maximum size of the database pool.",
+        )
+        .unwrap();
+        let health_status = IntGaugeVec::new(
+            Opts::new(
+                "midnight_health_status",
+                "1 for the current status of each health service, 0 otherwise.",
+            ),
+            &["service", "id", "status"],
+        )
+        .unwrap();
+        let health_probe_latency = GaugeVec::new(
+            Opts::new(
+                "midnight_health_probe_latency_seconds",
+                "Latency of the most recent probe of each health service.",
+            ),
+            &health_labels,
+        )
+        .unwrap();
+        let health_flapping = IntGaugeVec::new(
+            Opts::new(
+                "midnight_health_flapping",
+                "1 if the health service is flapping.",
+            ),
+            &health_labels,
+        )
+        .unwrap();
+
+        for collector in [
+            Box::new(grpc_handled.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(grpc_handling_seconds.clone()),
+            Box::new(grpc_in_flight.clone()),
+            Box::new(db_connections.clone()),
+            Box::new(db_idle_connections.clone()),
+            Box::new(db_acquired_connections.clone()),
+            Box::new(db_max_connections.clone()),
+            Box::new(health_status.clone()),
+            Box::new(health_probe_latency.clone()),
+            Box::new(health_flapping.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        Self {
+            registry,
+            grpc_handled,
+            grpc_handling_seconds,
+            grpc_in_flight,
+            db_connections,
+            db_idle_connections,
+            db_acquired_connections,
+            db_max_connections,
+            health_status,
+            health_probe_latency,
+            health_flapping,
+        }
+    }
+
+    pub fn start_request(&self) -> InFlight<'_> {
+        self.grpc_in_flight.inc();
+        InFlight(&self.grpc_in_flight)
+    }
+
+    /// Records a finished gRPC call. `method` is its path,
+    /// `/package.Service/Method`, or `None` when that isn't one of the
+    /// server's RPCs: those share one series, so arbitrary requests can't
+    /// create new ones.
+    pub fn observe_request(&self, method: Option<&str>, code: tonic::Code, latency: Duration) {
+        let (service, method) = method
+            .and_then(|path| path.trim_start_matches('/').split_once('/'))
+            .unwrap_or(("unknown", "unknown"));
+        let code = format!("{code:?}");
+        let labels = [service, method, code.as_str()];
+        self.grpc_handled.with_label_values(&labels).inc();
+        self.grpc_handling_seconds
+            .with_label_values(&labels)
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Refreshes the scrape-time gauges and renders every metric in the
+    /// Prometheus text format.
+    pub async fn render(&self, db: &PgPool, health: &HealthRegistry) -> String {
+        let size = db.size() as i64;
+        let idle = db.num_idle() as i64;
+        self.db_connections.set(size);
+        self.db_idle_connections.set(idle);
+        self.db_acquired_connections.set((size - idle).max(0));
+        self.db_max_connections
+            .set(db.options().get_max_connections() as i64);
+
+        self.health_status.reset();
+        self.health_probe_latency.reset();
+        self.health_flapping.reset();
+        for svc in health.list().await {
+            let id = svc.id.to_string();
+            for status in STATUSES {
+                self.health_status
+                    .with_label_values(&[svc.name.as_str(), id.as_str(), status.as_str()])
+                    .set((svc.status == status) as i64);
+            }
+            self.health_flapping
+                .with_label_values(&[svc.name.as_str(), id.as_str()])
+                .set(svc.flapping as i64);
+            if let Some(last) = health
+                .history(&svc.id)
+                .await
+                .and_then(|h| h.last().cloned())
+            {
+                self.health_probe_latency
+                    .with_label_values(&[svc.name.as_str(), id.as_str()])
+                    .set(last.latency.as_secs_f64());
+            }
+        }
+
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .expect("encoding metrics to a Vec cannot fail");
+        String::from_utf8(buf).expect("metrics text is UTF-8")
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/metrics.rs"]
+mod tests;
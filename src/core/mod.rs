@@ -1,6 +1,21 @@
+pub mod audit;
+pub mod auth;
+pub mod authz;
 pub mod config;
 pub mod db;
 pub mod error;
 pub mod health;
+pub mod lifecycle;
+pub mod listen;
+pub mod log_file;
 pub mod logging;
+pub mod metrics;
+pub mod notifier;
+pub mod probes;
+pub mod redact;
+pub mod reload;
+pub mod request_id;
+pub mod secrets;
 pub mod state;
+pub mod telemetry;
+pub mod tls;
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use super::config::Config;
+use super::health::{HealthEvent, ServiceHealth, ServiceStatus};
+use super::redact;
+use super::state::AppState;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest wait between delivery attempts, however many retries are left.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone)]
+pub struct NotifierSettings {
+    pub webhooks: Vec<String>,
+    /// Services to notify about, by name. Empty means every service.
+    pub services: Vec<String>,
+    /// Minimum time between notifications for the same service. Changes
+    /// in between are coalesced into one notification once it has passed.
+    pub min_interval: Duration,
+    /// Extra delivery attempts after the first one fails.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after, up to
+    /// five minutes.
+    pub retry_backoff: Duration,
+}
+
+impl NotifierSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            webhooks: config
+                .notify_webhooks
+                .iter()
+                .map(|webhook| webhook.expose().clone())
+                .collect(),
+            services: config.notify_services.clone(),
+            min_interval: Duration::from_secs(config.notify_min_interval_secs),
+            max_retries: config.notify_max_retries,
+            retry_backoff: Duration::from_millis(config.notify_retry_backoff_ms),
+        }
+    }
+
+    fn wants(&self, service: &str) -> bool {
+        self.services.is_empty() || self.services.iter().any(|s| s == service)
+    }
+}
+
+/// JSON body POSTed to each webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub service: String,
+    pub previous_status: &'static str,
+    pub status: &'static str,
+    pub message: Option<String>,
+    pub timestamp_ms: u64,
+}
+
+struct Tracked {
+    name: String,
+    /// Status last notified about, or first seen.
+    reported: ServiceStatus,
+    current: ServiceStatus,
+    message: Option<String>,
+    last_sent: Option<Instant>,
+}
+
+impl Tracked {
+    fn new(health: &ServiceHealth) -> Self {
+        Self {
+            name: health.name.clone(),
+            reported: health.status,
+            current: health.status,
+            message: health.message.clone(),
+            last_sent: None,
+        }
+    }
+
+    /// When a pending change may be notified; `None` if nothing changed.
+    fn ready_at(&self, min_interval: Duration, now: Instant) -> Option<Instant> {
+        if self.current == self.reported {
+            return None;
+        }
+        Some(self.last_sent.map_or(now, |sent| sent + min_interval))
+    }
+}
+
+/// Turns health events into status-change notifications, applying the
+/// service filter and rate limit. Delivery happens in `run`.
+pub struct Notifier {
+    settings: NotifierSettings,
+    services: HashMap<Uuid, Tracked>,
+}
+
+impl Notifier {
+    pub fn new(settings: NotifierSettings) -> Self {
+        Self {
+            settings,
+            services: HashMap::new(),
+        }
+    }
+
+    /// Applies an event and returns the notification to send now, if any.
+    pub fn handle(&mut self, event: &HealthEvent, now: Instant) -> Option<Notification> {
+        match event {
+            HealthEvent::Registered(h) => {
+                self.services.insert(h.id, Tracked::new(h));
+                None
+            }
+            HealthEvent::Deregistered(h) => {
+                self.services.remove(&h.id);
+                None
+            }
+            HealthEvent::Changed(h) => self.observe(h, now),
+            HealthEvent::ServerOverride(_) => None,
+        }
+    }
+
+    fn observe(&mut self, health: &ServiceHealth, now: Instant) -> Option<Notification> {
+        let tracked = self
+            .services
+            .entry(health.id)
+            .or_insert_with(|| Tracked::new(health));
+        tracked.current = health.status;
+        tracked.message = health.message.clone();
+        let ready_at = tracked.ready_at(self.settings.min_interval, now)?;
+        if ready_at > now || !self.settings.wants(&health.name) {
+            return None;
+        }
+        Some(Self::take(health.id, tracked, now))
+    }
+
+    /// Notifications held back by the rate limit whose interval has passed.
+    pub fn flush(&mut self, now: Instant) -> Vec<Notification> {
+        let min_interval = self.settings.min_interval;
+        let settings = &self.settings;
+        self.services
+            .iter_mut()
+            .filter(|(_, t)| settings.wants(&t.name))
+            .filter(|(_, t)| t.ready_at(min_interval, now).is_some_and(|at| at <= now))
+            .map(|(id, t)| Self::take(*id, t, now))
+            .collect()
+    }
+
+    /// When the next held-back notification becomes due.
+    pub fn next_due(&self, now: Instant) -> Option<Instant> {
+        self.services
+            .values()
+            .filter(|t| self.settings.wants(&t.name))
+            .filter_map(|t| t.ready_at(self.settings.min_interval, now))
+            .min()
+    }
+
+    fn take(id: Uuid, tracked: &mut Tracked, now: Instant) -> Notification {
+        let notification = Notification {
+            id,
+            service: tracked.name.clone(),
+            previous_status: tracked.reported.as_str(),
+            status: tracked.current.as_str(),
+            message: tracked.message.clone(),
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        };
+        tracked.reported = tracked.current;
+        tracked.last_sent = Some(now);
+        notification
+    }
+
+    /// Starts tracking services registered before the notifier.
+    fn seed(&mut self, services: &[ServiceHealth]) {
+        for health in services {
+            self.services
+                .entry(health.id)
+                .or_insert_with(|| Tracked::new(health));
+        }
+    }
+
+    async fn run(mut self, state: Arc<AppState>, mut events: broadcast::Receiver<HealthEvent>) {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .expect("failed to build webhook client");
+        self.seed(&state.health().list().await);
+
+        loop {
+            let due = self.next_due(Instant::now());
+            let pending = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.handle(&event, Instant::now()).into_iter().collect(),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "notifier lagged, resyncing health statuses");
+                        let now = Instant::now();
+                        state
+                            .health()
+                            .list()
+                            .await
+                            .iter()
+                            .filter_map(|h| self.observe(h, now))
+                            .collect()
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = tokio::time::sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {
+                    self.flush(Instant::now())
+                }
+            };
+
+            for notification in pending {
+                tracing::info!(
+                    service = %notification.service,
+                    from = notification.previous_status,
+                    to = notification.status,
+                    "sending health notification"
+                );
+                for url in &self.settings.webhooks {
+                    tokio::spawn(deliver(
+                        client.clone(),
+                        url.clone(),
+                        notification.clone(),
+                        self.settings.max_retries,
+                        self.settings.retry_backoff,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// POSTs a notification, retrying with exponential backoff on errors and
+/// non-2xx responses. Returns whether it was accepted. Webhook URLs can
+/// carry tokens, so only their scheme and host are logged.
+pub async fn deliver(
+    client: reqwest::Client,
+    url: String,
+    notification: Notification,
+    max_retries: u32,
+    backoff: Duration,
+) -> bool {
+    let webhook = redact::scheme_and_host(&url);
+    for attempt in 0..=max_retries {
+        if attempt > 0 {
+            tokio::time::sleep(retry_delay(backoff, attempt)).await;
+        }
+        let error = match client.post(&url).json(&notification).send().await {
+            Ok(resp) if resp.status().is_success() => return true,
+            Ok(resp) => format!("status {}", resp.status()),
+            Err(err) => err.without_url().to_string(),
+        };
+        tracing::warn!(%webhook, attempt, %error, "webhook delivery failed");
+    }
+    tracing::error!(%webhook, service = %notification.service, "giving up on webhook delivery");
+    false
+}
+
+/// How long to wait before retry number `attempt`, counting from 1.
+fn retry_delay(backoff: Duration, attempt: u32) -> Duration {
+    backoff
+        .saturating_mul(2u32.saturating_pow(attempt - 1))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Subscribes to health events and notifies the configured webhooks.
+/// Subscribe before registering services so their first status is known.
+pub fn spawn(state: Arc<AppState>, settings: NotifierSettings) -> JoinHandle<()> {
+    let events = state.health().subscribe();
+    tracing::info!(
+        webhooks = settings.webhooks.len(),
+        "health notifier started"
+    );
+    tokio::spawn(Notifier::new(settings).run(state, events))
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/notifier.rs"]
+mod tests;
@@ -0,0 +1,339 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::types::Json;
+use sqlx::{Connection, PgConnection, PgPool};
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use super::error::{AppError, AppResult};
+use super::health::{HealthCheckFn, RegisterOptions};
+use super::redact::Secret;
+use super::state::AppState;
+
+/// What a runtime probe checks. Stored as JSON alongside the definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProbeKind {
+    /// Opens a TCP connection to `host:port`.
+    Tcp { address: String },
+    /// Sends a GET and expects `expected_status` back.
+    Http { url: String, expected_status: u16 },
+    /// Resolves `host` to at least one address.
+    Dns { host: String },
+    /// Runs `query` in a read-only transaction, against the server's own
+    /// database unless `database_url` is set.
+    Sql {
+        query: String,
+        #[serde(serialize_with = "expose_url")]
+        database_url: Option<Secret<String>>,
+    },
+}
+
+/// The stored definition needs the real URL to run the probe after a
+/// restart; `Secret` alone would serialize as `[REDACTED]`.
+fn expose_url<S: Serializer>(url: &Option<Secret<String>>, s: S) -> Result<S::Ok, S::Error> {
+    url.as_ref().map(Secret::expose).serialize(s)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeSpec {
+    pub name: String,
+    pub kind: ProbeKind,
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub failure_threshold: u32,
+    pub success_threshold: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeDefinition {
+    /// Also the id of the health service the probe is registered as.
+    pub id: Uuid,
+    pub spec: ProbeSpec,
+}
+
+impl ProbeKind {
+    pub fn validate(&self) -> AppResult<()> {
+        match self {
+            Self::Tcp { address } => {
+                let port = address
+                    .rsplit_once(':')
+                    .map(|(_, port)| port.parse::<u16>());
+                if !matches!(port, Some(Ok(_))) {
+                    return Err(invalid(format!("tcp address must be host:port: {address}")));
+                }
+            }
+            Self::Http {
+                url,
+                expected_status,
+            } => {
+                let parsed: url::Url = url
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid url: {url}")))?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err(invalid(format!("url must be http or https: {url}")));
+                }
+                if !(100..=599).contains(expected_status) {
+                    return Err(invalid(format!(
+                        "invalid expected status: {expected_status}"
+                    )));
+                }
+            }
+            Self::Dns { host } => {
+                if host.trim().is_empty() {
+                    return Err(invalid("dns host is required".into()));
+                }
+            }
+            Self::Sql {
+                query,
+                database_url,
+            } => {
+                if query.trim().is_empty() {
+                    return Err(invalid("sql query is required".into()));
+                }
+                if let Some(database_url) = database_url
+                    && database_url.expose().parse::<url::Url>().is_err()
+                {
+                    return Err(invalid("invalid sql database url".into()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the check the registry runs. The registry applies the probe
+    /// timeout, so the checks themselves don't.
+    pub fn check(&self, db: PgPool) -> HealthCheckFn {
+        match self.clone() {
+            Self::Tcp { address } => Box::new(move || {
+                let address = address.clone();
+                Box::pin(async move {
+                    tokio::net::TcpStream::connect(&address)
+                        .await
+                        .map(drop)
+                        .map_err(|e| format!("tcp connect to {address}: {e}"))
+                })
+            }),
+            Self::Http {
+                url,
+                expected_status,
+            } => {
+                let client = reqwest::Client::new();
+                Box::new(move || {
+                    let request = client.get(&url);
+                    Box::pin(async move {
+                        let status = request
+                            .send()
+                            .await
+                            .map_err(|e| format!("http request failed: {e}"))?
+                            .status();
+                        if status.as_u16() == expected_status {
+                            Ok(())
+                        } else {
+                            Err(format!("http status {status}, expected {expected_status}"))
+                        }
+                    })
+                })
+            }
+            Self::Dns { host } => Box::new(move || {
+                let host = host.clone();
+                Box::pin(async move {
+                    let mut addrs = tokio::net::lookup_host((host.as_str(), 0))
+                        .await
+                        .map_err(|e| format!("dns lookup of {host}: {e}"))?;
+                    match addrs.next() {
+                        Some(_) => Ok(()),
+                        None => Err(format!("dns lookup of {host} returned no addresses")),
+                    }
+                })
+            }),
+            Self::Sql {
+                query,
+                database_url,
+            } => Box::new(move || {
+                let query = query.clone();
+                let database_url = database_url.clone();
+                let db = db.clone();
+                Box::pin(async move {
+                    let result = match database_url {
+                        Some(url) => {
+                            let mut conn = PgConnection::connect(url.expose())
+                                .await
+                                .map_err(|e| format!("sql connect failed: {e}"))?;
+                            let result = run_read_only(&mut conn, &query).await;
+                            let _ = conn.close().await;
+                            result
+                        }
+                        None => {
+                            let mut conn = db
+                                .acquire()
+                                .await
+                                .map_err(|e| format!("sql connect failed: {e}"))?;
+                            run_read_only(&mut conn, &query).await
+                        }
+                    };
+                    result.map_err(|e| format!("sql query failed: {e}"))
+                })
+            }),
+        }
+    }
+}
+
+async fn run_read_only(conn: &mut PgConnection, query: &str) -> Result<(), sqlx::Error> {
+    let mut tx = conn.begin().await?;
+    sqlx::query("SET TRANSACTION READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(query).fetch_optional(&mut *tx).await?;
+    tx.rollback().await
+}
+
+fn invalid(message: String) -> AppError {
+    AppError::InvalidArgument(message)
+}
+
+impl ProbeSpec {
+    pub fn validate(&self) -> AppResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(invalid("name is required".into()));
+        }
+        // Stored in whole milliseconds.
+        if self.interval < Duration::from_millis(1) {
+            return Err(invalid("interval must be at least 1ms".into()));
+        }
+        if self.timeout < Duration::from_millis(1) {
+            return Err(invalid("timeout must be at least 1ms".into()));
+        }
+        if self.failure_threshold == 0 || self.success_threshold == 0 {
+            return Err(invalid("thresholds must be at least 1".into()));
+        }
+        self.kind.validate()
+    }
+}
+
+/// Registers a stored probe with the health registry under its own id.
+async fn start(state: &AppState, def: &ProbeDefinition) -> AppResult<Uuid> {
+    let spec = &def.spec;
+    state
+        .health()
+        .register_with_options(
+            spec.name.clone(),
+            spec.interval,
+            None,
+            spec.kind.check(state.db().clone()),
+            RegisterOptions {
+                failure_threshold: spec.failure_threshold,
+                success_threshold: spec.success_threshold,
+                timeout: spec.timeout,
+                id: Some(def.id),
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+/// Validates and stores a probe, then starts probing it. The first probe
+/// runs inline, so the row is committed before starting rather than holding
+/// a transaction open; a probe that can't start is deleted again, so it
+/// isn't retried on every boot.
+pub async fn create(state: &AppState, spec: ProbeSpec) -> AppResult<ProbeDefinition> {
+    spec.validate()?;
+
+    let id = sqlx::query_scalar!(
+        r#"INSERT INTO health_probes
+               (name, kind, interval_ms, timeout_ms, failure_threshold, success_threshold)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING id"#,
+        spec.name,
+        Json(&spec.kind) as _,
+        spec.interval.as_millis() as i64,
+        spec.timeout.as_millis() as i64,
+        spec.failure_threshold as i32,
+        spec.success_threshold as i32,
+    )
+    .fetch_one(state.db())
+    .await?;
+
+    let def = ProbeDefinition { id, spec };
+    if let Err(err) = start(state, &def).await {
+        if let Err(cleanup) = sqlx::query!("DELETE FROM health_probes WHERE id = $1", id)
+            .execute(state.db())
+            .await
+        {
+            tracing::warn!(%id, error = %cleanup, "failed to remove health probe that didn't start");
+        }
+        return Err(err);
+    }
+    tracing::info!(probe = %def.spec.name, %id, "health probe created");
+    Ok(def)
+}
+
+/// Stops and forgets a stored probe.
+pub async fn delete(state: &AppState, id: &Uuid) -> AppResult<()> {
+    let deleted = sqlx::query!("DELETE FROM health_probes WHERE id = $1", id)
+        .execute(state.db())
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(AppError::NotFound(format!("unknown health probe: {id}")));
+    }
+
+    state.health().deregister(id).await;
+    tracing::info!(%id, "health probe deleted");
+    Ok(())
+}
+
+pub async fn list(db: &PgPool) -> AppResult<Vec<ProbeDefinition>> {
+    let rows = sqlx::query!(
+        r#"SELECT id, name, kind as "kind: Json<ProbeKind>", interval_ms, timeout_ms,
+                  failure_threshold, success_threshold
+           FROM health_probes
+           ORDER BY name, created_at"#
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ProbeDefinition {
+            id: row.id,
+            spec: ProbeSpec {
+                name: row.name,
+                kind: row.kind.0,
+                interval: Duration::from_millis(row.interval_ms as u64),
+                timeout: Duration::from_millis(row.timeout_ms as u64),
+                failure_threshold: row.failure_threshold as u32,
+                success_threshold: row.success_threshold as u32,
+            },
+        })
+        .collect())
+}
+
+/// Starts every stored probe. First probes run concurrently so a slow
+/// target doesn't hold up startup for the others.
+pub async fn restore(state: &Arc<AppState>) -> AppResult<usize> {
+    let defs = list(state.db()).await?;
+    let mut tasks = JoinSet::new();
+    for def in defs {
+        let state = Arc::clone(state);
+        tasks.spawn(async move { (start(&state, &def).await, def) });
+    }
+
+    let mut started = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let (result, def) = joined.map_err(|e| AppError::Internal(e.to_string()))?;
+        match result {
+            Ok(_) => started += 1,
+            Err(err) => {
+                tracing::error!(probe = %def.spec.name, id = %def.id, %err, "failed to restore health probe")
+            }
+        }
+    }
+    Ok(started)
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/probes.rs"]
+mod tests;
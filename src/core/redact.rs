@@ -0,0 +1,407 @@
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::fmt;
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::field::{DebugValue, Field, FieldSet, Value, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Field names masked by default, matched case-insensitively and by suffix.
+pub const DEFAULT_FIELDS: &str = "password,passwd,secret,token,api_key,authorization,cookie";
+
+/// A value that must not end up in logs: `Debug`, `Display` and
+/// `Serialize` give `[REDACTED]`. Use `expose` where the real value is
+/// needed.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+#[allow(dead_code)]
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+/// `url` without its password, to show which database or endpoint a
+/// secret URL points at. A value that isn't a URL is masked entirely.
+pub fn without_password(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) => {
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        }
+        Err(_) => REDACTED.to_owned(),
+    }
+}
+
+/// Only the scheme and host of `url`, to name an endpoint whose path or
+/// query may carry a token. A value that isn't a URL is masked entirely.
+pub fn scheme_and_host(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) => format!("{}://{}", parsed.scheme(), parsed.host_str().unwrap_or("")),
+        Err(_) => REDACTED.to_owned(),
+    }
+}
+
+/// Masks the values of sensitive fields and the credentials in URLs, in
+/// log fields and in text such as the config file.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    url: Regex,
+    fields: Option<Regex>,
+    /// Lowercased field names whose values are always masked.
+    names: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new<S: AsRef<str>>(fields: &[S]) -> Self {
+        let url = Regex::new(r#"(?P<scheme>[A-Za-z][A-Za-z0-9+.-]*://)(?P<user>[^:@/\s"'<>]*)(?P<pass>:[^@/\s"'<>]*)?@"#)
+            .expect("valid URL pattern");
+
+        let names: Vec<String> = fields
+            .iter()
+            .map(|f| f.as_ref().trim().to_lowercase())
+            .filter(|f| !f.is_empty())
+            .collect();
+        let fields = (!names.is_empty()).then(|| {
+            let names = names
+                .iter()
+                .map(|n| regex::escape(n))
+                .collect::<Vec<_>>()
+                .join("|");
+            // `key=value` where the key is a name, or ends in `_name`,
+            // `.name` or `-name`. The character before the key is
+            // captured as there is no lookbehind.
+            Regex::new(&format!(
+                r#"(?i)(?P<key>(?:^|[^\w.-])(?:[\w.-]*[_.-])?(?:{names}))(?P<sep>\s*=\s*)(?:(?P<quoted>"(?:[^"\\]|\\.)*")|[^\s,;}}\]]+)"#
+            ))
+            .expect("valid field pattern")
+        });
+
+        Self { url, fields, names }
+    }
+
+    /// Masks URL credentials and the values of `key=value` pairs whose key
+    /// is sensitive.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let text = self
+            .url
+            .replace_all(text, |caps: &Captures| match caps.name("pass") {
+                Some(_) => format!("{}{}:{REDACTED}@", &caps["scheme"], &caps["user"]),
+                None => format!("{}{REDACTED}@", &caps["scheme"]),
+            });
+        let Some(fields) = &self.fields else {
+            return text;
+        };
+        match fields.replace_all(&text, |caps: &Captures| {
+            let value = match caps.name("quoted") {
+                Some(_) => format!("\"{REDACTED}\""),
+                None => REDACTED.to_owned(),
+            };
+            format!("{}{}{value}", &caps["key"], &caps["sep"])
+        }) {
+            Cow::Borrowed(_) => text,
+            Cow::Owned(redacted) => Cow::Owned(redacted),
+        }
+    }
+
+    /// Whether the value of a field called `name` is masked whole: it is
+    /// one of the names, or ends in one after a `_`, `.` or `-`, so
+    /// `db_password` is masked but `password_hint` isn't.
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.names.iter().any(|n| {
+            name.strip_suffix(n.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.ends_with(['_', '.', '-']))
+        })
+    }
+
+    /// Wraps `inner` so that it sees every event and span with sensitive
+    /// fields masked and `redact` applied to the other text values.
+    pub fn layer<L>(&self, inner: L) -> RedactingLayer<L> {
+        RedactingLayer {
+            redactor: self.clone(),
+            inner,
+        }
+    }
+
+    /// The values `record` visits, masked, or `None` if nothing needed
+    /// masking.
+    fn mask(
+        &self,
+        fields: &FieldSet,
+        record: impl FnOnce(&mut dyn Visit),
+    ) -> Option<Vec<Option<Owned>>> {
+        let mut visitor = Masking {
+            redactor: self,
+            values: (0..fields.len()).map(|_| None).collect(),
+            masked: false,
+        };
+        record(&mut visitor);
+        visitor.masked.then_some(visitor.values)
+    }
+}
+
+/// A field value copied out of an event so that it can be recorded again.
+enum Owned {
+    Str(String),
+    Debug(DebugValue<Verbatim>),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Bool(bool),
+}
+
+impl Owned {
+    fn as_value(&self) -> &dyn Value {
+        match self {
+            Self::Str(v) => v,
+            Self::Debug(v) => v,
+            Self::I64(v) => v,
+            Self::U64(v) => v,
+            Self::I128(v) => v,
+            Self::U128(v) => v,
+            Self::F64(v) => v,
+            Self::Bool(v) => v,
+        }
+    }
+}
+
+/// Already formatted text, recorded like the `?value` it came from.
+struct Verbatim(String);
+
+impl fmt::Debug for Verbatim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+struct Masking<'a> {
+    redactor: &'a Redactor,
+    values: Vec<Option<Owned>>,
+    masked: bool,
+}
+
+impl Masking<'_> {
+    fn set(&mut self, field: &Field, value: Owned) {
+        let value = if self.redactor.is_sensitive(field.name()) {
+            self.masked = true;
+            Owned::Str(REDACTED.to_owned())
+        } else {
+            value
+        };
+        self.values[field.index()] = Some(value);
+    }
+
+    fn set_text(&mut self, field: &Field, text: String, wrap: fn(String) -> Owned) {
+        let text = match self.redactor.redact(&text) {
+            Cow::Borrowed(_) => text,
+            Cow::Owned(redacted) => {
+                self.masked = true;
+                redacted
+            }
+        };
+        self.set(field, wrap(text));
+    }
+}
+
+impl Visit for Masking<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set_text(field, value.to_owned(), Owned::Str);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.set_text(field, format!("{value:?}"), |text| {
+            Owned::Debug(tracing::field::debug(Verbatim(text)))
+        });
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, Owned::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, Owned::U64(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.set(field, Owned::I128(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.set(field, Owned::U128(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, Owned::F64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, Owned::Bool(value));
+    }
+}
+
+fn as_values(values: &[Option<Owned>]) -> Vec<Option<&dyn Value>> {
+    values
+        .iter()
+        .map(|v| v.as_ref().map(Owned::as_value))
+        .collect()
+}
+
+/// See `Redactor::layer`. Events and spans with nothing to mask reach
+/// `inner` untouched; the others are rebuilt from the masked values, the
+/// way the `tracing` macros build them.
+pub struct RedactingLayer<L> {
+    redactor: Redactor,
+    inner: L,
+}
+
+impl<S, L> Layer<S> for RedactingLayer<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S>,
+{
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        let Some(values) = self.redactor.mask(metadata.fields(), |v| attrs.record(v)) else {
+            return self.inner.on_new_span(attrs, id, ctx);
+        };
+        let values = as_values(&values);
+        let values = metadata.fields().value_set_all(&values);
+        let attrs = match attrs.parent() {
+            _ if attrs.is_contextual() => Attributes::new(metadata, &values),
+            Some(parent) => Attributes::child_of(parent.clone(), metadata, &values),
+            None => Attributes::new_root(metadata, &values),
+        };
+        self.inner.on_new_span(&attrs, id, ctx);
+    }
+
+    fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(metadata) = ctx.metadata(span) else {
+            return self.inner.on_record(span, values, ctx);
+        };
+        let Some(masked) = self.redactor.mask(metadata.fields(), |v| values.record(v)) else {
+            return self.inner.on_record(span, values, ctx);
+        };
+        let masked = as_values(&masked);
+        let masked = metadata.fields().value_set_all(&masked);
+        self.inner.on_record(span, &Record::new(&masked), ctx);
+    }
+
+    fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let Some(values) = self.redactor.mask(metadata.fields(), |v| event.record(v)) else {
+            return self.inner.on_event(event, ctx);
+        };
+        let values = as_values(&values);
+        let values = metadata.fields().value_set_all(&values);
+        let event = if event.is_contextual() {
+            Event::new(metadata, &values)
+        } else {
+            Event::new_child_of(event.parent().cloned(), metadata, &values)
+        };
+        self.inner.on_event(&event, ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else {
+            // SAFETY: forwarded to the inner layer, which upholds the
+            // same contract.
+            unsafe { self.inner.downcast_raw(id) }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/redact.rs"]
+mod tests;
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use super::config::{Config, ConfigChange, ConfigErrors};
+use super::state::AppState;
+
+/// Settings a reload applies to the running server. Authentication, the
+/// CORS layer, request timeout and shutdown drain read the config per use,
+/// and the log filter and database connection options are swapped by
+/// `AppState::update_config`; everything else is captured at startup.
+pub const LIVE_KEYS: &[&str] = &[
+    "auth_jwt_secret",
+    "auth_jwks",
+    "auth_jwks_refresh_secs",
+    "auth_issuer",
+    "auth_audience",
+    "auth_public",
+    "auth_authenticated",
+    "authz_source",
+    "authz_rules",
+    "authz_role_claims",
+    "authz_refresh_secs",
+    "log_level",
+    "cors_origins",
+    "database_url",
+    "request_timeout_secs",
+    "shutdown_drain_secs",
+];
+
+/// What a reload changed, split by whether it is already in effect.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    pub applied: Vec<ConfigChange>,
+    pub restart_required: Vec<ConfigChange>,
+}
+
+impl ReloadReport {
+    pub fn new(running: &Config, loaded: &Config) -> Self {
+        let (applied, restart_required) = running
+            .diff(loaded)
+            .into_iter()
+            .partition(|change| LIVE_KEYS.contains(&change.key));
+        Self {
+            applied,
+            restart_required,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.restart_required.is_empty()
+    }
+
+    fn log(&self) {
+        if self.is_empty() {
+            tracing::info!("config reloaded, nothing changed");
+            return;
+        }
+        for change in &self.applied {
+            tracing::info!(key = change.key, old = %change.old, new = %change.new, "config change applied");
+        }
+        for change in &self.restart_required {
+            tracing::warn!(key = change.key, old = %change.old, new = %change.new, "config change needs a restart");
+        }
+    }
+}
+
+/// The running config with the live settings taken from `loaded`. The
+/// rest keep their running values, so `AppState::config` describes what
+/// is in effect and later reloads keep reporting them until a restart.
+pub fn apply_live(running: &Config, loaded: &Config) -> Config {
+    let mut next = running.clone();
+    next.auth_jwt_secret = loaded.auth_jwt_secret.clone();
+    next.auth_jwks = loaded.auth_jwks.clone();
+    next.auth_jwks_refresh_secs = loaded.auth_jwks_refresh_secs;
+    next.auth_issuer = loaded.auth_issuer.clone();
+    next.auth_audience = loaded.auth_audience.clone();
+    next.auth_public = loaded.auth_public.clone();
+    next.auth_authenticated = loaded.auth_authenticated.clone();
+    next.authz_source = loaded.authz_source.clone();
+    next.authz_rules = loaded.authz_rules.clone();
+    next.authz_role_claims = loaded.authz_role_claims.clone();
+    next.authz_refresh_secs = loaded.authz_refresh_secs;
+    next.log_level = loaded.log_level.clone();
+    next.cors_origins = loaded.cors_origins.clone();
+    next.database_url = loaded.database_url.clone();
+    next.request_timeout_secs = loaded.request_timeout_secs;
+    next.shutdown_drain_secs = loaded.shutdown_drain_secs;
+    next
+}
+
+/// Re-reads config, secrets included, from the sources used at startup.
+/// Invalid config is rejected whole and the running config kept.
+pub fn reload(state: &AppState) -> Result<ReloadReport, ConfigErrors> {
+    let loaded = Config::load_with(&state.args(), state.secret_providers())?;
+    Ok(apply(state, &loaded))
+}
+
+/// Applies the live settings of an already validated config, and re-reads
+/// the authorization policy whether or not they changed.
+pub fn apply(state: &AppState, loaded: &Config) -> ReloadReport {
+    let running = state.config();
+    let report = ReloadReport::new(&running, loaded);
+    if !report.applied.is_empty() {
+        state.update_config(apply_live(&running, loaded));
+    }
+    state.reload_policy();
+    report.log();
+    report
+}
+
+/// Reloads config on every SIGHUP.
+#[cfg(unix)]
+pub fn watch_sighup(state: Arc<AppState>) -> anyhow::Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            tracing::info!("received SIGHUP, reloading config");
+            if let Err(err) = reload(&state) {
+                tracing::error!(%err, "config reload rejected");
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/reload.rs"]
+mod tests;
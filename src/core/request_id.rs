@@ -0,0 +1,151 @@
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::body::{Body, Bytes};
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use http_body::{Frame, SizeHint};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: RequestId;
+}
+
+/// Identifies one request in logs, responses and error details. Taken from
+/// the client's `x-request-id` when it is well formed, generated otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+#[allow(dead_code)]
+impl RequestId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// Accepts 1 to 128 ASCII letters, digits, `-`, `_`, `.` or `:`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_LEN
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'));
+        valid.then(|| Self(value.to_owned()))
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let supplied = headers.get(REQUEST_ID_HEADER);
+        match supplied.and_then(|v| v.to_str().ok()).and_then(Self::parse) {
+            Some(id) => id,
+            None => {
+                if supplied.is_some() {
+                    tracing::debug!("ignoring malformed x-request-id");
+                }
+                Self::new()
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The id of the request being handled on this task, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Runs `f` with this id as `current()`.
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        CURRENT.scope(self, f).await
+    }
+
+    fn header_value(&self) -> HeaderValue {
+        HeaderValue::from_str(&self.0).expect("request ids are valid header values")
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Assigns the request its id before anything else sees it: the id is
+/// stored in the request extensions, where tonic passes it on to handlers,
+/// and replaces the `x-request-id` header.
+pub fn assign<B>(mut req: http::Request<B>) -> http::Request<B> {
+    let id = RequestId::from_headers(req.headers());
+    req.headers_mut()
+        .insert(REQUEST_ID_HEADER, id.header_value());
+    req.extensions_mut().insert(id);
+    req
+}
+
+/// Returns the request id in the response headers and, for gRPC, the
+/// trailers. The id is also `RequestId::current()` while the request is
+/// handled, so errors converted without the request still carry it.
+pub async fn propagate(req: Request, next: Next) -> Response {
+    let req = match req.extensions().get::<RequestId>() {
+        Some(_) => req,
+        None => assign(req),
+    };
+    let id = req.extensions().get::<RequestId>().cloned().unwrap();
+    let value = id.header_value();
+
+    let response = id.scope(next.run(req)).await;
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert(REQUEST_ID_HEADER, value.clone());
+    Response::from_parts(parts, Body::new(WithTrailer { inner: body, value }))
+}
+
+/// Adds the request id to the trailers of a body that sends any.
+struct WithTrailer {
+    inner: Body,
+    value: HeaderValue,
+}
+
+impl http_body::Body for WithTrailer {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+        let value = self.value.clone();
+        Pin::new(&mut self.inner)
+            .poll_frame(cx)
+            .map_ok(|frame| match frame.into_trailers() {
+                Ok(mut trailers) => {
+                    trailers.insert(REQUEST_ID_HEADER, value);
+                    Frame::trailers(trailers)
+                }
+                Err(frame) => frame,
+            })
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/request_id.rs"]
+mod tests;
@@ -0,0 +1,62 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use super::config::env_name;
+use super::redact::Secret;
+
+/// A source of setting values kept out of the config file and the plain
+/// environment, such as mounted secret files or a secrets manager.
+/// Providers are asked again on every load, so a reload picks up rotated
+/// values.
+pub trait SecretProvider: Send + Sync {
+    /// Where this provider reads `key` from, for error messages.
+    fn describe(&self, key: &str) -> String;
+
+    /// The value of setting `key` (a name from `KEYS`), or `None` if this
+    /// provider doesn't set it.
+    fn fetch(&self, key: &str) -> Result<Option<Secret<String>>, String>;
+}
+
+/// Reads a setting from the file named by its environment variable with
+/// `_FILE` appended, e.g. `DATABASE_URL_FILE=/run/secrets/database_url`,
+/// as Docker and Kubernetes mount secrets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSecrets;
+
+impl SecretProvider for FileSecrets {
+    fn describe(&self, key: &str) -> String {
+        format!("environment variable {}", file_var(key))
+    }
+
+    fn fetch(&self, key: &str) -> Result<Option<Secret<String>>, String> {
+        let Some(path) = std::env::var_os(file_var(key)).filter(|p| !p.is_empty()) else {
+            return Ok(None);
+        };
+        let plain = env_name(key);
+        if std::env::var_os(&plain).is_some() {
+            return Err(format!("{plain} is set as well, set only one of them"));
+        }
+        read_file(Path::new(&path)).map(Some)
+    }
+}
+
+/// The providers used when none are given: `*_FILE` variables.
+pub fn defaults() -> Vec<Arc<dyn SecretProvider>> {
+    vec![Arc::new(FileSecrets)]
+}
+
+/// A secret file's contents without trailing whitespace, since files are
+/// usually written with a final newline.
+pub fn read_file(path: &Path) -> Result<Secret<String>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    Ok(Secret::new(text.trim_end().to_owned()))
+}
+
+fn file_var(key: &str) -> String {
+    format!("{}_FILE", env_name(key))
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/secrets.rs"]
+mod tests;
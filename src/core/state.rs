@@ -1,27 +1,58 @@
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use arc_swap::{ArcSwap, Guard};
 use sqlx::PgPool;
+use tokio::sync::Notify;
 
-use super::config::Config;
-use super::health::HealthRegistry;
+use super::auth::Authenticator;
+use super::authz::Policy;
+use super::config::{Args, Config, ConfigErrors};
+use super::db;
+use super::health::{HealthRegistry, HealthSettings};
+use super::logging::LogFilter;
+use super::metrics::Metrics;
+use super::reload::{self, ReloadReport};
+use super::secrets::{self, SecretProvider};
 
 #[allow(dead_code)]
 pub struct AppState {
     config: ArcSwap<Config>,
     db: PgPool,
     health: HealthRegistry,
+    metrics: Metrics,
+    authenticator: Authenticator,
+    policy: ArcSwap<Policy>,
+    policy_reload: Notify,
+    log_filter: OnceLock<LogFilter>,
+    args: OnceLock<Args>,
+    secret_providers: OnceLock<Vec<Arc<dyn SecretProvider>>>,
     started_at: Instant,
 }
 
 #[allow(dead_code)]
 impl AppState {
     pub fn new(config: Config, db: PgPool) -> Arc<Self> {
+        let health = HealthRegistry::with_settings(HealthSettings {
+            history_capacity: config.health_history_size,
+            flap_window: Duration::from_secs(config.health_flap_window_secs),
+            flap_threshold: config.health_flap_threshold,
+        });
+        let policy = match config.authz_source.as_str() {
+            "config" => Policy::from_config(&config),
+            _ => Policy::default(),
+        };
         Arc::new(Self {
             config: ArcSwap::from_pointee(config),
             db,
-            health: HealthRegistry::new(),
+            health,
+            metrics: Metrics::new(),
+            authenticator: Authenticator::new(),
+            policy: ArcSwap::from_pointee(policy),
+            policy_reload: Notify::new(),
+            log_filter: OnceLock::new(),
+            args: OnceLock::new(),
+            secret_providers: OnceLock::new(),
             started_at: Instant::now(),
         })
     }
@@ -38,13 +69,89 @@ impl AppState {
         &self.health
     }
 
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    pub fn authenticator(&self) -> &Authenticator {
+        &self.authenticator
+    }
+
+    pub fn policy(&self) -> Arc<Policy> {
+        self.policy.load_full()
+    }
+
+    pub fn set_policy(&self, policy: Policy) {
+        self.policy.store(Arc::new(policy));
+    }
+
+    /// Rebuilds the policy from config, or has `authz::init`'s task read
+    /// the database again, depending on `authz_source`.
+    pub fn reload_policy(&self) {
+        match self.config().authz_source.as_str() {
+            "config" => self.set_policy(Policy::from_config(&self.config())),
+            "database" => self.policy_reload.notify_one(),
+            _ => {}
+        }
+    }
+
+    /// Resolves when `reload_policy` asks for the database to be read.
+    pub async fn policy_reload_requested(&self) {
+        self.policy_reload.notified().await;
+    }
+
+    /// Lets `update_config` and the admin RPCs change the log filter.
+    pub fn attach_log_filter(&self, filter: LogFilter) {
+        let _ = self.log_filter.set(filter);
+    }
+
+    pub fn log_filter(&self) -> Option<&LogFilter> {
+        self.log_filter.get()
+    }
+
+    /// The command line the config was loaded with, so reloads read the
+    /// same config file and overrides.
+    pub fn attach_args(&self, args: Args) {
+        let _ = self.args.set(args);
+    }
+
+    pub fn args(&self) -> Args {
+        self.args.get().cloned().unwrap_or_default()
+    }
+
+    /// Secret providers reloads read, when config wasn't loaded with the
+    /// defaults.
+    pub fn attach_secret_providers(&self, providers: Vec<Arc<dyn SecretProvider>>) {
+        let _ = self.secret_providers.set(providers);
+    }
+
+    pub fn secret_providers(&self) -> &[Arc<dyn SecretProvider>] {
+        self.secret_providers.get_or_init(secrets::defaults)
+    }
+
     pub fn update_config(&self, new_config: Config) {
+        if let Some(filter) = self.log_filter()
+            && new_config.log_level != self.config().log_level
+            && let Err(err) = filter.set_base(&new_config.log_level)
+        {
+            tracing::warn!(%err, "log level not updated");
+        }
+        if new_config.database_url != self.config().database_url
+            && let Err(err) = db::set_database_url(&self.db, new_config.database_url.expose())
+        {
+            tracing::warn!(%err, "database connection options not updated");
+        }
+        let policy_changed = new_config.authz_source != self.config().authz_source
+            || new_config.authz_rules != self.config().authz_rules;
         self.config.store(Arc::new(new_config));
+        if policy_changed {
+            self.reload_policy();
+        }
         tracing::info!("configuration updated at runtime");
     }
 
-    pub fn reload_config_from_env(&self) {
-        self.update_config(Config::from_env());
+    pub fn reload_config(&self) -> Result<ReloadReport, ConfigErrors> {
+        reload::reload(self)
     }
 
     pub fn started_at(&self) -> Instant {
@@ -0,0 +1,121 @@
+use anyhow::{Context as _, Result};
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::{TraceContextExt, TraceId, TracerProvider as _};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use tracing::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use super::config::Config;
+use super::redact;
+
+/// Response header carrying the trace id of the request's span.
+pub const TRACE_ID_HEADER: &str = "x-trace-id";
+
+/// OpenTelemetry tracer provider. Spans always get W3C trace ids so they
+/// can join the caller's trace; they are only exported when an OTLP
+/// endpoint is configured.
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+    exporting: bool,
+}
+
+#[allow(dead_code)]
+impl Telemetry {
+    /// Must be called inside the Tokio runtime, which drives the exporter.
+    pub fn init(config: &Config) -> Result<Self> {
+        let resource = Resource::builder()
+            .with_service_name(config.otel_service_name.clone())
+            .build();
+        let mut builder = SdkTracerProvider::builder().with_resource(resource);
+
+        if let Some(endpoint) = &config.otlp_endpoint {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint.expose())
+                .build()
+                .with_context(|| {
+                    format!(
+                        "invalid OTLP endpoint: {}",
+                        redact::scheme_and_host(endpoint.expose())
+                    )
+                })?;
+            builder = builder.with_batch_exporter(exporter);
+        }
+
+        Ok(Self {
+            provider: builder.build(),
+            exporting: config.otlp_endpoint.is_some(),
+        })
+    }
+
+    pub fn is_exporting(&self) -> bool {
+        self.exporting
+    }
+
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.provider.tracer("midnight"))
+    }
+
+    /// Exports any buffered spans. Call before the process exits.
+    pub fn shutdown(&self) {
+        if let Err(err) = self.provider.shutdown() {
+            tracing::warn!(%err, "failed to flush traces");
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Makes `span` a child of the trace in the request's `traceparent` and
+/// `tracestate` headers, if any, and records its `trace_id` field.
+pub fn link_span(span: &tracing::Span, headers: &HeaderMap) {
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    if parent.span().span_context().is_valid() {
+        let _ = span.set_parent(parent);
+    }
+    if let Some(trace_id) = trace_id(span) {
+        span.record("trace_id", tracing::field::display(trace_id));
+    }
+}
+
+pub fn trace_id(span: &tracing::Span) -> Option<TraceId> {
+    let trace_id = span.context().span().span_context().trace_id();
+    (trace_id != TraceId::INVALID).then_some(trace_id)
+}
+
+/// Adds the current span's trace id to the response headers.
+pub async fn trace_id_header(req: Request, next: Next) -> Response {
+    let trace_id = trace_id(&tracing::Span::current());
+    let mut response = next.run(req).await;
+    if let Some(trace_id) = trace_id
+        && let Ok(value) = HeaderValue::from_str(&trace_id.to_string())
+    {
+        response.headers_mut().insert(TRACE_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/telemetry.rs"]
+mod tests;
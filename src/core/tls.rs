@@ -0,0 +1,227 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use arc_swap::ArcSwap;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{self, RootCertStore, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+
+use super::audit::Principal;
+use super::config::Config;
+
+/// How often the certificate files are checked for changes.
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Connections that haven't finished the handshake by then are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsSettings {
+    /// PEM certificate chain, leaf first.
+    pub cert: PathBuf,
+    /// PEM private key for `cert`.
+    pub key: PathBuf,
+    /// When set, clients must present a certificate signed by one of the
+    /// CAs in this PEM file.
+    pub client_ca: Option<PathBuf>,
+    /// `1.2` or `1.3`.
+    pub min_version: String,
+}
+
+impl TlsSettings {
+    /// `None` unless both a certificate and a key are configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        Some(Self {
+            cert: config.tls_cert.as_ref()?.into(),
+            key: config.tls_key.as_ref()?.into(),
+            client_ca: config.tls_client_ca.as_ref().map(PathBuf::from),
+            min_version: config.tls_min_version.clone(),
+        })
+    }
+
+    /// Reads the certificate, key and client CA from disk.
+    pub fn load(&self) -> Result<ServerConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let versions: &[&rustls::SupportedProtocolVersion] = match self.min_version.as_str() {
+            "1.3" => &[&rustls::version::TLS13],
+            _ => rustls::ALL_VERSIONS,
+        };
+        let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_protocol_versions(versions)
+            .context("unsupported TLS version")?;
+
+        let builder = match &self.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(path)? {
+                    roots
+                        .add(cert)
+                        .with_context(|| format!("invalid client CA in {}", path.display()))?;
+                }
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .with_context(|| format!("invalid client CA in {}", path.display()))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let certs = read_certs(&self.cert)?;
+        let key = PrivateKeyDer::from_pem_file(&self.key)
+            .with_context(|| format!("failed to read TLS key {}", self.key.display()))?;
+        let mut config = builder
+            .with_single_cert(certs, key)
+            .context("TLS certificate and key don't match")?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+
+    /// Modification times of the files, to tell when they change.
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        [&self.cert, &self.key]
+            .into_iter()
+            .chain(&self.client_ca)
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("failed to read certificates from {}", path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("no certificates in {}", path.display());
+    }
+    Ok(certs)
+}
+
+/// The server's TLS config, replaced when the files it was read from
+/// change. Each handshake uses the config current when it starts, so open
+/// connections are unaffected by a reload.
+pub struct Tls {
+    settings: TlsSettings,
+    config: ArcSwap<ServerConfig>,
+    modified: Mutex<Vec<Option<SystemTime>>>,
+}
+
+#[allow(dead_code)]
+impl Tls {
+    pub fn new(settings: TlsSettings) -> Result<Arc<Self>> {
+        let modified = settings.modified();
+        let config = settings.load()?;
+        Ok(Arc::new(Self {
+            settings,
+            config: ArcSwap::from_pointee(config),
+            modified: Mutex::new(modified),
+        }))
+    }
+
+    pub fn settings(&self) -> &TlsSettings {
+        &self.settings
+    }
+
+    /// Reloads the files if any changed since the last check. If they
+    /// don't load, the current config is kept until they change again.
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let modified = self.settings.modified();
+        {
+            let mut last = self.modified.lock().unwrap();
+            if *last == modified {
+                return Ok(false);
+            }
+            *last = modified;
+        }
+        self.config.store(Arc::new(self.settings.load()?));
+        Ok(true)
+    }
+
+    /// Checks the files for changes every `interval`.
+    pub fn watch(self: Arc<Self>, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match self.reload_if_changed() {
+                    Ok(true) => tracing::info!("TLS certificates reloaded"),
+                    Ok(false) => {}
+                    Err(err) => tracing::error!("TLS certificates not reloaded: {err:#}"),
+                }
+            }
+        });
+    }
+
+    /// Connections accepted on `listener`, once their handshake is done.
+    /// Handshakes run concurrently, so a slow client doesn't hold up the
+    /// others.
+    pub fn incoming(
+        self: Arc<Self>,
+        listener: TcpListener,
+    ) -> ReceiverStream<io::Result<TlsStream<TcpStream>>> {
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            while !tx.is_closed() {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(err) => {
+                        // Usually out of file descriptors; give some back.
+                        tracing::warn!(%err, "failed to accept connection");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                let acceptor = tokio_rustls::TlsAcceptor::from(self.config.load_full());
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = tx.send(Ok(stream)).await;
+                        }
+                        Ok(Err(err)) => tracing::debug!(%peer, %err, "TLS handshake failed"),
+                        Err(_) => tracing::debug!(%peer, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+}
+
+/// Makes the subject of the client certificate, verified during the
+/// handshake, the request's `Principal`.
+pub async fn client_principal(mut req: Request, next: Next) -> Response {
+    let subject = req
+        .extensions()
+        .get::<TlsConnectInfo<TcpConnectInfo>>()
+        .and_then(TlsConnectInfo::peer_certs)
+        .and_then(|certs| certs.first().and_then(subject));
+    if let Some(subject) = subject {
+        req.extensions_mut()
+            .insert(Principal::certificate(&subject));
+    }
+    next.run(req).await
+}
+
+/// The subject of a DER certificate, e.g. `CN=client, O=Acme`.
+pub fn subject(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert).ok()?;
+    Some(cert.subject().to_string())
+}
+
+#[cfg(test)]
+#[path = "../../tests/core/tls.rs"]
+mod tests;
@@ -0,0 +1,364 @@
+use std::sync::Arc;
+
+use std::time::Duration;
+
+use crate::core::audit::{self, AuditEvent, AuditQuery};
+use crate::core::error::AppError;
+use crate::core::health::{HealthOverride, ServiceStatus};
+use crate::core::logging::{FilterState, LogFilter};
+use crate::core::probes::{self, ProbeDefinition, ProbeKind, ProbeSpec};
+use crate::core::redact::{self, Secret};
+use crate::core::reload::ReloadReport;
+use crate::core::state::AppState;
+use crate::grpc::health::{to_proto_duration, to_proto_override};
+use crate::proto::admin_service_server::AdminService;
+use crate::proto::health_probe_spec::{self, Kind};
+use crate::proto::service_health::ServingStatus;
+use crate::proto::{
+    AuditEventList, ConfigChange, ConfigReload, HealthProbe, HealthProbeList, HealthProbeSpec,
+    IdRequest, ListAuditEventsRequest, OptionalIdRequest, SetHealthOverrideRequest,
+    SetLogFilterRequest,
+};
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+/// Methods recorded in the audit log.
+pub const MUTATING_METHODS: &[&str] = &[
+    "SetHealthOverride",
+    "ClearHealthOverride",
+    "RegisterHealthProbe",
+    "DeregisterHealthProbe",
+    "SetLogFilter",
+    "ReloadConfig",
+];
+
+pub struct AdminServiceImpl {
+    state: Arc<AppState>,
+}
+
+impl AdminServiceImpl {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    fn log_filter(&self) -> Result<&LogFilter, AppError> {
+        self.state
+            .log_filter()
+            .ok_or_else(|| AppError::Internal("log filter not initialised".into()))
+    }
+}
+
+/// An unset or empty id targets the whole server.
+fn parse_target(id: Option<&str>) -> Result<Option<Uuid>, AppError> {
+    match id.unwrap_or("") {
+        "" => Ok(None),
+        id => Uuid::parse_str(id)
+            .map(Some)
+            .map_err(|_| AppError::InvalidArgument(format!("invalid uuid: {id}"))),
+    }
+}
+
+fn from_proto_status(status: ServingStatus) -> Result<ServiceStatus, AppError> {
+    match status {
+        ServingStatus::Serving => Ok(ServiceStatus::Serving),
+        ServingStatus::Degraded => Ok(ServiceStatus::Degraded),
+        ServingStatus::NotServing => Ok(ServiceStatus::NotServing),
+        ServingStatus::Unspecified => {
+            Err(AppError::InvalidArgument("status must be specified".into()))
+        }
+    }
+}
+
+/// Converts an optional duration field, rejecting negative or zero values.
+fn from_proto_duration(
+    field: &str,
+    d: Option<prost_types::Duration>,
+) -> Result<Option<Duration>, AppError> {
+    let Some(d) = d else {
+        return Ok(None);
+    };
+    match Duration::try_from(d) {
+        Ok(d) if !d.is_zero() => Ok(Some(d)),
+        _ => Err(AppError::InvalidArgument(format!(
+            "{field} must be positive"
+        ))),
+    }
+}
+
+fn from_proto_spec(spec: HealthProbeSpec) -> Result<ProbeSpec, AppError> {
+    let kind = match spec.kind {
+        Some(Kind::Tcp(tcp)) => ProbeKind::Tcp {
+            address: tcp.address,
+        },
+        Some(Kind::Http(http)) => ProbeKind::Http {
+            url: http.url,
+            expected_status: match http.expected_status {
+                0 => 200,
+                status => u16::try_from(status).map_err(|_| {
+                    AppError::InvalidArgument(format!("invalid expected status: {status}"))
+                })?,
+            },
+        },
+        Some(Kind::Dns(dns)) => ProbeKind::Dns { host: dns.host },
+        Some(Kind::Sql(sql)) => ProbeKind::Sql {
+            query: sql.query,
+            database_url: sql
+                .database_url
+                .filter(|url| !url.is_empty())
+                .map(Secret::new),
+        },
+        None => return Err(AppError::InvalidArgument("probe kind is required".into())),
+    };
+
+    Ok(ProbeSpec {
+        name: spec.name,
+        kind,
+        interval: from_proto_duration("interval", spec.interval)?
+            .ok_or_else(|| AppError::InvalidArgument("interval is required".into()))?,
+        timeout: from_proto_duration("timeout", spec.timeout)?.unwrap_or(Duration::from_secs(5)),
+        failure_threshold: spec.failure_threshold.max(1),
+        success_threshold: spec.success_threshold.max(1),
+    })
+}
+
+fn to_proto_probe_def(def: &ProbeDefinition) -> HealthProbe {
+    let spec = &def.spec;
+    let kind = match &spec.kind {
+        ProbeKind::Tcp { address } => Kind::Tcp(health_probe_spec::Tcp {
+            address: address.clone(),
+        }),
+        ProbeKind::Http {
+            url,
+            expected_status,
+        } => Kind::Http(health_probe_spec::Http {
+            url: url.clone(),
+            expected_status: (*expected_status).into(),
+        }),
+        ProbeKind::Dns { host } => Kind::Dns(health_probe_spec::Dns { host: host.clone() }),
+        ProbeKind::Sql {
+            query,
+            database_url,
+        } => Kind::Sql(health_probe_spec::Sql {
+            query: query.clone(),
+            // Only the stored definition keeps the password.
+            database_url: database_url
+                .as_ref()
+                .map(|url| redact::without_password(url.expose())),
+        }),
+    };
+
+    HealthProbe {
+        id: def.id.to_string(),
+        spec: Some(HealthProbeSpec {
+            name: spec.name.clone(),
+            kind: Some(kind),
+            interval: Some(to_proto_duration(spec.interval)),
+            timeout: Some(to_proto_duration(spec.timeout)),
+            failure_threshold: spec.failure_threshold,
+            success_threshold: spec.success_threshold,
+        }),
+    }
+}
+
+/// Audit target for an override: the service, or the whole server.
+fn override_target(id: Option<Uuid>) -> String {
+    match id {
+        Some(id) => format!("health_service/{id}"),
+        None => "server".to_owned(),
+    }
+}
+
+fn from_proto_audit_query(req: ListAuditEventsRequest) -> Result<AuditQuery, AppError> {
+    let timestamp = |field: &str, t: Option<prost_types::Timestamp>| {
+        t.map(|t| {
+            std::time::SystemTime::try_from(t)
+                .map_err(|_| AppError::InvalidArgument(format!("invalid {field}")))
+        })
+        .transpose()
+    };
+    Ok(AuditQuery {
+        since: timestamp("since", req.since)?,
+        until: timestamp("until", req.until)?,
+        before: audit::decode_cursor(&req.page_token)?,
+        method: req.method.filter(|s| !s.is_empty()),
+        principal: req.principal.filter(|s| !s.is_empty()),
+        target: req.target.filter(|s| !s.is_empty()),
+        status: req.status.filter(|s| !s.is_empty()),
+        page_size: req.page_size,
+    })
+}
+
+fn to_proto_audit_event(event: &AuditEvent) -> crate::proto::AuditEvent {
+    crate::proto::AuditEvent {
+        id: event.id,
+        occurred_at: Some(event.occurred_at.into()),
+        method: event.method.clone(),
+        principal: event.principal.clone(),
+        request_id: event.request_id.clone(),
+        target: event.target.clone(),
+        status: event.status.clone(),
+    }
+}
+
+fn to_proto_log_filter(state: &FilterState) -> crate::proto::LogFilter {
+    crate::proto::LogFilter {
+        directive: state.directive.clone(),
+        expires_at: state.expires_at.map(Into::into),
+        revert_to: state.revert_to.clone(),
+    }
+}
+
+fn to_proto_config_reload(report: &ReloadReport) -> ConfigReload {
+    let change = |change: &crate::core::config::ConfigChange, applied| ConfigChange {
+        key: change.key.to_owned(),
+        old_value: change.old.clone(),
+        new_value: change.new.clone(),
+        applied,
+    };
+    ConfigReload {
+        changes: report
+            .applied
+            .iter()
+            .map(|c| change(c, true))
+            .chain(report.restart_required.iter().map(|c| change(c, false)))
+            .collect(),
+    }
+}
+
+#[tonic::async_trait]
+impl AdminService for AdminServiceImpl {
+    async fn set_health_override(
+        &self,
+        request: Request<SetHealthOverrideRequest>,
+    ) -> Result<Response<crate::proto::HealthOverride>, Status> {
+        let target = parse_target(request.get_ref().id.as_deref())?;
+        audit::set_target(&request, override_target(target));
+        let req = request.into_inner();
+        let status = from_proto_status(req.status())?;
+        let ttl = from_proto_duration("ttl", req.ttl)?;
+        if req.reason.trim().is_empty() {
+            return Err(AppError::InvalidArgument("reason is required".into()).into());
+        }
+
+        let manual = HealthOverride::new(status, req.reason, ttl);
+        match target {
+            Some(id) => {
+                self.state
+                    .health()
+                    .set_override(&id, manual.clone())
+                    .await?;
+            }
+            None => {
+                self.state
+                    .health()
+                    .set_server_override(manual.clone())
+                    .await;
+            }
+        }
+
+        Ok(Response::new(to_proto_override(&manual)))
+    }
+
+    async fn clear_health_override(
+        &self,
+        request: Request<OptionalIdRequest>,
+    ) -> Result<Response<()>, Status> {
+        let target = parse_target(request.get_ref().id.as_deref())?;
+        audit::set_target(&request, override_target(target));
+        match target {
+            Some(id) => {
+                self.state.health().clear_override(&id).await?;
+            }
+            None => {
+                self.state.health().clear_server_override().await;
+            }
+        }
+
+        Ok(Response::new(()))
+    }
+
+    async fn register_health_probe(
+        &self,
+        request: Request<HealthProbeSpec>,
+    ) -> Result<Response<HealthProbe>, Status> {
+        // The probe's id is only known once it's stored.
+        let scope = audit::scope(&request);
+        let spec = from_proto_spec(request.into_inner())?;
+        let def = probes::create(&self.state, spec).await?;
+        scope.set_target(format!("health_probe/{}", def.id));
+
+        Ok(Response::new(to_proto_probe_def(&def)))
+    }
+
+    async fn deregister_health_probe(
+        &self,
+        request: Request<IdRequest>,
+    ) -> Result<Response<()>, Status> {
+        let id = &request.get_ref().id;
+        let uuid = Uuid::parse_str(id)
+            .map_err(|_| AppError::InvalidArgument(format!("invalid uuid: {id}")))?;
+        audit::set_target(&request, format!("health_probe/{uuid}"));
+        probes::delete(&self.state, &uuid).await?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn list_health_probes(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<HealthProbeList>, Status> {
+        let defs = probes::list(self.state.db()).await?;
+
+        Ok(Response::new(HealthProbeList {
+            probes: defs.iter().map(to_proto_probe_def).collect(),
+        }))
+    }
+
+    async fn get_log_filter(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<crate::proto::LogFilter>, Status> {
+        let state = self.log_filter()?.state();
+
+        Ok(Response::new(to_proto_log_filter(&state)))
+    }
+
+    async fn set_log_filter(
+        &self,
+        request: Request<SetLogFilterRequest>,
+    ) -> Result<Response<crate::proto::LogFilter>, Status> {
+        audit::set_target(&request, "log_filter");
+        let req = request.into_inner();
+        let ttl = from_proto_duration("ttl", req.ttl)?;
+        let state = self.log_filter()?.set(&req.directive, ttl)?;
+
+        Ok(Response::new(to_proto_log_filter(&state)))
+    }
+
+    async fn list_audit_events(
+        &self,
+        request: Request<ListAuditEventsRequest>,
+    ) -> Result<Response<AuditEventList>, Status> {
+        let query = from_proto_audit_query(request.into_inner())?;
+        let (events, next) = audit::list(self.state.db(), &query).await?;
+
+        Ok(Response::new(AuditEventList {
+            events: events.iter().map(to_proto_audit_event).collect(),
+            next_page_token: next.unwrap_or_default(),
+        }))
+    }
+
+    async fn reload_config(&self, request: Request<()>) -> Result<Response<ConfigReload>, Status> {
+        audit::set_target(&request, "config");
+        let report = self
+            .state
+            .reload_config()
+            .map_err(|e| AppError::FailedPrecondition(e.to_string()))?;
+
+        Ok(Response::new(to_proto_config_reload(&report)))
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/grpc/admin.rs"]
+mod tests;
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::core::audit::{self, AuditScope, Principal};
+use crate::core::request_id::RequestId;
+use crate::core::state::AppState;
+use crate::grpc::admin;
+use crate::grpc::metrics::grpc_code;
+use crate::proto::admin_service_server;
+
+/// Mutating methods of each service. Calls to these are recorded in the
+/// audit log; list new ones next to their service implementation.
+const MUTATING: &[(&str, &[&str])] =
+    &[(admin_service_server::SERVICE_NAME, admin::MUTATING_METHODS)];
+
+/// Whether `path`, `/package.Service/Method`, is a mutating RPC.
+pub fn is_mutating(path: &str) -> bool {
+    let Some((service, method)) = path.trim_start_matches('/').split_once('/') else {
+        return false;
+    };
+    MUTATING
+        .iter()
+        .any(|(s, methods)| *s == service && methods.contains(&method))
+}
+
+/// Writes an audit event for every mutating call once its handler has
+/// finished. Failing to write one is logged; the response is unaffected.
+pub async fn record(State(state): State<Arc<AppState>>, mut req: Request, next: Next) -> Response {
+    if !is_mutating(req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    let method = req.uri().path().to_owned();
+    let principal = req
+        .extensions()
+        .get::<Principal>()
+        .cloned()
+        .unwrap_or_else(Principal::anonymous);
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .cloned()
+        .unwrap_or_default();
+    let scope = AuditScope::default();
+    req.extensions_mut().insert(scope.clone());
+
+    let response = next.run(req).await;
+
+    let code = grpc_code(&response);
+    let target = scope.target();
+    if let Err(err) = audit::record(
+        state.db(),
+        &method,
+        &principal,
+        request_id.as_str(),
+        target.as_deref(),
+        code,
+    )
+    .await
+    {
+        tracing::error!(%err, %method, "failed to write audit event");
+    }
+    response
+}
+
+#[cfg(test)]
+#[path = "../../tests/grpc/audit.rs"]
+mod tests;
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::HeaderMap;
+use http::header::AUTHORIZATION;
+
+use crate::core::audit::Principal;
+use crate::core::auth::{self, Access, AuthSettings};
+use crate::core::error::AppError;
+use crate::core::request_id::RequestId;
+use crate::core::state::AppState;
+
+/// Checks the `authorization: Bearer` token of every call once a JWT
+/// secret or JWKS is configured. The verified claims go in the request
+/// extensions as `Claims` and their subject becomes the `Principal`.
+/// Public methods need no token, but one that is sent must be valid. A
+/// caller already identified by a client certificate or Unix socket peer
+/// needs none either.
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let (settings, access) = {
+        let config = state.config();
+        let Some(settings) = AuthSettings::from_config(&config) else {
+            return next.run(req).await;
+        };
+        (settings, auth::access(&config, req.uri().path()))
+    };
+
+    let token = match bearer(req.headers()) {
+        Ok(token) => token,
+        Err(message) => return reject(&req, message.to_owned()),
+    };
+    match token {
+        Some(token) => match state.authenticator().verify(&settings, &token).await {
+            Ok(claims) => {
+                let principal = Principal::token(claims.subject().unwrap_or_default());
+                req.extensions_mut().insert(principal);
+                req.extensions_mut().insert(claims);
+            }
+            Err(message) => return reject(&req, message),
+        },
+        None if access == Access::Public || req.extensions().get::<Principal>().is_some() => {}
+        None => return reject(&req, "missing bearer token".to_owned()),
+    }
+    next.run(req).await
+}
+
+/// The token of an `authorization: Bearer <token>` header, if there is one.
+fn bearer(headers: &HeaderMap) -> Result<Option<String>, &'static str> {
+    let Some(value) = headers.get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|v| v.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim())
+        .filter(|token| !token.is_empty())
+        .map(|token| Some(token.to_owned()))
+        .ok_or("authorization header is not a bearer token")
+}
+
+fn reject(req: &Request, message: String) -> Response {
+    let request_id = req.extensions().get::<RequestId>();
+    AppError::Unauthenticated(message)
+        .into_status(request_id)
+        .into_http()
+}
+
+#[cfg(test)]
+#[path = "../../tests/grpc/auth.rs"]
+mod tests;
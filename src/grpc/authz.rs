@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::core::audit::{self, Principal};
+use crate::core::auth::Claims;
+use crate::core::authz;
+use crate::core::error::AppError;
+use crate::core::request_id::RequestId;
+use crate::core::state::AppState;
+use crate::grpc::methods;
+
+/// Checks every call against the authorization policy unless
+/// `authz_source` is `off`. Denied calls fail with `PERMISSION_DENIED`
+/// and are recorded in the audit log, whether or not the method mutates
+/// anything; paths that aren't RPCs of the server are not.
+pub async fn authorize(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let token_roles = {
+        let config = state.config();
+        if config.authz_source == "off" {
+            return next.run(req).await;
+        }
+        authz::token_roles(req.extensions().get::<Claims>(), &config.authz_role_claims)
+    };
+    let method = req.uri().path().to_owned();
+    let principal = req
+        .extensions()
+        .get::<Principal>()
+        .cloned()
+        .unwrap_or_else(Principal::anonymous);
+    let Err(denial) = state.policy().check(&method, &principal, &token_roles) else {
+        return next.run(req).await;
+    };
+
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .cloned()
+        .unwrap_or_default();
+    if methods::is_known(&method)
+        && let Err(err) = audit::record(
+            state.db(),
+            &method,
+            &principal,
+            request_id.as_str(),
+            None,
+            tonic::Code::PermissionDenied,
+        )
+        .await
+    {
+        tracing::error!(%err, %method, "failed to write audit event");
+    }
+    AppError::PermissionDenied(format!("{} may not call {method}: {denial}", principal.0))
+        .into_status(Some(&request_id))
+        .into_http()
+}
+
+#[cfg(test)]
+#[path = "../../tests/grpc/authz.rs"]
+mod tests;
@@ -1,11 +1,22 @@
 use std::sync::Arc;
 
 use crate::core::error::AppError;
-use crate::core::health::{ServiceHealth, ServiceStatus};
+use crate::core::health::{
+    AggregatedHealth, DependencyKind, HealthEvent, HealthGraph, HealthOverride, ProbeOutcome,
+    ProbeRecord, ServiceHealth, ServiceStatus,
+};
 use crate::core::state::AppState;
+use crate::proto::health_service_event::Event;
 use crate::proto::health_service_server::HealthService;
+use crate::proto::probe_result::Outcome;
 use crate::proto::service_health::ServingStatus;
-use crate::proto::{OptionalIdRequest, ServiceHealthList};
+use crate::proto::{
+    DependencyEdge, HealthHistory, HealthServiceEvent, OptionalIdRequest, ProbeResult,
+    ServiceHealthList,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
 pub struct HealthServiceImpl {
@@ -16,31 +27,139 @@ impl HealthServiceImpl {
     pub fn new(state: Arc<AppState>) -> Self {
         Self { state }
     }
+
+    async fn resolve(&self, id: Option<&str>) -> Result<ServiceHealth, AppError> {
+        let id = id.unwrap_or("");
+        if id.is_empty() {
+            self.state
+                .health()
+                .get_by_name("server")
+                .await
+                .ok_or_else(|| AppError::NotFound("server service not registered".into()))
+        } else {
+            let uuid = uuid::Uuid::parse_str(id)
+                .map_err(|_| AppError::InvalidArgument(format!("invalid uuid: {id}")))?;
+            self.state
+                .health()
+                .get(&uuid)
+                .await
+                .ok_or_else(|| AppError::NotFound(format!("unknown service: {id}")))
+        }
+    }
 }
 
-fn to_proto(h: &ServiceHealth) -> crate::proto::ServiceHealth {
-    let status = match h.status {
+pub(super) fn to_proto_duration(d: std::time::Duration) -> prost_types::Duration {
+    prost_types::Duration {
+        seconds: d.as_secs() as i64,
+        nanos: d.subsec_nanos() as i32,
+    }
+}
+
+fn to_proto_status(status: ServiceStatus) -> ServingStatus {
+    match status {
         ServiceStatus::Serving => ServingStatus::Serving,
+        ServiceStatus::Degraded => ServingStatus::Degraded,
         ServiceStatus::NotServing => ServingStatus::NotServing,
-    };
+    }
+}
 
-    let uptime = h.uptime();
-    let interval = h.interval;
+/// Converts a service without dependency information; the aggregated
+/// status mirrors the service's own status.
+fn to_proto(h: &ServiceHealth) -> crate::proto::ServiceHealth {
+    let status = to_proto_status(h.status);
 
     crate::proto::ServiceHealth {
         id: h.id.to_string(),
         name: h.name.clone(),
         status: status.into(),
-        interval: Some(prost_types::Duration {
-            seconds: interval.as_secs() as i64,
-            nanos: interval.subsec_nanos() as i32,
-        }),
-        uptime: Some(prost_types::Duration {
-            seconds: uptime.as_secs() as i64,
-            nanos: uptime.subsec_nanos() as i32,
-        }),
+        interval: Some(to_proto_duration(h.interval)),
+        uptime: Some(to_proto_duration(h.uptime())),
         version: h.version.clone(),
         message: h.message.clone(),
+        flapping: h.flapping,
+        timeout: Some(to_proto_duration(h.timeout)),
+        next_probe_at: Some(h.next_probe_at.into()),
+        aggregated_status: status.into(),
+        root_cause: (h.status != ServiceStatus::Serving).then(|| h.name.clone()),
+        manual_override: h.manual_override.as_ref().map(to_proto_override),
+    }
+}
+
+pub(super) fn to_proto_override(o: &HealthOverride) -> crate::proto::HealthOverride {
+    crate::proto::HealthOverride {
+        status: to_proto_status(o.status).into(),
+        reason: o.reason.clone(),
+        set_at: Some(o.set_at.into()),
+        expires_at: o.expires_at.map(Into::into),
+    }
+}
+
+fn to_proto_aggregated(
+    h: &ServiceHealth,
+    agg: Option<&AggregatedHealth>,
+) -> crate::proto::ServiceHealth {
+    let mut proto = to_proto(h);
+    if let Some(agg) = agg {
+        proto.aggregated_status = to_proto_status(agg.status).into();
+        proto.root_cause = agg.root_cause.clone();
+    }
+    proto
+}
+
+fn to_proto_list(graph: &HealthGraph) -> ServiceHealthList {
+    ServiceHealthList {
+        services: graph
+            .services
+            .iter()
+            .map(|h| to_proto_aggregated(h, graph.aggregated.get(&h.id)))
+            .collect(),
+        dependencies: graph
+            .edges
+            .iter()
+            .map(|e| DependencyEdge {
+                service_id: e.service_id.to_string(),
+                service: e.service.clone(),
+                dependency: e.dependency.clone(),
+                critical: e.kind == DependencyKind::Critical,
+            })
+            .collect(),
+        overall_status: to_proto_status(graph.overall.status).into(),
+        root_cause: graph.overall.root_cause.clone(),
+        server_override: graph.server_override.as_ref().map(to_proto_override),
+    }
+}
+
+fn to_proto_probe(r: &ProbeRecord) -> ProbeResult {
+    let outcome = match r.outcome {
+        ProbeOutcome::Success => Outcome::Success,
+        ProbeOutcome::Failure => Outcome::Failure,
+        ProbeOutcome::Timeout => Outcome::Timeout,
+    };
+
+    ProbeResult {
+        timestamp: Some(r.timestamp.into()),
+        latency: Some(to_proto_duration(r.latency)),
+        outcome: outcome.into(),
+        error: r.error.clone(),
+    }
+}
+
+/// Returns `None` for events that change the overall status, which only a
+/// fresh snapshot can express.
+fn to_proto_event(event: &HealthEvent) -> Option<HealthServiceEvent> {
+    let event = match event {
+        HealthEvent::Registered(h) => Event::Registered(to_proto(h)),
+        HealthEvent::Changed(h) => Event::Changed(to_proto(h)),
+        HealthEvent::Deregistered(h) => Event::Deregistered(to_proto(h)),
+        HealthEvent::ServerOverride(_) => return None,
+    };
+    Some(HealthServiceEvent { event: Some(event) })
+}
+
+async fn snapshot(state: &AppState) -> HealthServiceEvent {
+    let graph = state.health().graph().await;
+    HealthServiceEvent {
+        event: Some(Event::Snapshot(to_proto_list(&graph))),
     }
 }
 
@@ -50,34 +169,75 @@ impl HealthService for HealthServiceImpl {
         &self,
         _request: Request<()>,
     ) -> Result<Response<ServiceHealthList>, Status> {
-        let services = self.state.health().list().await;
-        let services = services.iter().map(to_proto).collect();
-        Ok(Response::new(ServiceHealthList { services }))
+        let graph = self.state.health().graph().await;
+        Ok(Response::new(to_proto_list(&graph)))
     }
 
     async fn get_health_service(
         &self,
         request: Request<OptionalIdRequest>,
     ) -> Result<Response<crate::proto::ServiceHealth>, Status> {
-        let id = request.get_ref().id.as_deref().unwrap_or("");
+        let health = self.resolve(request.get_ref().id.as_deref()).await?;
+        let agg = self.state.health().aggregated(&health.id).await;
 
-        let health = if id.is_empty() {
-            self.state
-                .health()
-                .get_by_name("server")
-                .await
-                .ok_or_else(|| AppError::NotFound("server service not registered".into()))?
-        } else {
-            let uuid = uuid::Uuid::parse_str(id)
-                .map_err(|_| AppError::InvalidArgument(format!("invalid uuid: {id}")))?;
-            self.state
-                .health()
-                .get(&uuid)
-                .await
-                .ok_or_else(|| AppError::NotFound(format!("unknown service: {id}")))?
-        };
+        Ok(Response::new(to_proto_aggregated(&health, agg.as_ref())))
+    }
+
+    type WatchHealthServicesStream = ReceiverStream<Result<HealthServiceEvent, Status>>;
+
+    async fn watch_health_services(
+        &self,
+        _request: Request<()>,
+    ) -> Result<Response<Self::WatchHealthServicesStream>, Status> {
+        let state = Arc::clone(&self.state);
+        let mut events = state.health().subscribe();
+        let (tx, rx) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            let mut message = snapshot(&state).await;
+            loop {
+                if tx.send(Ok(message)).await.is_err() {
+                    break;
+                }
+
+                message = tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => match to_proto_event(&event) {
+                            Some(message) => message,
+                            None => snapshot(&state).await,
+                        },
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!(skipped, "health watcher lagged, resending snapshot");
+                            snapshot(&state).await
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = tx.closed() => break,
+                };
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn get_health_history(
+        &self,
+        request: Request<OptionalIdRequest>,
+    ) -> Result<Response<HealthHistory>, Status> {
+        let health = self.resolve(request.get_ref().id.as_deref()).await?;
+        let results = self
+            .state
+            .health()
+            .history(&health.id)
+            .await
+            .ok_or_else(|| AppError::NotFound(format!("unknown service: {}", health.id)))?;
 
-        Ok(Response::new(to_proto(&health)))
+        Ok(Response::new(HealthHistory {
+            id: health.id.to_string(),
+            name: health.name,
+            flapping: health.flapping,
+            results: results.iter().map(to_proto_probe).collect(),
+        }))
     }
 }
 
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_server::Health;
+use tonic_health::pb::{HealthCheckRequest, HealthCheckResponse};
+
+use crate::core::error::AppError;
+use crate::core::health::ServiceStatus;
+use crate::core::lifecycle::{Lifecycle, Phase};
+use crate::core::state::AppState;
+
+/// Standard `grpc.health.v1.Health` service backed by the `HealthRegistry`.
+///
+/// Each registered service name is exposed as a health service name; the
+/// empty name reports the combined status of every registered service, or
+/// `NOT_SERVING` once the server is draining.
+pub struct StandardHealthImpl {
+    state: Arc<AppState>,
+    lifecycle: Arc<Lifecycle>,
+}
+
+impl StandardHealthImpl {
+    pub fn new(state: Arc<AppState>, lifecycle: Arc<Lifecycle>) -> Self {
+        Self { state, lifecycle }
+    }
+}
+
+/// Degraded services are still answering, so the standard protocol reports
+/// them as serving.
+fn to_serving_status(status: ServiceStatus) -> ServingStatus {
+    match status {
+        ServiceStatus::Serving | ServiceStatus::Degraded => ServingStatus::Serving,
+        ServiceStatus::NotServing => ServingStatus::NotServing,
+    }
+}
+
+async fn resolve(state: &AppState, lifecycle: &Lifecycle, service: &str) -> Option<ServingStatus> {
+    let status = if service.is_empty() {
+        if lifecycle.phase() == Phase::Draining {
+            return Some(ServingStatus::NotServing);
+        }
+        state.health().overall_status().await
+    } else {
+        state.health().status_by_name(service).await?
+    };
+    Some(to_serving_status(status))
+}
+
+#[tonic::async_trait]
+impl Health for StandardHealthImpl {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service = &request.get_ref().service;
+        let status = resolve(&self.state, &self.lifecycle, service)
+            .await
+            .ok_or_else(|| AppError::NotFound(format!("unknown service: {service}")))?;
+
+        Ok(Response::new(HealthCheckResponse {
+            status: status.into(),
+        }))
+    }
+
+    type WatchStream = ReceiverStream<Result<HealthCheckResponse, Status>>;
+
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service = request.into_inner().service;
+        let state = Arc::clone(&self.state);
+        let lifecycle = Arc::clone(&self.lifecycle);
+        let mut events = state.health().subscribe();
+        let mut phase = lifecycle.subscribe();
+        let (tx, rx) = mpsc::channel(4);
+
+        tokio::spawn(async move {
+            let mut last = None;
+            loop {
+                let status = resolve(&state, &lifecycle, &service)
+                    .await
+                    .unwrap_or(ServingStatus::ServiceUnknown);
+                if last != Some(status) {
+                    let response = HealthCheckResponse {
+                        status: status.into(),
+                    };
+                    if tx.send(Ok(response)).await.is_err() {
+                        break;
+                    }
+                    last = Some(status);
+                }
+
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    },
+                    Ok(()) = phase.changed() => {}
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/grpc/health_v1.rs"]
+mod tests;
@@ -0,0 +1,46 @@
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use prost::Message;
+use prost_types::FileDescriptorSet;
+
+use crate::proto::FILE_DESCRIPTOR_SET;
+
+/// Every RPC the server implements, as `/package.Service/Method`, read
+/// from the descriptors of each service it can register.
+static KNOWN: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    [
+        FILE_DESCRIPTOR_SET,
+        tonic_health::pb::FILE_DESCRIPTOR_SET,
+        tonic_reflection::pb::v1::FILE_DESCRIPTOR_SET,
+        tonic_reflection::pb::v1alpha::FILE_DESCRIPTOR_SET,
+    ]
+    .into_iter()
+    .flat_map(|bytes| {
+        FileDescriptorSet::decode(bytes)
+            .expect("descriptor sets are valid")
+            .file
+    })
+    .flat_map(|file| {
+        let package = file.package().to_owned();
+        file.service.into_iter().flat_map(move |service| {
+            let name = format!("{package}.{}", service.name());
+            service
+                .method
+                .into_iter()
+                .map(move |method| format!("/{name}/{}", method.name()))
+        })
+    })
+    .collect()
+});
+
+/// Whether `path` is one of the server's RPCs. Any other path can only
+/// fail, so middleware must not use it as a metric label or record it in
+/// the audit log: a client could send as many as it likes.
+pub fn is_known(path: &str) -> bool {
+    KNOWN.contains(path)
+}
+
+#[cfg(test)]
+#[path = "../../tests/grpc/methods.rs"]
+mod tests;
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::core::state::AppState;
+use crate::grpc::methods;
+
+/// Records request count, latency and in-flight requests for every gRPC
+/// call. A missing `grpc-status` header means the status is sent in the
+/// trailers, which only happens for calls that reached their handler, so
+/// it is counted as OK.
+pub async fn track(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let path = req.uri().path();
+    let method = methods::is_known(path).then(|| path.to_owned());
+    let metrics = state.metrics();
+    let _in_flight = metrics.start_request();
+    let started = Instant::now();
+
+    let response = next.run(req).await;
+
+    metrics.observe_request(method.as_deref(), grpc_code(&response), started.elapsed());
+    response
+}
+
+/// The status of a gRPC response, from its `grpc-status` header.
+pub(super) fn grpc_code(response: &Response) -> tonic::Code {
+    response
+        .headers()
+        .get("grpc-status")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i32>().ok())
+        .map_or(tonic::Code::Ok, tonic::Code::from_i32)
+}
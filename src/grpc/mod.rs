@@ -1 +1,9 @@
+pub mod admin;
+pub mod audit;
+pub mod auth;
+pub mod authz;
 pub mod health;
+pub mod health_v1;
+pub mod methods;
+pub mod metrics;
+pub mod timeout;
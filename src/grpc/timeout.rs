@@ -0,0 +1,25 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use tonic::Status;
+
+use crate::core::state::AppState;
+
+/// Fails calls that take longer than `request_timeout_secs` to respond,
+/// as `Server::timeout` would, but reads the limit per call so a config
+/// reload applies to the next one. Clients' `grpc-timeout` is still
+/// enforced by tonic.
+pub async fn enforce(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let limit = Duration::from_secs(state.config().request_timeout_secs);
+    match tokio::time::timeout(limit, next.run(req)).await {
+        Ok(response) => response,
+        Err(_) => Status::cancelled("Timeout expired").into_http(),
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/grpc/timeout.rs"]
+mod tests;
@@ -4,49 +4,98 @@ use std::time::Duration;
 use anyhow::Result;
 use std::net::SocketAddr;
 
+use tonic::service::Routes;
 use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+use tonic_health::pb::health_server::HealthServer;
 use tonic_reflection::server::Builder as ReflectionBuilder;
 use tonic_web::GrpcWebLayer;
+use tower::util::MapRequestLayer;
 use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
 mod core;
 mod grpc;
 mod proto;
+mod web;
 
+use core::config::{Args, Config};
+use core::health::{Dependency, RegisterOptions};
+use core::lifecycle::Lifecycle;
+use core::listen::{Listener, ListenerSpec, Service};
+use core::request_id::RequestId;
 use core::state::AppState;
+use proto::FILE_DESCRIPTOR_SET;
+use proto::admin_service_server::AdminServiceServer;
 use proto::health_service_server::HealthServiceServer;
 
-pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(
-    env!("CARGO_MANIFEST_DIR"),
-    "/src/proto/generated/descriptors.bin"
-));
-
 #[tokio::main]
 async fn main() -> Result<()> {
     let _ = dotenvy::dotenv();
 
-    let config = core::config::Config::from_env();
-    core::logging::init(&config);
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => exit_with(err),
+    };
+    if args.help {
+        print!("{}", Args::usage());
+        return Ok(());
+    }
+    let config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(err) => exit_with(err),
+    };
+    if args.print_config {
+        print!("{}", config.to_redacted_toml());
+        return Ok(());
+    }
+
+    let telemetry = core::telemetry::Telemetry::init(&config)?;
+    let log_filter = core::logging::init(&config, &telemetry)?;
+
+    let tls = core::tls::TlsSettings::from_config(&config)
+        .map(core::tls::Tls::new)
+        .transpose()?;
+    let listeners = core::listen::bind_all(&config.listeners())?;
 
-    let addr: SocketAddr = config.listen_addr.parse()?;
+    let lifecycle = Arc::new(Lifecycle::new());
+    let web_state = web::WebState::new(Arc::clone(&lifecycle));
 
-    let cors_layer = build_cors_layer(&config);
+    if let Some(admin_addr) = &config.admin_listen_addr {
+        let admin_addr: SocketAddr = admin_addr.parse()?;
+        let listener = tokio::net::TcpListener::bind(admin_addr).await?;
+        tokio::spawn(web::serve(listener, web_state.clone()));
+        tracing::info!("admin HTTP listening on {admin_addr}");
+    }
 
-    let db = core::db::create_pool(&config.database_url, config.db_max_connections).await?;
+    let db = core::db::create_pool(config.database_url.expose(), config.db_max_connections).await?;
     core::db::run_migrations(&db).await?;
 
     let state = AppState::new(config, db);
+    state.attach_log_filter(log_filter);
+    state.attach_args(args);
+    core::authz::init(&state).await?;
+    #[cfg(unix)]
+    core::reload::watch_sighup(Arc::clone(&state))?;
+
+    let notifier_settings = core::notifier::NotifierSettings::from_config(&state.config());
+    if !notifier_settings.webhooks.is_empty() {
+        core::notifier::spawn(Arc::clone(&state), notifier_settings);
+    }
 
     state
         .health()
-        .register(
+        .register_with_options(
             "server",
             Duration::from_secs(60),
             Some(env!("CARGO_PKG_VERSION").to_owned()),
             Box::new(|| Box::pin(async { Ok(()) })),
+            RegisterOptions {
+                dependencies: vec![Dependency::critical("database")],
+                ..RegisterOptions::default()
+            },
         )
-        .await;
+        .await?;
 
     let db_version = get_db_version(state.db()).await;
     let db_pool = state.db().clone();
@@ -69,28 +118,30 @@ async fn main() -> Result<()> {
         )
         .await;
 
-    let health_service = grpc::health::HealthServiceImpl::new(Arc::clone(&state));
-
-    let reflection_v1 = ReflectionBuilder::configure()
-        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
-        .build_v1()?;
+    let restored = core::probes::restore(&state).await?;
+    tracing::info!(count = restored, "health probes restored");
 
-    let reflection_v1alpha = ReflectionBuilder::configure()
-        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
-        .build_v1alpha()?;
+    web_state.attach(Arc::clone(&state));
 
-    tracing::info!("MidnightServer listening on {addr}");
-
-    Server::builder()
+    let grpc_server = Server::builder()
         .accept_http1(true)
-        .timeout(Duration::from_secs(state.config().request_timeout_secs))
-        .layer(cors_layer)
-        .layer(GrpcWebLayer::new())
+        .layer(MapRequestLayer::new(core::request_id::assign))
         .layer(
             TraceLayer::new_for_grpc()
                 .make_span_with(|req: &http::Request<_>| {
-                    let request_id = uuid::Uuid::new_v4();
-                    tracing::info_span!("grpc", %request_id, method = %req.uri().path())
+                    let request_id = req
+                        .extensions()
+                        .get::<RequestId>()
+                        .cloned()
+                        .unwrap_or_default();
+                    let span = tracing::info_span!(
+                        "grpc",
+                        %request_id,
+                        method = %req.uri().path(),
+                        trace_id = tracing::field::Empty,
+                    );
+                    core::telemetry::link_span(&span, req.headers());
+                    span
                 })
                 .on_request(|_req: &http::Request<_>, _span: &tracing::Span| {
                     tracing::info!("request received");
@@ -114,34 +165,167 @@ async fn main() -> Result<()> {
                         tracing::error!(?err, latency_ms = latency.as_millis(), "request failed");
                     },
                 ),
-        )
-        .add_service(HealthServiceServer::new(health_service))
-        .add_service(reflection_v1)
-        .add_service(reflection_v1alpha)
-        .serve_with_shutdown(addr, shutdown_signal())
-        .await?;
+        );
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let mut servers = tokio::task::JoinSet::new();
+    for (listener, spec) in listeners {
+        let server = grpc_server
+            .clone()
+            .add_routes(routes(&state, &web_state, &spec)?.into());
+        let mut shutdown_rx = shutdown_rx.clone();
+        let shutdown = async move {
+            let _ = shutdown_rx.wait_for(|done| *done).await;
+        };
+        match listener {
+            Listener::Tcp(listener) => match &tls {
+                Some(tls) => {
+                    let mode = if tls.settings().client_ca.is_some() {
+                        "mTLS"
+                    } else {
+                        "TLS"
+                    };
+                    tracing::info!("MidnightServer listening on {spec}, {mode}");
+                    let incoming = Arc::clone(tls).incoming(listener);
+                    servers.spawn(server.serve_with_incoming_shutdown(incoming, shutdown));
+                }
+                None => {
+                    tracing::info!("MidnightServer listening on {spec}");
+                    let incoming = TcpIncoming::from(listener);
+                    servers.spawn(server.serve_with_incoming_shutdown(incoming, shutdown));
+                }
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener, path) => {
+                tracing::info!("MidnightServer listening on {spec}");
+                let incoming = tokio_stream::wrappers::UnixListenerStream::new(listener);
+                servers.spawn(async move {
+                    let result = server
+                        .serve_with_incoming_shutdown(incoming, shutdown)
+                        .await;
+                    if let Some(path) = path {
+                        let _ = std::fs::remove_file(path);
+                    }
+                    result
+                });
+            }
+        }
+    }
+    if let Some(tls) = &tls {
+        Arc::clone(tls).watch(core::tls::RELOAD_INTERVAL);
+    }
+    lifecycle.set_ready();
 
+    tokio::spawn(async move {
+        drain_on_shutdown(lifecycle, state).await;
+        let _ = shutdown_tx.send(true);
+    });
+    while let Some(result) = servers.join_next().await {
+        result??;
+    }
+
+    telemetry.shutdown();
     Ok(())
 }
 
-fn build_cors_layer(config: &core::config::Config) -> CorsLayer {
-    let origin = if config.cors_is_permissive() {
-        CorsLayer::new().allow_origin(Any)
-    } else {
-        let origins: Vec<_> = config
-            .cors_origins
-            .iter()
-            .filter_map(|o| o.parse().ok())
-            .collect();
-        CorsLayer::new().allow_origin(AllowOrigin::list(origins))
-    };
+/// The services `spec` asks for, with the middleware every listener
+/// shares.
+fn routes(
+    state: &Arc<AppState>,
+    web_state: &web::WebState,
+    spec: &ListenerSpec,
+) -> Result<axum::Router> {
+    let mut grpc_routes = Routes::builder();
+    if spec.serves(Service::Health) {
+        grpc_routes
+            .add_service(HealthServiceServer::new(
+                grpc::health::HealthServiceImpl::new(Arc::clone(state)),
+            ))
+            .add_service(HealthServer::new(grpc::health_v1::StandardHealthImpl::new(
+                Arc::clone(state),
+                Arc::clone(web_state.lifecycle()),
+            )));
+    }
+    if spec.serves(Service::Admin) {
+        grpc_routes.add_service(AdminServiceServer::new(grpc::admin::AdminServiceImpl::new(
+            Arc::clone(state),
+        )));
+    }
+    if spec.serves(Service::Reflection) {
+        grpc_routes
+            .add_service(
+                ReflectionBuilder::configure()
+                    .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                    .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+                    .build_v1()?,
+            )
+            .add_service(
+                ReflectionBuilder::configure()
+                    .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                    .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+                    .build_v1alpha()?,
+            );
+    }
+
+    // gRPC-Web only wraps the gRPC services; it rejects plain HTTP/1.1
+    // requests, which the health endpoints need to accept.
+    let mut routes = grpc_routes
+        .routes()
+        .into_axum_router()
+        .layer(GrpcWebLayer::new())
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::clone(state),
+            grpc::timeout::enforce,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::clone(state),
+            grpc::audit::record,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::clone(state),
+            grpc::authz::authorize,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::clone(state),
+            grpc::auth::authenticate,
+        ))
+        .layer(axum::middleware::from_fn(core::tls::client_principal));
+    #[cfg(unix)]
+    if spec.peer_auth {
+        routes = routes.layer(axum::middleware::from_fn(core::listen::peer_principal));
+    }
+    routes = routes.layer(axum::middleware::from_fn_with_state(
+        Arc::clone(state),
+        grpc::metrics::track,
+    ));
+    if spec.serves(Service::Web) && state.config().admin_listen_addr.is_none() {
+        routes = routes.merge(web::router(web_state.clone()));
+    }
+    Ok(routes
+        .layer(axum::middleware::from_fn(core::telemetry::trace_id_header))
+        .layer(axum::middleware::from_fn(core::request_id::propagate))
+        .layer(build_cors_layer(Arc::clone(state))))
+}
 
-    origin
+/// Origins are checked against the current config on every request, so a
+/// reload changes them without a restart.
+fn build_cors_layer(state: Arc<AppState>) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            state.config().cors_allows(origin)
+        }))
         .allow_headers(Any)
         .allow_methods(Any)
         .expose_headers(Any)
 }
 
+/// Config errors are the operator's to fix, so they get a plain message
+/// rather than a backtrace-style report.
+fn exit_with(err: impl std::fmt::Display) -> ! {
+    eprintln!("error: {err}");
+    std::process::exit(2);
+}
+
 async fn get_db_version(pool: &sqlx::PgPool) -> Option<String> {
     sqlx::query_scalar!("SELECT version()")
         .fetch_one(pool)
@@ -150,6 +334,19 @@ async fn get_db_version(pool: &sqlx::PgPool) -> Option<String> {
         .flatten()
 }
 
+/// Marks the server as draining once a shutdown signal arrives, then waits
+/// `shutdown_drain_secs` so load balancers see `/readyz` fail before
+/// connections close.
+async fn drain_on_shutdown(lifecycle: Arc<Lifecycle>, state: Arc<AppState>) {
+    shutdown_signal().await;
+    lifecycle.set_draining();
+    let drain = Duration::from_secs(state.config().shutdown_drain_secs);
+    if !drain.is_zero() {
+        tracing::info!(drain_secs = drain.as_secs(), "draining before shutdown");
+        tokio::time::sleep(drain).await;
+    }
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
@@ -173,3 +370,7 @@ async fn shutdown_signal() {
         _ = terminate => tracing::info!("received SIGTERM, shutting down"),
     }
 }
+
+#[cfg(test)]
+#[path = "../tests/main/routes.rs"]
+mod tests;
@@ -15,6 +15,21 @@ pub struct ServiceHealth {
     pub version: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(string, optional, tag = "7")]
     pub message: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(bool, tag = "8")]
+    pub flapping: bool,
+    #[prost(message, optional, tag = "9")]
+    pub timeout: ::core::option::Option<::prost_types::Duration>,
+    #[prost(message, optional, tag = "10")]
+    pub next_probe_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// Status once failing dependencies are propagated.
+    #[prost(enumeration = "service_health::ServingStatus", tag = "11")]
+    pub aggregated_status: i32,
+    /// Service whose own failure explains aggregated_status.
+    #[prost(string, optional, tag = "12")]
+    pub root_cause: ::core::option::Option<::prost::alloc::string::String>,
+    /// Set while an operator forces the status; message then shows the reason.
+    #[prost(message, optional, tag = "13")]
+    pub manual_override: ::core::option::Option<HealthOverride>,
 }
 /// Nested message and enum types in `ServiceHealth`.
 pub mod service_health {
@@ -34,6 +49,7 @@ pub mod service_health {
         Unspecified = 0,
         Serving = 1,
         NotServing = 2,
+        Degraded = 3,
     }
     impl ServingStatus {
         /// String value of the enum field names used in the ProtoBuf definition.
@@ -45,6 +61,7 @@ pub mod service_health {
                 Self::Unspecified => "SERVING_STATUS_UNSPECIFIED",
                 Self::Serving => "SERVING_STATUS_SERVING",
                 Self::NotServing => "SERVING_STATUS_NOT_SERVING",
+                Self::Degraded => "SERVING_STATUS_DEGRADED",
             }
         }
         /// Creates an enum from field names used in the ProtoBuf definition.
@@ -53,15 +70,270 @@ pub mod service_health {
                 "SERVING_STATUS_UNSPECIFIED" => Some(Self::Unspecified),
                 "SERVING_STATUS_SERVING" => Some(Self::Serving),
                 "SERVING_STATUS_NOT_SERVING" => Some(Self::NotServing),
+                "SERVING_STATUS_DEGRADED" => Some(Self::Degraded),
                 _ => None,
             }
         }
     }
 }
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct HealthOverride {
+    #[prost(enumeration = "service_health::ServingStatus", tag = "1")]
+    pub status: i32,
+    #[prost(string, tag = "2")]
+    pub reason: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub set_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// Unset when the override lasts until it is cleared.
+    #[prost(message, optional, tag = "4")]
+    pub expires_at: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct DependencyEdge {
+    #[prost(string, tag = "1")]
+    pub service_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub service: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub dependency: ::prost::alloc::string::String,
+    #[prost(bool, tag = "4")]
+    pub critical: bool,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ServiceHealthList {
     #[prost(message, repeated, tag = "1")]
     pub services: ::prost::alloc::vec::Vec<ServiceHealth>,
+    #[prost(message, repeated, tag = "2")]
+    pub dependencies: ::prost::alloc::vec::Vec<DependencyEdge>,
+    /// Combined status of the services nothing else depends on.
+    #[prost(enumeration = "service_health::ServingStatus", tag = "3")]
+    pub overall_status: i32,
+    #[prost(string, optional, tag = "4")]
+    pub root_cause: ::core::option::Option<::prost::alloc::string::String>,
+    /// Server-wide override, which takes precedence for overall_status.
+    #[prost(message, optional, tag = "5")]
+    pub server_override: ::core::option::Option<HealthOverride>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthServiceEvent {
+    #[prost(oneof = "health_service_event::Event", tags = "1, 2, 3, 4")]
+    pub event: ::core::option::Option<health_service_event::Event>,
+}
+/// Nested message and enum types in `HealthServiceEvent`.
+pub mod health_service_event {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Event {
+        #[prost(message, tag = "1")]
+        Snapshot(super::ServiceHealthList),
+        #[prost(message, tag = "2")]
+        Registered(super::ServiceHealth),
+        #[prost(message, tag = "3")]
+        Changed(super::ServiceHealth),
+        #[prost(message, tag = "4")]
+        Deregistered(super::ServiceHealth),
+    }
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ProbeResult {
+    #[prost(message, optional, tag = "1")]
+    pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "2")]
+    pub latency: ::core::option::Option<::prost_types::Duration>,
+    #[prost(enumeration = "probe_result::Outcome", tag = "3")]
+    pub outcome: i32,
+    #[prost(string, optional, tag = "4")]
+    pub error: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Nested message and enum types in `ProbeResult`.
+pub mod probe_result {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Outcome {
+        Unspecified = 0,
+        Success = 1,
+        Failure = 2,
+        Timeout = 3,
+    }
+    impl Outcome {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unspecified => "OUTCOME_UNSPECIFIED",
+                Self::Success => "OUTCOME_SUCCESS",
+                Self::Failure => "OUTCOME_FAILURE",
+                Self::Timeout => "OUTCOME_TIMEOUT",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "OUTCOME_UNSPECIFIED" => Some(Self::Unspecified),
+                "OUTCOME_SUCCESS" => Some(Self::Success),
+                "OUTCOME_FAILURE" => Some(Self::Failure),
+                "OUTCOME_TIMEOUT" => Some(Self::Timeout),
+                _ => None,
+            }
+        }
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthHistory {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub flapping: bool,
+    #[prost(message, repeated, tag = "4")]
+    pub results: ::prost::alloc::vec::Vec<ProbeResult>,
+}
+/// Definition of a probe registered at runtime.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct HealthProbeSpec {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "6")]
+    pub interval: ::core::option::Option<::prost_types::Duration>,
+    /// Defaults to 5s.
+    #[prost(message, optional, tag = "7")]
+    pub timeout: ::core::option::Option<::prost_types::Duration>,
+    /// Both default to 1.
+    #[prost(uint32, tag = "8")]
+    pub failure_threshold: u32,
+    #[prost(uint32, tag = "9")]
+    pub success_threshold: u32,
+    #[prost(oneof = "health_probe_spec::Kind", tags = "2, 3, 4, 5")]
+    pub kind: ::core::option::Option<health_probe_spec::Kind>,
+}
+/// Nested message and enum types in `HealthProbeSpec`.
+pub mod health_probe_spec {
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+    pub struct Tcp {
+        /// host:port to connect to.
+        #[prost(string, tag = "1")]
+        pub address: ::prost::alloc::string::String,
+    }
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+    pub struct Http {
+        #[prost(string, tag = "1")]
+        pub url: ::prost::alloc::string::String,
+        /// Defaults to 200.
+        #[prost(uint32, tag = "2")]
+        pub expected_status: u32,
+    }
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+    pub struct Dns {
+        #[prost(string, tag = "1")]
+        pub host: ::prost::alloc::string::String,
+    }
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+    pub struct Sql {
+        /// Run in a read-only transaction.
+        #[prost(string, tag = "1")]
+        pub query: ::prost::alloc::string::String,
+        /// Defaults to the server's own database. Returned without its
+        /// password.
+        #[prost(string, optional, tag = "2")]
+        pub database_url: ::core::option::Option<::prost::alloc::string::String>,
+    }
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "2")]
+        Tcp(Tcp),
+        #[prost(message, tag = "3")]
+        Http(Http),
+        #[prost(message, tag = "4")]
+        Dns(Dns),
+        #[prost(message, tag = "5")]
+        Sql(Sql),
+    }
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct HealthProbe {
+    /// Also the id of the health service the probe reports as.
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<HealthProbeSpec>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthProbeList {
+    #[prost(message, repeated, tag = "1")]
+    pub probes: ::prost::alloc::vec::Vec<HealthProbe>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LogFilter {
+    /// tracing EnvFilter directive, e.g. "info,sqlx=warn".
+    #[prost(string, tag = "1")]
+    pub directive: ::prost::alloc::string::String,
+    /// Set while a temporary directive is in effect.
+    #[prost(message, optional, tag = "2")]
+    pub expires_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// Directive restored when expires_at passes.
+    #[prost(string, optional, tag = "3")]
+    pub revert_to: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct AuditEvent {
+    #[prost(int64, tag = "1")]
+    pub id: i64,
+    #[prost(message, optional, tag = "2")]
+    pub occurred_at: ::core::option::Option<::prost_types::Timestamp>,
+    /// Full gRPC path, e.g. "/midnight.AdminService/SetHealthOverride".
+    #[prost(string, tag = "3")]
+    pub method: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub principal: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub request_id: ::prost::alloc::string::String,
+    /// Resource the call acted on, e.g. "health_probe/<id>", when known.
+    #[prost(string, optional, tag = "6")]
+    pub target: ::core::option::Option<::prost::alloc::string::String>,
+    /// gRPC status code name, e.g. "Ok" or "NotFound".
+    #[prost(string, tag = "7")]
+    pub status: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuditEventList {
+    /// Newest first.
+    #[prost(message, repeated, tag = "1")]
+    pub events: ::prost::alloc::vec::Vec<AuditEvent>,
+    /// Pass as page_token to get the next page; empty on the last page.
+    #[prost(string, tag = "2")]
+    pub next_page_token: ::prost::alloc::string::String,
+}
+/// A setting whose reloaded value differs from the running one.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ConfigChange {
+    /// Setting name as in the config file, e.g. "log_level".
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    /// Values as --print-config shows them, secrets masked; empty if unset.
+    #[prost(string, tag = "2")]
+    pub old_value: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub new_value: ::prost::alloc::string::String,
+    /// False if the change only takes effect after a restart.
+    #[prost(bool, tag = "4")]
+    pub applied: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigReload {
+    #[prost(message, repeated, tag = "1")]
+    pub changes: ::prost::alloc::vec::Vec<ConfigChange>,
 }
 /// Generic request
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -74,6 +346,49 @@ pub struct OptionalIdRequest {
     #[prost(string, optional, tag = "1")]
     pub id: ::core::option::Option<::prost::alloc::string::String>,
 }
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetHealthOverrideRequest {
+    /// Service to override; unset or empty overrides the whole server.
+    #[prost(string, optional, tag = "1")]
+    pub id: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(enumeration = "service_health::ServingStatus", tag = "2")]
+    pub status: i32,
+    #[prost(string, tag = "3")]
+    pub reason: ::prost::alloc::string::String,
+    /// Unset keeps the override until it is cleared.
+    #[prost(message, optional, tag = "4")]
+    pub ttl: ::core::option::Option<::prost_types::Duration>,
+}
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetLogFilterRequest {
+    #[prost(string, tag = "1")]
+    pub directive: ::prost::alloc::string::String,
+    /// Unset makes the change permanent; otherwise the previous directive is
+    /// restored once the ttl runs out.
+    #[prost(message, optional, tag = "2")]
+    pub ttl: ::core::option::Option<::prost_types::Duration>,
+}
+/// Unset filters match everything.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ListAuditEventsRequest {
+    #[prost(string, optional, tag = "1")]
+    pub method: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub principal: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "3")]
+    pub target: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub status: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "5")]
+    pub since: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(message, optional, tag = "6")]
+    pub until: ::core::option::Option<::prost_types::Timestamp>,
+    /// Defaults to 50, at most 500.
+    #[prost(uint32, tag = "7")]
+    pub page_size: u32,
+    #[prost(string, tag = "8")]
+    pub page_token: ::prost::alloc::string::String,
+}
 /// Generated server implementations.
 pub mod health_service_server {
     #![allow(
@@ -98,6 +413,27 @@ pub mod health_service_server {
             &self,
             request: tonic::Request<super::OptionalIdRequest>,
         ) -> std::result::Result<tonic::Response<super::ServiceHealth>, tonic::Status>;
+        /// Server streaming response type for the WatchHealthServices method.
+        type WatchHealthServicesStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::HealthServiceEvent, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Sends a snapshot of every service, then an event per registration,
+        /// status/message change or deregistration. A fresh snapshot is sent if
+        /// the stream falls behind or the server-wide override changes.
+        async fn watch_health_services(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<
+            tonic::Response<Self::WatchHealthServicesStream>,
+            tonic::Status,
+        >;
+        /// Recent probe results for a service, oldest first.
+        async fn get_health_history(
+            &self,
+            request: tonic::Request<super::OptionalIdRequest>,
+        ) -> std::result::Result<tonic::Response<super::HealthHistory>, tonic::Status>;
     }
     /// HealthService provides health checking for the server and its services.
     #[derive(Debug)]
@@ -263,6 +599,94 @@ pub mod health_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/midnight.HealthService/WatchHealthServices" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchHealthServicesSvc<T: HealthService>(pub Arc<T>);
+                    impl<T: HealthService> tonic::server::ServerStreamingService<()>
+                    for WatchHealthServicesSvc<T> {
+                        type Response = super::HealthServiceEvent;
+                        type ResponseStream = T::WatchHealthServicesStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as HealthService>::watch_health_services(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = WatchHealthServicesSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.HealthService/GetHealthHistory" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetHealthHistorySvc<T: HealthService>(pub Arc<T>);
+                    impl<
+                        T: HealthService,
+                    > tonic::server::UnaryService<super::OptionalIdRequest>
+                    for GetHealthHistorySvc<T> {
+                        type Response = super::HealthHistory;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::OptionalIdRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as HealthService>::get_health_history(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetHealthHistorySvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
@@ -303,3 +727,578 @@ pub mod health_service_server {
         const NAME: &'static str = SERVICE_NAME;
     }
 }
+/// Generated server implementations.
+pub mod admin_service_server {
+    #![allow(
+        unused_variables,
+        dead_code,
+        missing_docs,
+        clippy::wildcard_imports,
+        clippy::let_unit_value,
+    )]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with AdminServiceServer.
+    #[async_trait]
+    pub trait AdminService: std::marker::Send + std::marker::Sync + 'static {
+        /// Forces a service, or the whole server, to a status until cleared or
+        /// until the ttl runs out, regardless of probe results.
+        async fn set_health_override(
+            &self,
+            request: tonic::Request<super::SetHealthOverrideRequest>,
+        ) -> std::result::Result<tonic::Response<super::HealthOverride>, tonic::Status>;
+        /// Returns a service, or the whole server when id is unset or empty, to
+        /// its probe-driven status.
+        async fn clear_health_override(
+            &self,
+            request: tonic::Request<super::OptionalIdRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
+        /// Stores a TCP, HTTP, DNS or SQL probe and starts probing it. Stored
+        /// probes are restored on startup.
+        async fn register_health_probe(
+            &self,
+            request: tonic::Request<super::HealthProbeSpec>,
+        ) -> std::result::Result<tonic::Response<super::HealthProbe>, tonic::Status>;
+        /// Stops and deletes a probe created by RegisterHealthProbe.
+        async fn deregister_health_probe(
+            &self,
+            request: tonic::Request<super::IdRequest>,
+        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
+        async fn list_health_probes(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<tonic::Response<super::HealthProbeList>, tonic::Status>;
+        async fn get_log_filter(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<tonic::Response<super::LogFilter>, tonic::Status>;
+        /// Replaces the log filter, optionally only for a while.
+        async fn set_log_filter(
+            &self,
+            request: tonic::Request<super::SetLogFilterRequest>,
+        ) -> std::result::Result<tonic::Response<super::LogFilter>, tonic::Status>;
+        /// Calls to the mutating RPCs above, newest first.
+        async fn list_audit_events(
+            &self,
+            request: tonic::Request<super::ListAuditEventsRequest>,
+        ) -> std::result::Result<tonic::Response<super::AuditEventList>, tonic::Status>;
+        /// Re-reads config from the file, environment and flags the server was
+        /// started with, as SIGHUP does. Invalid config is rejected whole.
+        async fn reload_config(
+            &self,
+            request: tonic::Request<()>,
+        ) -> std::result::Result<tonic::Response<super::ConfigReload>, tonic::Status>;
+    }
+    /// AdminService holds operator actions that change how the server behaves.
+    #[derive(Debug)]
+    pub struct AdminServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> AdminServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for AdminServiceServer<T>
+    where
+        T: AdminService,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::Body>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/midnight.AdminService/SetHealthOverride" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetHealthOverrideSvc<T: AdminService>(pub Arc<T>);
+                    impl<
+                        T: AdminService,
+                    > tonic::server::UnaryService<super::SetHealthOverrideRequest>
+                    for SetHealthOverrideSvc<T> {
+                        type Response = super::HealthOverride;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetHealthOverrideRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::set_health_override(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetHealthOverrideSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/ClearHealthOverride" => {
+                    #[allow(non_camel_case_types)]
+                    struct ClearHealthOverrideSvc<T: AdminService>(pub Arc<T>);
+                    impl<
+                        T: AdminService,
+                    > tonic::server::UnaryService<super::OptionalIdRequest>
+                    for ClearHealthOverrideSvc<T> {
+                        type Response = ();
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::OptionalIdRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::clear_health_override(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ClearHealthOverrideSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/RegisterHealthProbe" => {
+                    #[allow(non_camel_case_types)]
+                    struct RegisterHealthProbeSvc<T: AdminService>(pub Arc<T>);
+                    impl<
+                        T: AdminService,
+                    > tonic::server::UnaryService<super::HealthProbeSpec>
+                    for RegisterHealthProbeSvc<T> {
+                        type Response = super::HealthProbe;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::HealthProbeSpec>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::register_health_probe(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RegisterHealthProbeSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/DeregisterHealthProbe" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeregisterHealthProbeSvc<T: AdminService>(pub Arc<T>);
+                    impl<T: AdminService> tonic::server::UnaryService<super::IdRequest>
+                    for DeregisterHealthProbeSvc<T> {
+                        type Response = ();
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::IdRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::deregister_health_probe(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = DeregisterHealthProbeSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/ListHealthProbes" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListHealthProbesSvc<T: AdminService>(pub Arc<T>);
+                    impl<T: AdminService> tonic::server::UnaryService<()>
+                    for ListHealthProbesSvc<T> {
+                        type Response = super::HealthProbeList;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::list_health_probes(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListHealthProbesSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/GetLogFilter" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLogFilterSvc<T: AdminService>(pub Arc<T>);
+                    impl<T: AdminService> tonic::server::UnaryService<()>
+                    for GetLogFilterSvc<T> {
+                        type Response = super::LogFilter;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::get_log_filter(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetLogFilterSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/SetLogFilter" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetLogFilterSvc<T: AdminService>(pub Arc<T>);
+                    impl<
+                        T: AdminService,
+                    > tonic::server::UnaryService<super::SetLogFilterRequest>
+                    for SetLogFilterSvc<T> {
+                        type Response = super::LogFilter;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SetLogFilterRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::set_log_filter(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetLogFilterSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/ListAuditEvents" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListAuditEventsSvc<T: AdminService>(pub Arc<T>);
+                    impl<
+                        T: AdminService,
+                    > tonic::server::UnaryService<super::ListAuditEventsRequest>
+                    for ListAuditEventsSvc<T> {
+                        type Response = super::AuditEventList;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListAuditEventsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::list_audit_events(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListAuditEventsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/midnight.AdminService/ReloadConfig" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReloadConfigSvc<T: AdminService>(pub Arc<T>);
+                    impl<T: AdminService> tonic::server::UnaryService<()>
+                    for ReloadConfigSvc<T> {
+                        type Response = super::ConfigReload;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as AdminService>::reload_config(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ReloadConfigSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        let mut response = http::Response::new(
+                            tonic::body::Body::default(),
+                        );
+                        let headers = response.headers_mut();
+                        headers
+                            .insert(
+                                tonic::Status::GRPC_STATUS,
+                                (tonic::Code::Unimplemented as i32).into(),
+                            );
+                        headers
+                            .insert(
+                                http::header::CONTENT_TYPE,
+                                tonic::metadata::GRPC_CONTENT_TYPE,
+                            );
+                        Ok(response)
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for AdminServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "midnight.AdminService";
+    impl<T> tonic::server::NamedService for AdminServiceServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
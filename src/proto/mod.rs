@@ -4,3 +4,9 @@ mod generated {
 }
 
 pub use generated::*;
+
+/// Descriptors of the midnight services, for reflection.
+pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/proto/generated/descriptors.bin"
+));
@@ -0,0 +1,121 @@
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use super::WebState;
+use crate::core::health::{HealthGraph, ServiceStatus};
+
+#[derive(Debug, Serialize)]
+pub struct Liveness {
+    pub status: &'static str,
+    pub uptime_secs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub phase: &'static str,
+    pub status: Option<&'static str>,
+    pub root_cause: Option<String>,
+    /// Set while the whole server is manually overridden.
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceReport {
+    pub id: String,
+    pub name: String,
+    pub status: &'static str,
+    pub aggregated_status: &'static str,
+    pub root_cause: Option<String>,
+    pub message: Option<String>,
+    pub flapping: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    #[serde(flatten)]
+    pub readiness: Readiness,
+    pub services: Vec<ServiceReport>,
+}
+
+fn http_status(ready: bool) -> StatusCode {
+    if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+async fn graph(state: &WebState) -> Option<HealthGraph> {
+    Some(state.app()?.health().graph().await)
+}
+
+/// Ready once startup has finished, shutdown has not begun, and no service
+/// nothing else depends on is down. Degraded services stay ready.
+fn readiness(state: &WebState, graph: Option<&HealthGraph>) -> Readiness {
+    let phase = state.lifecycle().phase();
+    let overall = graph.map(|g| &g.overall);
+    let healthy = overall.is_some_and(|o| o.status != ServiceStatus::NotServing);
+
+    Readiness {
+        ready: state.lifecycle().is_ready() && healthy,
+        phase: phase.as_str(),
+        status: overall.map(|o| o.status.as_str()),
+        root_cause: overall.and_then(|o| o.root_cause.clone()),
+        message: graph
+            .and_then(|g| g.server_override.as_ref())
+            .map(|o| o.message()),
+    }
+}
+
+pub async fn livez(State(state): State<WebState>) -> (StatusCode, Json<Liveness>) {
+    (
+        StatusCode::OK,
+        Json(Liveness {
+            status: "alive",
+            uptime_secs: state.lifecycle().uptime().as_secs(),
+        }),
+    )
+}
+
+pub async fn readyz(State(state): State<WebState>) -> (StatusCode, Json<Readiness>) {
+    let graph = graph(&state).await;
+    let readiness = readiness(&state, graph.as_ref());
+    (http_status(readiness.ready), Json(readiness))
+}
+
+pub async fn healthz(State(state): State<WebState>) -> (StatusCode, Json<HealthReport>) {
+    let graph = graph(&state).await;
+    let readiness = readiness(&state, graph.as_ref());
+    let services = graph
+        .iter()
+        .flat_map(|g| {
+            g.services.iter().map(|h| {
+                let agg = &g.aggregated[&h.id];
+                ServiceReport {
+                    id: h.id.to_string(),
+                    name: h.name.clone(),
+                    status: h.status.as_str(),
+                    aggregated_status: agg.status.as_str(),
+                    root_cause: agg.root_cause.clone(),
+                    message: h.message.clone(),
+                    flapping: h.flapping,
+                }
+            })
+        })
+        .collect();
+
+    (
+        http_status(readiness.ready),
+        Json(HealthReport {
+            readiness,
+            services,
+        }),
+    )
+}
+
+#[cfg(test)]
+#[path = "../../tests/web/health.rs"]
+mod tests;
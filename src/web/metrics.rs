@@ -0,0 +1,25 @@
+use axum::extract::State;
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+
+use super::WebState;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prometheus scrape endpoint. Unavailable until the database is connected,
+/// since most metrics come from `AppState`.
+pub async fn metrics(State(state): State<WebState>) -> impl IntoResponse {
+    let Some(app) = state.app() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::CONTENT_TYPE, CONTENT_TYPE)],
+            String::new(),
+        );
+    };
+    let body = app.metrics().render(app.db(), app.health()).await;
+    (StatusCode::OK, [(header::CONTENT_TYPE, CONTENT_TYPE)], body)
+}
+
+#[cfg(test)]
+#[path = "../../tests/web/metrics.rs"]
+mod tests;
@@ -0,0 +1,55 @@
+use std::sync::{Arc, OnceLock};
+
+use axum::Router;
+use axum::routing::get;
+use tokio::net::TcpListener;
+
+use crate::core::lifecycle::Lifecycle;
+use crate::core::state::AppState;
+
+pub mod health;
+pub mod metrics;
+
+/// Shared state for the plain HTTP endpoints. The admin listener starts
+/// before the database is ready, so `AppState` is attached later.
+#[derive(Clone)]
+pub struct WebState {
+    lifecycle: Arc<Lifecycle>,
+    app: Arc<OnceLock<Arc<AppState>>>,
+}
+
+impl WebState {
+    pub fn new(lifecycle: Arc<Lifecycle>) -> Self {
+        Self {
+            lifecycle,
+            app: Arc::new(OnceLock::new()),
+        }
+    }
+
+    pub fn attach(&self, app: Arc<AppState>) {
+        let _ = self.app.set(app);
+    }
+
+    pub fn lifecycle(&self) -> &Arc<Lifecycle> {
+        &self.lifecycle
+    }
+
+    pub fn app(&self) -> Option<&Arc<AppState>> {
+        self.app.get()
+    }
+}
+
+pub fn router(state: WebState) -> Router {
+    Router::new()
+        .route("/livez", get(health::livez))
+        .route("/readyz", get(health::readyz))
+        .route("/healthz", get(health::healthz))
+        .route("/metrics", get(metrics::metrics))
+        .with_state(state)
+}
+
+pub async fn serve(listener: TcpListener, state: WebState) {
+    if let Err(err) = axum::serve(listener, router(state)).await {
+        tracing::error!(%err, "admin HTTP server failed");
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+#[test]
+fn anonymous_principal() {
+    assert_eq!(Principal::anonymous().0, "anonymous");
+}
+
+#[test]
+fn limit_defaults_and_caps_page_size() {
+    let mut query = AuditQuery::default();
+    assert_eq!(query.limit(), DEFAULT_PAGE_SIZE);
+    query.page_size = 10;
+    assert_eq!(query.limit(), 10);
+    query.page_size = 10_000;
+    assert_eq!(query.limit(), MAX_PAGE_SIZE);
+}
+
+#[test]
+fn cursor_round_trips() {
+    assert_eq!(decode_cursor(&encode_cursor(42)).unwrap(), Some(42));
+    assert_eq!(decode_cursor("").unwrap(), None);
+}
+
+#[test]
+fn invalid_cursor_is_rejected() {
+    for token in ["abc", "-1", "0", "1.5"] {
+        assert!(matches!(
+            decode_cursor(token),
+            Err(AppError::InvalidArgument(_))
+        ));
+    }
+}
+
+#[test]
+fn set_target_records_in_request_scope() {
+    let scope = AuditScope::default();
+    let mut request = tonic::Request::new(());
+    request.extensions_mut().insert(scope.clone());
+
+    assert!(scope.target().is_none());
+    set_target(&request, "health_probe/1");
+    assert_eq!(scope.target().as_deref(), Some("health_probe/1"));
+}
+
+#[test]
+fn set_target_without_scope_is_ignored() {
+    let request = tonic::Request::new(());
+    set_target(&request, "health_probe/1");
+    assert!(scope(&request).target().is_none());
+}
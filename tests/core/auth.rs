@@ -0,0 +1,379 @@
+use super::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::jwk::Jwk;
+use jsonwebtoken::{EncodingKey, Header};
+use serde_json::json;
+
+const SECRET: &str = "an HS256 test secret of 32+ bytes";
+
+/// A JWKS file with one ES256 key per kid, in a temp directory removed on
+/// drop.
+struct Keys {
+    dir: std::path::PathBuf,
+    signing: Vec<(String, EncodingKey)>,
+}
+
+impl Keys {
+    fn new(kids: &[&str]) -> Self {
+        let dir = std::env::temp_dir().join(format!("midnight-auth-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let mut keys = Self {
+            dir,
+            signing: Vec::new(),
+        };
+        for kid in kids {
+            keys.add(kid);
+        }
+        keys
+    }
+
+    fn path(&self) -> String {
+        self.dir.join("jwks.json").display().to_string()
+    }
+
+    /// Adds a key and rewrites the JWKS.
+    fn add(&mut self, kid: &str) {
+        let pem = rcgen::KeyPair::generate().unwrap().serialize_pem();
+        let key = EncodingKey::from_ec_pem(pem.as_bytes()).unwrap();
+        self.signing.push((kid.to_owned(), key));
+        std::fs::write(self.dir.join("jwks.json"), self.jwks().to_string()).unwrap();
+    }
+
+    fn jwks(&self) -> Value {
+        let keys: Vec<Jwk> = self
+            .signing
+            .iter()
+            .map(|(kid, key)| {
+                let mut jwk = Jwk::from_encoding_key(key, Algorithm::ES256).unwrap();
+                jwk.common.key_id = Some(kid.clone());
+                jwk
+            })
+            .collect();
+        json!({ "keys": keys })
+    }
+
+    fn sign(&self, kid: &str, claims: &Value) -> String {
+        let (_, key) = self.signing.iter().find(|(k, _)| k == kid).unwrap();
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(kid.to_owned());
+        jsonwebtoken::encode(&header, claims, key).unwrap()
+    }
+}
+
+impl Drop for Keys {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn claims(sub: &str) -> Value {
+    json!({ "sub": sub, "exp": now() + 300, "roles": ["ops"] })
+}
+
+fn hs256(claims: &Value, secret: &str) -> String {
+    jsonwebtoken::encode(
+        &Header::new(Algorithm::HS256),
+        claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+/// Rewrites the cached JWKS, as if time had passed.
+fn age(auth: &Authenticator, f: impl FnOnce(&mut CachedJwks)) {
+    let mut cached = (*auth.jwks.cached.load_full().unwrap()).clone();
+    f(&mut cached);
+    auth.jwks.cached.store(Some(Arc::new(cached)));
+}
+
+fn settings(jwks: Option<String>) -> AuthSettings {
+    let mut config = Config::for_tests();
+    config.auth_jwt_secret = Some(Secret::new(SECRET.to_owned()));
+    config.auth_jwks = jwks.map(Secret::new);
+    AuthSettings::from_config(&config).unwrap()
+}
+
+#[test]
+fn settings_need_a_secret_or_jwks() {
+    let mut config = Config::for_tests();
+    assert_eq!(AuthSettings::from_config(&config), None);
+    config.auth_jwks = Some(Secret::new("/etc/midnight/jwks.json".to_owned()));
+    config.auth_issuer = Some("https://id.example.com".to_owned());
+    assert_eq!(
+        AuthSettings::from_config(&config),
+        Some(AuthSettings {
+            secret: None,
+            jwks: Some("/etc/midnight/jwks.json".to_owned()),
+            jwks_refresh: Duration::from_secs(300),
+            issuer: Some("https://id.example.com".to_owned()),
+            audience: None,
+        })
+    );
+}
+
+#[test]
+fn methods_win_over_services() {
+    let mut config = Config::for_tests();
+    config.auth_public = vec![
+        "midnight.HealthService".to_owned(),
+        "midnight.AdminService/GetLogFilter".to_owned(),
+    ];
+    config.auth_authenticated = vec!["midnight.HealthService/Check".to_owned()];
+    let access = |path| access(&config, path);
+    assert_eq!(access("/midnight.HealthService/List"), Access::Public);
+    assert_eq!(
+        access("/midnight.HealthService/Check"),
+        Access::Authenticated
+    );
+    assert_eq!(
+        access("/midnight.AdminService/GetLogFilter"),
+        Access::Public
+    );
+    assert_eq!(
+        access("/midnight.AdminService/SetLogFilter"),
+        Access::Authenticated
+    );
+    assert_eq!(access("/other.Service/Method"), Access::Authenticated);
+}
+
+#[tokio::test]
+async fn verifies_hs256_tokens() {
+    let auth = Authenticator::new();
+    let claims = auth
+        .verify(&settings(None), &hs256(&claims("alice"), SECRET))
+        .await
+        .unwrap();
+    assert_eq!(claims.subject(), Some("alice"));
+    assert_eq!(claims.0["roles"], json!(["ops"]));
+
+    let forged = hs256(&self::claims("alice"), "some other secret of 32+ bytes!!");
+    let err = auth.verify(&settings(None), &forged).await.unwrap_err();
+    assert_eq!(err, "invalid token: InvalidSignature");
+}
+
+#[tokio::test]
+async fn rejects_expired_and_malformed_tokens() {
+    let auth = Authenticator::new();
+    let settings = settings(None);
+    let expired = json!({ "sub": "alice", "exp": now() - LEEWAY_SECS - 10 });
+    let err = auth
+        .verify(&settings, &hs256(&expired, SECRET))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "invalid token: ExpiredSignature");
+
+    let no_exp = json!({ "sub": "alice" });
+    let err = auth
+        .verify(&settings, &hs256(&no_exp, SECRET))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "invalid token: Missing required claim: exp");
+
+    let empty_sub = json!({ "sub": "", "exp": now() + 60 });
+    let err = auth
+        .verify(&settings, &hs256(&empty_sub, SECRET))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "invalid token: sub must be a non-empty string");
+
+    assert!(auth.verify(&settings, "not.a.token").await.is_err());
+}
+
+#[tokio::test]
+async fn checks_issuer_and_audience_when_set() {
+    let auth = Authenticator::new();
+    let mut settings = settings(None);
+    settings.issuer = Some("https://id.example.com".to_owned());
+    settings.audience = Some("midnight".to_owned());
+
+    let mut claims = claims("alice");
+    claims["iss"] = json!("https://id.example.com");
+    claims["aud"] = json!("midnight");
+    auth.verify(&settings, &hs256(&claims, SECRET))
+        .await
+        .unwrap();
+
+    claims["aud"] = json!("someone-else");
+    let err = auth
+        .verify(&settings, &hs256(&claims, SECRET))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "invalid token: InvalidAudience");
+
+    claims["aud"] = json!("midnight");
+    claims["iss"] = json!("https://evil.example.com");
+    let err = auth
+        .verify(&settings, &hs256(&claims, SECRET))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "invalid token: InvalidIssuer");
+}
+
+#[tokio::test]
+async fn verifies_es256_tokens_from_jwks_file() {
+    let keys = Keys::new(&["k1", "k2"]);
+    let auth = Authenticator::new();
+    let settings = settings(Some(keys.path()));
+    for kid in ["k1", "k2"] {
+        let claims = auth
+            .verify(&settings, &keys.sign(kid, &claims("bob")))
+            .await
+            .unwrap();
+        assert_eq!(claims.subject(), Some("bob"));
+    }
+
+    // Signed by k2's key but naming k1.
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some("k1".to_owned());
+    let forged = jsonwebtoken::encode(&header, &claims("bob"), &keys.signing[1].1).unwrap();
+    assert_eq!(
+        auth.verify(&settings, &forged).await.unwrap_err(),
+        "invalid token: InvalidSignature"
+    );
+}
+
+#[tokio::test]
+async fn algorithms_need_their_keys() {
+    let keys = Keys::new(&["k1"]);
+    let auth = Authenticator::new();
+
+    let mut jwks_only = settings(Some(keys.path()));
+    jwks_only.secret = None;
+    let err = auth
+        .verify(&jwks_only, &hs256(&claims("alice"), SECRET))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "HS256 tokens are not accepted");
+
+    let err = auth
+        .verify(&settings(None), &keys.sign("k1", &claims("bob")))
+        .await
+        .unwrap_err();
+    assert_eq!(err, "ES256 tokens are not accepted");
+}
+
+#[tokio::test]
+async fn refetches_jwks_for_rotated_keys() {
+    let mut keys = Keys::new(&["k1"]);
+    let auth = Authenticator::new();
+    let settings = settings(Some(keys.path()));
+    auth.verify(&settings, &keys.sign("k1", &claims("bob")))
+        .await
+        .unwrap();
+
+    keys.add("k2");
+    let token = keys.sign("k2", &claims("bob"));
+    // Fetched too recently to try again.
+    assert_eq!(
+        auth.verify(&settings, &token).await.unwrap_err(),
+        "no key \"k2\" in the JWKS"
+    );
+
+    age(&auth, |cached| cached.fetched_at -= MIN_REFETCH);
+    auth.verify(&settings, &token).await.unwrap();
+}
+
+#[tokio::test]
+async fn keeps_cached_keys_when_jwks_is_unavailable() {
+    let keys = Keys::new(&["k1"]);
+    let auth = Authenticator::new();
+    let mut settings = settings(Some(keys.path()));
+    let token = keys.sign("k1", &claims("bob"));
+    auth.verify(&settings, &token).await.unwrap();
+
+    std::fs::remove_file(keys.path()).unwrap();
+    age(&auth, |cached| cached.expires_at = Instant::now());
+    auth.verify(&settings, &token).await.unwrap();
+
+    settings.jwks = Some(keys.dir.join("missing.json").display().to_string());
+    assert_eq!(
+        auth.verify(&settings, &token).await.unwrap_err(),
+        "signing keys unavailable"
+    );
+}
+
+#[tokio::test]
+async fn caches_jwks_from_url() {
+    let keys = Keys::new(&["k1"]);
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let jwks = keys.jwks();
+    let counter = Arc::clone(&fetches);
+    let app = axum::Router::new().route(
+        "/jwks.json",
+        axum::routing::get(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let jwks = jwks.clone();
+            async move { axum::Json(jwks) }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let auth = Authenticator::new();
+    let settings = settings(Some(format!("http://{addr}/jwks.json")));
+    let token = keys.sign("k1", &claims("bob"));
+    for _ in 0..3 {
+        auth.verify(&settings, &token).await.unwrap();
+    }
+    assert_eq!(fetches.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn slow_jwks_fetches_block_nobody() {
+    let keys = Keys::new(&["k1"]);
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let jwks = keys.jwks();
+    let counter = Arc::clone(&fetches);
+    let app = axum::Router::new().route(
+        "/jwks.json",
+        axum::routing::get(move || {
+            let slow = counter.fetch_add(1, Ordering::SeqCst) > 0;
+            let jwks = jwks.clone();
+            async move {
+                if slow {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                axum::Json(jwks)
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let auth = Authenticator::new();
+    let settings = settings(Some(format!("http://{addr}/jwks.json")));
+    let token = keys.sign("k1", &claims("bob"));
+    auth.verify(&settings, &token).await.unwrap();
+
+    // Expired keys are refreshed in the background, once, while they stay
+    // in use, and HS256 tokens never wait on the JWKS.
+    age(&auth, |cached| cached.expires_at = Instant::now());
+    let quick = Duration::from_secs(1);
+    for _ in 0..3 {
+        tokio::time::timeout(quick, auth.verify(&settings, &token))
+            .await
+            .expect("waited for the JWKS fetch")
+            .unwrap();
+    }
+    tokio::time::timeout(
+        quick,
+        auth.verify(&settings, &hs256(&claims("bob"), SECRET)),
+    )
+    .await
+    .expect("waited for the JWKS fetch")
+    .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(fetches.load(Ordering::SeqCst), 2);
+}
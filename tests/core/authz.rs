@@ -0,0 +1,171 @@
+use super::*;
+use serde_json::json;
+
+fn rule(s: &str) -> Rule {
+    s.parse().unwrap()
+}
+
+fn policy(rules: &[&str], bindings: &[(&str, &str)]) -> Policy {
+    Policy::new(
+        rules.iter().map(|r| rule(r)),
+        bindings
+            .iter()
+            .map(|(p, r)| ((*p).to_owned(), (*r).to_owned())),
+    )
+}
+
+fn roles(roles: &[&str]) -> Vec<String> {
+    roles.iter().map(|r| (*r).to_owned()).collect()
+}
+
+fn alice() -> Principal {
+    Principal("alice".to_owned())
+}
+
+#[test]
+fn parses_rules() {
+    assert_eq!(
+        rule("midnight.AdminService/SetLogFilter = admin+ops"),
+        Rule {
+            pattern: "midnight.AdminService/SetLogFilter".to_owned(),
+            roles: roles(&["admin", "ops"]),
+        }
+    );
+    assert_eq!(rule("*=admin").pattern, ANY_METHOD);
+
+    let err = |s: &str| s.parse::<Rule>().unwrap_err();
+    assert_eq!(err("midnight.AdminService"), "expected pattern=role+role");
+    assert_eq!(
+        err("midnight.AdminService="),
+        "no roles for midnight.AdminService"
+    );
+    assert_eq!(
+        err("/midnight.AdminService=admin"),
+        "\"/midnight.AdminService\" is not *, a package.Service or package.Service/Method name"
+    );
+}
+
+#[test]
+fn most_specific_rule_wins() {
+    let policy = policy(
+        &[
+            "*=admin",
+            "midnight.AdminService=ops",
+            "midnight.AdminService/GetLogFilter=viewer",
+        ],
+        &[],
+    );
+    assert_eq!(
+        policy.required("/midnight.AdminService/GetLogFilter"),
+        Some(&roles(&["viewer"])[..])
+    );
+    assert_eq!(
+        policy.required("/midnight.AdminService/SetLogFilter"),
+        Some(&roles(&["ops"])[..])
+    );
+    assert_eq!(
+        policy.required("/other.Service/Method"),
+        Some(&roles(&["admin"])[..])
+    );
+}
+
+#[test]
+fn denies_by_default() {
+    let policy = policy(&["midnight.HealthService=*"], &[]);
+    assert_eq!(
+        policy.check(
+            "/midnight.AdminService/SetLogFilter",
+            &alice(),
+            &roles(&["admin"])
+        ),
+        Err(Denial::NoRule)
+    );
+    assert_eq!(
+        Policy::default().required("/midnight.HealthService/List"),
+        None
+    );
+}
+
+#[test]
+fn any_role_lets_everyone_in() {
+    let policy = policy(&["midnight.HealthService=*"], &[]);
+    assert_eq!(
+        policy.check(
+            "/midnight.HealthService/ListHealthServices",
+            &Principal::anonymous(),
+            &[]
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn needs_one_of_the_roles() {
+    let policy = policy(&["midnight.AdminService=admin+ops"], &[]);
+    let path = "/midnight.AdminService/SetLogFilter";
+    assert_eq!(
+        policy.check(path, &alice(), &roles(&["viewer", "ops"])),
+        Ok(())
+    );
+    assert_eq!(
+        policy.check(path, &alice(), &roles(&["viewer"])),
+        Err(Denial::MissingRole(roles(&["admin", "ops"])))
+    );
+    assert_eq!(
+        Denial::MissingRole(roles(&["admin", "ops"])).to_string(),
+        "it needs one of the roles admin, ops"
+    );
+}
+
+#[test]
+fn bindings_grant_roles_to_principals() {
+    let policy = policy(
+        &["midnight.AdminService=admin"],
+        &[("uid:0", "admin"), ("x509:O=Acme, CN=ops-bot", "admin")],
+    );
+    let path = "/midnight.AdminService/ReloadConfig";
+    assert_eq!(policy.check(path, &Principal::unix_peer(0), &[]), Ok(()));
+    assert_eq!(
+        policy.check(path, &Principal::certificate("O=Acme, CN=ops-bot"), &[]),
+        Ok(())
+    );
+    assert!(
+        policy
+            .check(path, &Principal::unix_peer(1000), &[])
+            .is_err()
+    );
+    // Token subjects can't pass for the other kinds.
+    assert!(policy.check(path, &Principal::token("uid:0"), &[]).is_err());
+    assert!(
+        policy
+            .check(path, &Principal::token("O=Acme, CN=ops-bot"), &[])
+            .is_err()
+    );
+}
+
+#[test]
+fn rules_for_one_pattern_add_up() {
+    let policy = policy(
+        &["midnight.AdminService=admin", "midnight.AdminService=ops"],
+        &[],
+    );
+    assert_eq!(
+        policy.required("/midnight.AdminService/SetLogFilter"),
+        Some(&roles(&["admin", "ops"])[..])
+    );
+}
+
+#[test]
+fn reads_roles_from_claims() {
+    let claims = Claims(
+        json!({ "sub": "alice", "roles": ["admin", 7, "ops"], "scope": "read write" })
+            .as_object()
+            .unwrap()
+            .clone(),
+    );
+    assert_eq!(
+        token_roles(Some(&claims), &roles(&["roles", "scope", "missing"])),
+        roles(&["admin", "ops", "read", "write"])
+    );
+    assert!(token_roles(None, &roles(&["roles"])).is_empty());
+}
@@ -43,6 +43,13 @@ fn already_exists_maps_to_grpc_already_exists() {
     assert!(status.message().contains("duplicate"));
 }
 
+#[test]
+fn failed_precondition_maps_to_grpc_failed_precondition() {
+    let status: Status = AppError::FailedPrecondition("not ready".into()).into();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert!(status.message().contains("not ready"));
+}
+
 #[test]
 fn anyhow_error_maps_to_grpc_internal() {
     let err = AppError::Anyhow(anyhow::anyhow!("something broke"));
@@ -50,6 +57,14 @@ fn anyhow_error_maps_to_grpc_internal() {
     assert_eq!(status.code(), Code::Internal);
 }
 
+#[test]
+fn sqlx_error_details_stay_out_of_status() {
+    let err = AppError::Sqlx(sqlx::Error::Protocol("password=hunter2".into()));
+    let status: Status = err.into();
+    assert_eq!(status.code(), Code::Internal);
+    assert_eq!(status.message(), "internal: database error");
+}
+
 #[test]
 fn display_formats_correctly() {
     assert_eq!(
@@ -72,3 +87,29 @@ fn from_anyhow_conversion() {
     let app_err: AppError = anyhow_err.into();
     assert!(matches!(app_err, AppError::Anyhow(_)));
 }
+
+#[test]
+fn status_carries_request_id_in_details() {
+    let id = RequestId::parse("client-123").unwrap();
+    let status = AppError::NotFound("missing".into()).into_status(Some(&id));
+    assert_eq!(status.code(), Code::NotFound);
+    assert!(status.message().contains("missing"));
+    let info = status.get_error_details().request_info().cloned().unwrap();
+    assert_eq!(info.request_id, "client-123");
+}
+
+#[test]
+fn status_has_no_details_outside_request() {
+    let status: Status = AppError::Internal("boom".into()).into();
+    assert!(status.details().is_empty());
+}
+
+#[tokio::test]
+async fn conversion_uses_current_request_id() {
+    let id = RequestId::parse("client-123").unwrap();
+    let status = id
+        .scope(async { Status::from(AppError::InvalidArgument("bad".into())) })
+        .await;
+    let info = status.get_error_details().request_info().cloned().unwrap();
+    assert_eq!(info.request_id, "client-123");
+}
@@ -293,6 +293,7 @@ fn service_status_equality() {
     assert_eq!(ServiceStatus::Serving, ServiceStatus::Serving);
     assert_eq!(ServiceStatus::NotServing, ServiceStatus::NotServing);
     assert_ne!(ServiceStatus::Serving, ServiceStatus::NotServing);
+    assert_ne!(ServiceStatus::Degraded, ServiceStatus::Serving);
 }
 
 #[test]
@@ -305,6 +306,10 @@ fn service_health_clone() {
         registered_at: Instant::now(),
         version: Some("1.0.0".to_owned()),
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let cloned = health.clone();
@@ -313,3 +318,1066 @@ fn service_health_clone() {
     assert_eq!(cloned.status, health.status);
     assert_eq!(cloned.version, health.version);
 }
+
+#[tokio::test]
+async fn status_by_name_returns_none_for_unknown() {
+    let registry = HealthRegistry::new();
+    assert!(registry.status_by_name("missing").await.is_none());
+}
+
+#[tokio::test]
+async fn status_by_name_combines_duplicates() {
+    let registry = HealthRegistry::new();
+    registry
+        .register("dup", Duration::from_secs(60), None, ok_check())
+        .await;
+    assert_eq!(
+        registry.status_by_name("dup").await,
+        Some(ServiceStatus::Serving)
+    );
+
+    registry
+        .register("dup", Duration::from_secs(60), None, failing_check("err"))
+        .await;
+    assert_eq!(
+        registry.status_by_name("dup").await,
+        Some(ServiceStatus::NotServing)
+    );
+}
+
+#[tokio::test]
+async fn overall_status_serving_when_empty() {
+    let registry = HealthRegistry::new();
+    assert_eq!(registry.overall_status().await, ServiceStatus::Serving);
+}
+
+#[tokio::test]
+async fn overall_status_not_serving_if_any_down() {
+    let registry = HealthRegistry::new();
+    registry
+        .register("up", Duration::from_secs(60), None, ok_check())
+        .await;
+    registry
+        .register("down", Duration::from_secs(60), None, failing_check("err"))
+        .await;
+    assert_eq!(registry.overall_status().await, ServiceStatus::NotServing);
+}
+
+#[tokio::test]
+async fn subscribe_receives_registration_and_deregistration() {
+    let registry = HealthRegistry::new();
+    let mut events = registry.subscribe();
+
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, ok_check())
+        .await;
+    match events.recv().await.unwrap() {
+        HealthEvent::Registered(h) => assert_eq!(h.id, id),
+        other => panic!("unexpected event: {other:?}"),
+    }
+
+    registry.deregister(&id).await;
+    match events.recv().await.unwrap() {
+        HealthEvent::Deregistered(h) => assert_eq!(h.id, id),
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn subscribe_receives_status_changes_only() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let should_fail = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&should_fail);
+
+    let check: HealthCheckFn = Box::new(move || {
+        let flag = Arc::clone(&flag);
+        Box::pin(async move {
+            if flag.load(Ordering::Relaxed) {
+                Err("went down".to_owned())
+            } else {
+                Ok(())
+            }
+        })
+    });
+
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("flaky", Duration::from_millis(50), None, check)
+        .await;
+    let mut events = registry.subscribe();
+
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert!(events.try_recv().is_err());
+
+    should_fail.store(true, Ordering::Relaxed);
+    let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    match event {
+        HealthEvent::Changed(h) => {
+            assert_eq!(h.id, id);
+            assert_eq!(h.status, ServiceStatus::NotServing);
+        }
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+fn toggling_check() -> HealthCheckFn {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let state = Arc::new(AtomicBool::new(false));
+    Box::new(move || {
+        let fail = !state.fetch_xor(true, Ordering::Relaxed);
+        Box::pin(async move {
+            if fail {
+                Err("toggled down".to_owned())
+            } else {
+                Ok(())
+            }
+        })
+    })
+}
+
+#[tokio::test]
+async fn history_records_initial_probe() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, failing_check("boom"))
+        .await;
+
+    let history = registry.history(&id).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].outcome, ProbeOutcome::Failure);
+    assert_eq!(history[0].error.as_deref(), Some("boom"));
+}
+
+#[tokio::test]
+async fn history_records_timeouts() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register(
+            "slow",
+            Duration::from_secs(60),
+            None,
+            slow_check(Duration::from_secs(10)),
+        )
+        .await;
+
+    let history = registry.history(&id).await.unwrap();
+    assert_eq!(history[0].outcome, ProbeOutcome::Timeout);
+    assert!(history[0].latency >= DEFAULT_PROBE_TIMEOUT);
+}
+
+#[tokio::test]
+async fn history_is_bounded() {
+    let registry = HealthRegistry::with_settings(HealthSettings {
+        history_capacity: 3,
+        ..HealthSettings::default()
+    });
+    let id = registry
+        .register("svc", Duration::from_millis(20), None, ok_check())
+        .await;
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    let history = registry.history(&id).await.unwrap();
+    assert_eq!(history.len(), 3);
+    assert!(history.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+}
+
+#[tokio::test]
+async fn history_removed_on_deregister() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, ok_check())
+        .await;
+    registry.deregister(&id).await;
+    assert!(registry.history(&id).await.is_none());
+}
+
+#[tokio::test]
+async fn toggling_service_is_flapping() {
+    let registry = HealthRegistry::with_settings(HealthSettings {
+        flap_threshold: 3,
+        ..HealthSettings::default()
+    });
+    let id = registry
+        .register("flappy", Duration::from_millis(20), None, toggling_check())
+        .await;
+    assert!(!registry.get(&id).await.unwrap().flapping);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(registry.get(&id).await.unwrap().flapping);
+}
+
+#[tokio::test]
+async fn flapping_ignores_transitions_outside_window() {
+    let registry = HealthRegistry::with_settings(HealthSettings {
+        flap_window: Duration::ZERO,
+        flap_threshold: 1,
+        ..HealthSettings::default()
+    });
+    let id = registry
+        .register("flappy", Duration::from_millis(20), None, toggling_check())
+        .await;
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(!registry.get(&id).await.unwrap().flapping);
+}
+
+#[tokio::test]
+async fn flap_threshold_zero_disables_detection() {
+    let registry = HealthRegistry::with_settings(HealthSettings {
+        flap_threshold: 0,
+        ..HealthSettings::default()
+    });
+    let id = registry
+        .register("flappy", Duration::from_millis(20), None, toggling_check())
+        .await;
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(!registry.get(&id).await.unwrap().flapping);
+}
+
+fn switch_check(should_fail: &Arc<std::sync::atomic::AtomicBool>) -> HealthCheckFn {
+    use std::sync::atomic::Ordering;
+
+    let flag = Arc::clone(should_fail);
+    Box::new(move || {
+        let flag = Arc::clone(&flag);
+        Box::pin(async move {
+            if flag.load(Ordering::Relaxed) {
+                Err("down".to_owned())
+            } else {
+                Ok(())
+            }
+        })
+    })
+}
+
+#[tokio::test]
+async fn failures_below_threshold_report_degraded() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register_with_options(
+            "svc",
+            Duration::from_secs(60),
+            None,
+            failing_check("blip"),
+            RegisterOptions {
+                failure_threshold: 3,
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::Degraded);
+    assert_eq!(health.message.as_deref(), Some("blip"));
+}
+
+#[tokio::test]
+async fn failures_reaching_threshold_report_not_serving() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let should_fail = Arc::new(AtomicBool::new(false));
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register_with_options(
+            "svc",
+            Duration::from_millis(40),
+            None,
+            switch_check(&should_fail),
+            RegisterOptions {
+                failure_threshold: 3,
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+    let mut events = registry.subscribe();
+
+    should_fail.store(true, Ordering::Relaxed);
+    let mut statuses = Vec::new();
+    while statuses.last() != Some(&ServiceStatus::NotServing) {
+        let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if let HealthEvent::Changed(h) = event {
+            statuses.push(h.status);
+        }
+    }
+    assert_eq!(
+        statuses,
+        vec![ServiceStatus::Degraded, ServiceStatus::NotServing]
+    );
+    assert_eq!(
+        registry.history(&id).await.unwrap().last().unwrap().outcome,
+        ProbeOutcome::Failure
+    );
+}
+
+#[tokio::test]
+async fn success_while_degraded_restores_serving() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let should_fail = Arc::new(AtomicBool::new(true));
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register_with_options(
+            "svc",
+            Duration::from_millis(40),
+            None,
+            switch_check(&should_fail),
+            RegisterOptions {
+                failure_threshold: 100,
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        registry.get(&id).await.unwrap().status,
+        ServiceStatus::Degraded
+    );
+
+    should_fail.store(false, Ordering::Relaxed);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::Serving);
+    assert!(health.message.is_none());
+}
+
+#[tokio::test]
+async fn recovery_requires_success_threshold() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let should_fail = Arc::new(AtomicBool::new(true));
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register_with_options(
+            "svc",
+            Duration::from_millis(40),
+            None,
+            switch_check(&should_fail),
+            RegisterOptions {
+                success_threshold: 3,
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        registry.get(&id).await.unwrap().status,
+        ServiceStatus::NotServing
+    );
+    let mut events = registry.subscribe();
+
+    should_fail.store(false, Ordering::Relaxed);
+    let mut changes = Vec::new();
+    while changes.last().map(|h: &ServiceHealth| h.status) != Some(ServiceStatus::Serving) {
+        let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        if let HealthEvent::Changed(h) = event {
+            changes.push(h);
+        }
+    }
+
+    let messages: Vec<_> = changes.iter().map(|h| h.message.as_deref()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            Some("recovering (1/3 successful probes)"),
+            Some("recovering (2/3 successful probes)"),
+            None,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn overall_status_degraded_if_any_degraded() {
+    let registry = HealthRegistry::new();
+    registry
+        .register("up", Duration::from_secs(60), None, ok_check())
+        .await;
+    registry
+        .register_with_options(
+            "blip",
+            Duration::from_secs(60),
+            None,
+            failing_check("err"),
+            RegisterOptions {
+                failure_threshold: 2,
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(registry.overall_status().await, ServiceStatus::Degraded);
+
+    registry
+        .register("down", Duration::from_secs(60), None, failing_check("err"))
+        .await;
+    assert_eq!(registry.overall_status().await, ServiceStatus::NotServing);
+}
+
+#[tokio::test]
+async fn per_registration_timeout_applies() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register_with_options(
+            "slow",
+            Duration::from_secs(60),
+            None,
+            slow_check(Duration::from_millis(200)),
+            RegisterOptions {
+                timeout: Duration::from_millis(50),
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::NotServing);
+    assert_eq!(health.message.as_deref(), Some("probe timed out"));
+    assert_eq!(health.timeout, Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn default_timeout_is_reported() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, ok_check())
+        .await;
+    assert_eq!(
+        registry.get(&id).await.unwrap().timeout,
+        DEFAULT_PROBE_TIMEOUT
+    );
+}
+
+#[tokio::test]
+async fn next_probe_at_follows_interval() {
+    let registry = HealthRegistry::new();
+    let before = SystemTime::now();
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let next = registry.get(&id).await.unwrap().next_probe_at;
+    assert!(next >= before + Duration::from_secs(60));
+    assert!(next <= SystemTime::now() + Duration::from_secs(60));
+}
+
+#[tokio::test]
+async fn initial_delay_defers_first_probe() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register_with_options(
+            "svc",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            RegisterOptions {
+                initial_delay: Duration::from_millis(50),
+                success_threshold: 3,
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::NotServing);
+    assert_eq!(health.message.as_deref(), Some("awaiting first probe"));
+    assert!(registry.history(&id).await.unwrap().is_empty());
+
+    tokio::time::sleep(Duration::from_millis(120)).await;
+
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::Serving);
+    assert_eq!(registry.history(&id).await.unwrap().len(), 1);
+}
+
+fn failing_state(backoff: Option<Backoff>, failures: u32) -> ProbeState {
+    let mut state = ProbeState::new(RegisterOptions {
+        backoff,
+        ..RegisterOptions::default()
+    });
+    state.consecutive_failures = failures;
+    state
+}
+
+#[test]
+fn next_delay_without_backoff_is_interval() {
+    let state = failing_state(None, 5);
+    assert_eq!(
+        state.next_delay(Duration::from_secs(10)),
+        Duration::from_secs(10)
+    );
+}
+
+#[test]
+fn next_delay_grows_exponentially_while_failing() {
+    let backoff = Backoff {
+        multiplier: 2.0,
+        max_interval: Duration::from_secs(1000),
+        jitter: 0.0,
+    };
+    let interval = Duration::from_secs(10);
+
+    assert_eq!(
+        failing_state(Some(backoff.clone()), 0).next_delay(interval),
+        interval
+    );
+    assert_eq!(
+        failing_state(Some(backoff.clone()), 1).next_delay(interval),
+        interval
+    );
+    assert_eq!(
+        failing_state(Some(backoff.clone()), 2).next_delay(interval),
+        Duration::from_secs(20)
+    );
+    assert_eq!(
+        failing_state(Some(backoff), 4).next_delay(interval),
+        Duration::from_secs(80)
+    );
+}
+
+#[test]
+fn next_delay_capped_at_max_interval() {
+    let backoff = Backoff {
+        multiplier: 10.0,
+        max_interval: Duration::from_secs(60),
+        jitter: 0.0,
+    };
+    assert_eq!(
+        failing_state(Some(backoff), 10).next_delay(Duration::from_secs(10)),
+        Duration::from_secs(60)
+    );
+}
+
+#[test]
+fn next_delay_jitter_stays_within_bounds() {
+    let backoff = Backoff {
+        multiplier: 2.0,
+        max_interval: Duration::from_secs(1000),
+        jitter: 0.5,
+    };
+    let state = failing_state(Some(backoff), 2);
+    for _ in 0..100 {
+        let delay = state.next_delay(Duration::from_secs(10));
+        assert!(delay >= Duration::from_secs(10));
+        assert!(delay <= Duration::from_secs(30));
+    }
+}
+
+#[tokio::test]
+async fn backoff_pushes_next_probe_out() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register_with_options(
+            "down",
+            Duration::from_millis(20),
+            None,
+            failing_check("err"),
+            RegisterOptions {
+                backoff: Some(Backoff {
+                    multiplier: 4.0,
+                    max_interval: Duration::from_secs(60),
+                    jitter: 0.0,
+                }),
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // 20ms, 80ms, 320ms: only the first two background probes fit.
+    assert_eq!(registry.history(&id).await.unwrap().len(), 3);
+}
+
+fn depends_on(deps: Vec<Dependency>) -> RegisterOptions {
+    RegisterOptions {
+        dependencies: deps,
+        ..RegisterOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn critical_dependency_failure_propagates() {
+    let registry = HealthRegistry::new();
+    registry
+        .register(
+            "database",
+            Duration::from_secs(60),
+            None,
+            failing_check("down"),
+        )
+        .await;
+    let server = registry
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("database")]),
+        )
+        .await
+        .unwrap();
+
+    let agg = registry.aggregated(&server).await.unwrap();
+    assert_eq!(agg.status, ServiceStatus::NotServing);
+    assert_eq!(agg.root_cause.as_deref(), Some("database"));
+    assert_eq!(
+        registry.get(&server).await.unwrap().status,
+        ServiceStatus::Serving
+    );
+}
+
+#[tokio::test]
+async fn optional_dependency_failure_degrades() {
+    let registry = HealthRegistry::new();
+    registry
+        .register(
+            "cache",
+            Duration::from_secs(60),
+            None,
+            failing_check("down"),
+        )
+        .await;
+    let server = registry
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::optional("cache")]),
+        )
+        .await
+        .unwrap();
+
+    let agg = registry.aggregated(&server).await.unwrap();
+    assert_eq!(agg.status, ServiceStatus::Degraded);
+    assert_eq!(agg.root_cause.as_deref(), Some("cache"));
+    assert_eq!(registry.overall_status().await, ServiceStatus::Degraded);
+    assert_eq!(
+        registry.status_by_name("server").await,
+        Some(ServiceStatus::Degraded)
+    );
+}
+
+#[tokio::test]
+async fn root_cause_follows_transitive_dependencies() {
+    let registry = HealthRegistry::new();
+    registry
+        .register("disk", Duration::from_secs(60), None, failing_check("full"))
+        .await;
+    registry
+        .register_with_options(
+            "database",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("disk")]),
+        )
+        .await
+        .unwrap();
+    registry
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("database")]),
+        )
+        .await
+        .unwrap();
+
+    let graph = registry.graph().await;
+    assert_eq!(graph.edges.len(), 2);
+    assert_eq!(graph.overall.status, ServiceStatus::NotServing);
+    assert_eq!(graph.overall.root_cause.as_deref(), Some("disk"));
+}
+
+#[tokio::test]
+async fn missing_dependency_counts_as_not_serving() {
+    let registry = HealthRegistry::new();
+    let server = registry
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("database")]),
+        )
+        .await
+        .unwrap();
+
+    let agg = registry.aggregated(&server).await.unwrap();
+    assert_eq!(agg.status, ServiceStatus::NotServing);
+    assert_eq!(agg.root_cause.as_deref(), Some("database"));
+
+    registry
+        .register("database", Duration::from_secs(60), None, ok_check())
+        .await;
+    let agg = registry.aggregated(&server).await.unwrap();
+    assert_eq!(agg.status, ServiceStatus::Serving);
+    assert!(agg.root_cause.is_none());
+}
+
+#[tokio::test]
+async fn overall_status_ignores_failures_absorbed_by_optional_edges() {
+    let registry = HealthRegistry::new();
+    registry
+        .register(
+            "cache",
+            Duration::from_secs(60),
+            None,
+            failing_check("down"),
+        )
+        .await;
+    registry
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::optional("cache")]),
+        )
+        .await
+        .unwrap();
+
+    let graph = registry.graph().await;
+    assert_eq!(graph.overall.status, ServiceStatus::Degraded);
+    assert_eq!(graph.overall.root_cause.as_deref(), Some("cache"));
+}
+
+#[tokio::test]
+async fn self_dependency_is_rejected() {
+    let registry = HealthRegistry::new();
+    let err = registry
+        .register_with_options(
+            "loop",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("loop")]),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AppError::InvalidArgument(_)));
+    assert!(registry.list().await.is_empty());
+}
+
+#[tokio::test]
+async fn dependency_cycle_is_rejected() {
+    let registry = HealthRegistry::new();
+    registry
+        .register_with_options(
+            "a",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("b")]),
+        )
+        .await
+        .unwrap();
+    registry
+        .register_with_options(
+            "b",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::optional("c")]),
+        )
+        .await
+        .unwrap();
+
+    let err = registry
+        .register_with_options(
+            "c",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("a")]),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid argument: dependency cycle: c -> a -> b -> c"
+    );
+    assert_eq!(registry.list().await.len(), 2);
+}
+
+#[tokio::test]
+async fn diamond_dependencies_are_not_cycles() {
+    let registry = HealthRegistry::new();
+    registry
+        .register("db", Duration::from_secs(60), None, ok_check())
+        .await;
+    for name in ["left", "right"] {
+        registry
+            .register_with_options(
+                name,
+                Duration::from_secs(60),
+                None,
+                ok_check(),
+                depends_on(vec![Dependency::critical("db")]),
+            )
+            .await
+            .unwrap();
+    }
+    registry
+        .register_with_options(
+            "top",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![
+                Dependency::critical("left"),
+                Dependency::critical("right"),
+            ]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(registry.overall_status().await, ServiceStatus::Serving);
+}
+
+#[tokio::test]
+async fn override_holds_while_probes_pass() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("svc", Duration::from_millis(20), None, ok_check())
+        .await;
+
+    let manual = HealthOverride::new(ServiceStatus::NotServing, "db maintenance", None);
+    let previous = registry.set_override(&id, manual.clone()).await.unwrap();
+    assert!(previous.is_none());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::NotServing);
+    assert_eq!(
+        health.message.as_deref(),
+        Some("manual override: db maintenance")
+    );
+    assert_eq!(health.manual_override, Some(manual));
+}
+
+#[tokio::test]
+async fn clearing_override_restores_probe_status() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, failing_check("down"))
+        .await;
+
+    let manual = HealthOverride::new(ServiceStatus::Serving, "known issue", None);
+    registry.set_override(&id, manual).await.unwrap();
+    assert_eq!(
+        registry.get(&id).await.unwrap().status,
+        ServiceStatus::Serving
+    );
+
+    let previous = registry.clear_override(&id).await.unwrap();
+    assert_eq!(previous.unwrap().reason, "known issue");
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::NotServing);
+    assert_eq!(health.message.as_deref(), Some("down"));
+    assert!(health.manual_override.is_none());
+}
+
+#[tokio::test]
+async fn override_expires_after_ttl() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let manual = HealthOverride::new(
+        ServiceStatus::NotServing,
+        "short",
+        Some(Duration::from_millis(50)),
+    );
+    assert!(manual.expires_at.is_some());
+    registry.set_override(&id, manual).await.unwrap();
+    assert_eq!(
+        registry.get(&id).await.unwrap().status,
+        ServiceStatus::NotServing
+    );
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::Serving);
+    assert!(health.manual_override.is_none());
+}
+
+#[tokio::test]
+async fn expired_override_does_not_clear_replacement() {
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("svc", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let short = HealthOverride::new(
+        ServiceStatus::NotServing,
+        "short",
+        Some(Duration::from_millis(50)),
+    );
+    registry.set_override(&id, short).await.unwrap();
+    let long = HealthOverride::new(ServiceStatus::Degraded, "long", None);
+    registry.set_override(&id, long).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let health = registry.get(&id).await.unwrap();
+    assert_eq!(health.status, ServiceStatus::Degraded);
+    assert_eq!(health.manual_override.unwrap().reason, "long");
+}
+
+#[tokio::test]
+async fn override_unknown_service_is_not_found() {
+    let registry = HealthRegistry::new();
+    let manual = HealthOverride::new(ServiceStatus::NotServing, "x", None);
+    let err = registry
+        .set_override(&Uuid::new_v4(), manual)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AppError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn override_propagates_to_dependents() {
+    let registry = HealthRegistry::new();
+    let db = registry
+        .register("database", Duration::from_secs(60), None, ok_check())
+        .await;
+    registry
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            depends_on(vec![Dependency::critical("database")]),
+        )
+        .await
+        .unwrap();
+
+    let manual = HealthOverride::new(ServiceStatus::NotServing, "maintenance", None);
+    registry.set_override(&db, manual).await.unwrap();
+
+    let graph = registry.graph().await;
+    assert_eq!(graph.overall.status, ServiceStatus::NotServing);
+    assert_eq!(graph.overall.root_cause.as_deref(), Some("database"));
+}
+
+#[tokio::test]
+async fn server_override_sets_overall_status() {
+    let registry = HealthRegistry::new();
+    registry
+        .register("server", Duration::from_secs(60), None, ok_check())
+        .await;
+    let mut events = registry.subscribe();
+
+    let manual = HealthOverride::new(ServiceStatus::NotServing, "draining", None);
+    registry.set_server_override(manual.clone()).await;
+    assert_eq!(registry.overall_status().await, ServiceStatus::NotServing);
+    assert_eq!(registry.server_override().await, Some(manual.clone()));
+    assert_eq!(
+        registry.status_by_name("server").await,
+        Some(ServiceStatus::Serving)
+    );
+    match events.recv().await.unwrap() {
+        HealthEvent::ServerOverride(o) => assert_eq!(o, Some(manual)),
+        other => panic!("unexpected event: {other:?}"),
+    }
+
+    registry.clear_server_override().await;
+    assert_eq!(registry.overall_status().await, ServiceStatus::Serving);
+    assert!(registry.graph().await.server_override.is_none());
+}
+
+#[tokio::test]
+async fn server_override_expires_after_ttl() {
+    let registry = HealthRegistry::new();
+    let manual = HealthOverride::new(
+        ServiceStatus::NotServing,
+        "draining",
+        Some(Duration::from_millis(50)),
+    );
+    registry.set_server_override(manual).await;
+    assert_eq!(registry.overall_status().await, ServiceStatus::NotServing);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(registry.overall_status().await, ServiceStatus::Serving);
+    assert!(registry.server_override().await.is_none());
+}
+
+#[tokio::test]
+async fn register_with_fixed_id() {
+    let registry = HealthRegistry::new();
+    let fixed = Uuid::new_v4();
+    let options = || RegisterOptions {
+        id: Some(fixed),
+        ..Default::default()
+    };
+
+    let id = registry
+        .register_with_options("svc", Duration::from_secs(60), None, ok_check(), options())
+        .await
+        .unwrap();
+    assert_eq!(id, fixed);
+
+    let err = registry
+        .register_with_options("svc", Duration::from_secs(60), None, ok_check(), options())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AppError::AlreadyExists(_)));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_registrations_with_one_id_register_once() {
+    let registry = Arc::new(HealthRegistry::new());
+    let fixed = Uuid::new_v4();
+    let register = || {
+        let registry = Arc::clone(&registry);
+        tokio::spawn(async move {
+            registry
+                .register_with_options(
+                    "svc",
+                    Duration::from_secs(60),
+                    None,
+                    ok_check(),
+                    RegisterOptions {
+                        id: Some(fixed),
+                        ..Default::default()
+                    },
+                )
+                .await
+        })
+    };
+
+    let handles: Vec<_> = std::iter::repeat_with(register).take(8).collect();
+    let mut results = Vec::new();
+    for handle in handles {
+        results.push(handle.await.unwrap());
+    }
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+    assert!(
+        results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .all(|e| matches!(e, AppError::AlreadyExists(_)))
+    );
+    assert_eq!(registry.list().await.len(), 1);
+}
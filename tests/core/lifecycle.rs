@@ -0,0 +1,42 @@
+use super::*;
+
+#[test]
+fn starts_in_starting_phase() {
+    let lifecycle = Lifecycle::new();
+    assert_eq!(lifecycle.phase(), Phase::Starting);
+    assert!(!lifecycle.is_ready());
+}
+
+#[test]
+fn set_ready_marks_ready() {
+    let lifecycle = Lifecycle::new();
+    lifecycle.set_ready();
+    assert_eq!(lifecycle.phase(), Phase::Ready);
+    assert!(lifecycle.is_ready());
+}
+
+#[test]
+fn set_draining_clears_ready() {
+    let lifecycle = Lifecycle::new();
+    lifecycle.set_ready();
+    lifecycle.set_draining();
+    assert_eq!(lifecycle.phase(), Phase::Draining);
+    assert!(!lifecycle.is_ready());
+}
+
+#[tokio::test]
+async fn subscribers_see_transitions() {
+    let lifecycle = Lifecycle::new();
+    let mut rx = lifecycle.subscribe();
+
+    lifecycle.set_ready();
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow(), Phase::Ready);
+}
+
+#[test]
+fn phase_names() {
+    assert_eq!(Phase::Starting.as_str(), "starting");
+    assert_eq!(Phase::Ready.as_str(), "ready");
+    assert_eq!(Phase::Draining.as_str(), "draining");
+}
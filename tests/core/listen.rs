@@ -0,0 +1,235 @@
+use super::*;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+
+use axum::Router;
+use axum::body::Body;
+use axum::routing::get;
+use tonic::transport::server::Connected;
+use tower::ServiceExt;
+
+fn spec(s: &str) -> ListenerSpec {
+    s.parse().unwrap()
+}
+
+/// A directory under the system temp dir, removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("midnight-listen-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn tcp_listener_serves_everything_by_default() {
+    let spec = spec("127.0.0.1:50051");
+    assert_eq!(
+        spec.address,
+        Address::Tcp("127.0.0.1:50051".parse().unwrap())
+    );
+    assert_eq!(spec.services, Service::ALL);
+    assert_eq!(
+        spec.to_string(),
+        "127.0.0.1:50051 (health, admin, reflection, web)"
+    );
+}
+
+#[test]
+fn parses_unix_listener_options() {
+    let spec = spec("unix:/run/midnight/admin.sock services=admin+health mode=0600");
+    assert_eq!(
+        spec,
+        ListenerSpec {
+            address: Address::Unix("/run/midnight/admin.sock".into()),
+            services: vec![Service::Admin, Service::Health],
+            mode: 0o600,
+            peer_auth: false,
+        }
+    );
+    assert_eq!(
+        spec.to_string(),
+        "unix:/run/midnight/admin.sock (admin, health)"
+    );
+    assert_eq!(
+        self::spec("unix:/tmp/m.sock").mode,
+        ListenerSpec::DEFAULT_MODE
+    );
+}
+
+#[test]
+fn peer_auth_is_opt_in() {
+    assert!(!spec("unix:/tmp/m.sock").peer_auth);
+    let peer = spec("unix:/tmp/m.sock services=admin auth=peer");
+    assert!(peer.peer_auth);
+    assert_eq!(peer.to_string(), "unix:/tmp/m.sock (admin) with auth=peer");
+    assert!(spec("systemd:admin auth=peer").peer_auth);
+}
+
+#[test]
+fn parses_systemd_listeners() {
+    assert_eq!(spec("systemd").address, Address::Systemd(None));
+    let named = spec("systemd:grpc services=health");
+    assert_eq!(named.address, Address::Systemd(Some("grpc".to_owned())));
+    assert!(named.serves(Service::Health));
+    assert!(!named.serves(Service::Admin));
+}
+
+#[test]
+fn rejects_invalid_listeners() {
+    let err = |s: &str| s.parse::<ListenerSpec>().unwrap_err();
+    assert_eq!(err(""), "empty listener");
+    assert_eq!(err("unix:"), "unix: needs a socket path");
+    assert!(err("nowhere").starts_with("invalid address \"nowhere\""));
+    assert_eq!(
+        err("127.0.0.1:1 services=health+metrics"),
+        "unknown service \"metrics\", expected health, admin, reflection, web"
+    );
+    assert_eq!(
+        err("127.0.0.1:1 mode=0600"),
+        "mode only applies to unix: sockets"
+    );
+    assert_eq!(
+        err("unix:/tmp/m.sock mode=rw"),
+        "invalid mode \"rw\", expected octal like 0660"
+    );
+    assert_eq!(
+        err("127.0.0.1:1 auth=peer"),
+        "auth=peer only applies to unix: and systemd sockets"
+    );
+    assert_eq!(
+        err("unix:/tmp/m.sock auth=token"),
+        "unknown listener option \"auth=token\""
+    );
+    assert_eq!(
+        err("127.0.0.1:1 tls=off"),
+        "unknown listener option \"tls=off\""
+    );
+}
+
+#[test]
+fn reads_listen_fds_for_this_process_only() {
+    assert_eq!(
+        parse_listen_fds(Some("42"), Some("2"), Some("grpc:admin"), 42),
+        [(3, "grpc".to_owned()), (4, "admin".to_owned())]
+    );
+    assert_eq!(
+        parse_listen_fds(Some("42"), Some("1"), None, 42),
+        [(3, "unknown".to_owned())]
+    );
+    assert!(parse_listen_fds(Some("41"), Some("2"), None, 42).is_empty());
+    assert!(parse_listen_fds(None, Some("2"), None, 42).is_empty());
+}
+
+#[tokio::test]
+async fn binds_tcp_and_unix_listeners() {
+    let dir = TempDir::new();
+    let path = dir.0.join("admin.sock");
+    let specs = [
+        spec("127.0.0.1:0 services=health"),
+        spec(&format!("unix:{} services=admin mode=0600", path.display())),
+    ];
+    let listeners = bind_all(&specs).unwrap();
+    assert_eq!(listeners.len(), 2);
+    assert!(matches!(listeners[0].0, Listener::Tcp(_)));
+    assert!(matches!(&listeners[1].0, Listener::Unix(_, Some(p)) if *p == path));
+    assert_eq!(listeners[1].1, specs[1]);
+    let mode = std::fs::metadata(&path).unwrap().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[tokio::test]
+async fn unix_socket_replaces_stale_file_but_not_a_live_one() {
+    let dir = TempDir::new();
+    let path = dir.0.join("m.sock");
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let live = bind_all(&[spec(&format!("unix:{}", path.display()))]).unwrap();
+    let err = bind_all(&[spec(&format!("unix:{}", path.display()))]).unwrap_err();
+    assert!(
+        err.to_string().ends_with("is in use by another process"),
+        "{err}"
+    );
+    drop(live);
+
+    let file = dir.0.join("plain");
+    std::fs::write(&file, "").unwrap();
+    let err = bind_all(&[spec(&format!("unix:{}", file.display()))]).unwrap_err();
+    assert!(
+        err.to_string().ends_with("exists and is not a socket"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn adopts_inherited_tcp_and_unix_sockets() {
+    let tcp = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    assert!(matches!(
+        adopt(tcp.into_raw_fd()).unwrap(),
+        Listener::Tcp(_)
+    ));
+
+    let dir = TempDir::new();
+    let unix = UnixListener::bind(dir.0.join("m.sock")).unwrap();
+    assert!(matches!(
+        adopt(unix.into_raw_fd()).unwrap(),
+        Listener::Unix(_, None)
+    ));
+}
+
+#[tokio::test]
+async fn refuses_inherited_descriptors_that_are_not_listening() {
+    let (stream, _peer) = UnixStream::pair().unwrap();
+    let fd = stream.into_raw_fd();
+    assert_eq!(
+        adopt(fd).unwrap_err().to_string(),
+        format!("inherited descriptor {fd} is not a listening socket")
+    );
+    // SAFETY: `adopt` refused it, so `fd` is still only ours.
+    drop(unsafe { UnixStream::from_raw_fd(fd) });
+
+    let file = std::fs::File::open("/dev/null").unwrap();
+    assert!(adopt(file.as_raw_fd()).is_err());
+}
+
+#[tokio::test]
+async fn systemd_listener_needs_a_matching_socket() {
+    let err = bind_all(&[spec("systemd:grpc")]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "no unclaimed sockets from systemd match systemd:grpc"
+    );
+}
+
+#[tokio::test]
+async fn unix_peer_uid_becomes_principal() {
+    let (client, _server) = tokio::net::UnixStream::pair().unwrap();
+    let dir = TempDir::new();
+    let uid = std::fs::metadata(&dir.0).unwrap().uid();
+
+    let app = Router::new()
+        .route(
+            "/",
+            get(|req: Request| async move {
+                req.extensions()
+                    .get::<Principal>()
+                    .map(|p| p.0.clone())
+                    .unwrap_or_default()
+            }),
+        )
+        .layer(axum::middleware::from_fn(peer_principal));
+    let mut req = http::Request::get("/").body(Body::empty()).unwrap();
+    req.extensions_mut().insert(client.connect_info());
+    let resp = app.oneshot(req).await.unwrap();
+    let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+    assert_eq!(body, format!("uid:{uid}"));
+}
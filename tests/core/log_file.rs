@@ -0,0 +1,260 @@
+use super::*;
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::time::Duration;
+
+/// A fresh directory under the system temp dir, removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("midnight-logs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn settings(&self, rotation: Rotation, max_files: usize, compress: bool) -> FileSettings {
+        FileSettings {
+            path: self.0.join("server.log"),
+            rotation,
+            max_files,
+            compress,
+        }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn names(files: &[PathBuf]) -> Vec<String> {
+    files
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn rotation_from_str() {
+    assert_eq!(Rotation::from_str("daily", 10), Rotation::Daily);
+    assert_eq!(Rotation::from_str("SIZE", 10), Rotation::Size(10));
+    assert_eq!(Rotation::from_str("never", 10), Rotation::Never);
+    assert_eq!(Rotation::from_str("hourly", 10), Rotation::Daily);
+}
+
+#[test]
+fn utc_stamp_formats_date_and_time() {
+    assert_eq!(utc_stamp(at(0)), "19700101-000000");
+    // 2024-02-29T23:59:59Z
+    assert_eq!(utc_stamp(at(1_709_251_199)), "20240229-235959");
+    // 2026-10-17T18:21:23Z
+    assert_eq!(utc_stamp(at(1_792_261_283)), "20261017-182123");
+}
+
+#[test]
+fn creates_missing_directories() {
+    let dir = TempDir::new();
+    let mut settings = dir.settings(Rotation::Never, 0, false);
+    settings.path = dir.0.join("nested/deeper/server.log");
+    let mut file = RollingFile::open(settings).unwrap();
+    file.write_all(b"hello\n").unwrap();
+    assert_eq!(fs::read_to_string(file.path()).unwrap(), "hello\n");
+}
+
+#[test]
+fn rotates_by_size() {
+    let dir = TempDir::new();
+    let mut file = RollingFile::open(dir.settings(Rotation::Size(10), 0, false)).unwrap();
+
+    file.write_at(b"12345678\n", at(100)).unwrap();
+    // Would go past 10 bytes, so the first line is rotated out first.
+    file.write_at(b"abcdefgh\n", at(101)).unwrap();
+
+    let rotated = rotated_files(file.path());
+    assert_eq!(names(&rotated), ["server.log.19700101-000141"]);
+    assert_eq!(fs::read_to_string(&rotated[0]).unwrap(), "12345678\n");
+    assert_eq!(fs::read_to_string(file.path()).unwrap(), "abcdefgh\n");
+}
+
+#[test]
+fn oversized_write_goes_to_empty_file_without_rotating() {
+    let dir = TempDir::new();
+    let mut file = RollingFile::open(dir.settings(Rotation::Size(4), 0, false)).unwrap();
+    file.write_at(b"longer than four\n", at(100)).unwrap();
+    assert!(rotated_files(file.path()).is_empty());
+}
+
+#[test]
+fn rotates_daily() {
+    let dir = TempDir::new();
+    let mut file = RollingFile::open(dir.settings(Rotation::Daily, 0, false)).unwrap();
+    let day = 20_000 * SECS_PER_DAY;
+    // Opening set the day from the fresh file; pretend it was written on `day`.
+    file.day = day_of(at(day));
+
+    file.write_at(b"first\n", at(day + 10)).unwrap();
+    file.write_at(b"same day\n", at(day + SECS_PER_DAY - 1))
+        .unwrap();
+    assert!(rotated_files(file.path()).is_empty());
+
+    file.write_at(b"next day\n", at(day + SECS_PER_DAY))
+        .unwrap();
+    let rotated = rotated_files(file.path());
+    assert_eq!(rotated.len(), 1);
+    assert_eq!(
+        fs::read_to_string(&rotated[0]).unwrap(),
+        "first\nsame day\n"
+    );
+    assert_eq!(fs::read_to_string(file.path()).unwrap(), "next day\n");
+}
+
+#[test]
+fn rotations_in_the_same_second_get_unique_names() {
+    let dir = TempDir::new();
+    let mut file = RollingFile::open(dir.settings(Rotation::Size(1), 0, false)).unwrap();
+    for _ in 0..4 {
+        file.write_at(b"x\n", at(100)).unwrap();
+    }
+    assert_eq!(
+        names(&rotated_files(file.path())),
+        [
+            "server.log.19700101-000140",
+            "server.log.19700101-000140-1",
+            "server.log.19700101-000140-2",
+        ]
+    );
+}
+
+#[test]
+fn prunes_oldest_rotated_files() {
+    let dir = TempDir::new();
+    let mut file = RollingFile::open(dir.settings(Rotation::Size(1), 2, false)).unwrap();
+    for secs in 100..105 {
+        file.write_at(format!("{secs}\n").as_bytes(), at(secs))
+            .unwrap();
+    }
+    file.wait_for_worker();
+
+    let rotated = rotated_files(file.path());
+    assert_eq!(
+        names(&rotated),
+        ["server.log.19700101-000143", "server.log.19700101-000144"]
+    );
+    assert_eq!(fs::read_to_string(&rotated[1]).unwrap(), "103\n");
+    assert_eq!(fs::read_to_string(file.path()).unwrap(), "104\n");
+}
+
+#[test]
+fn compresses_rotated_files() {
+    let dir = TempDir::new();
+    let mut file = RollingFile::open(dir.settings(Rotation::Size(1), 2, true)).unwrap();
+    for secs in 100..104 {
+        file.write_at(format!("{secs}\n").as_bytes(), at(secs))
+            .unwrap();
+    }
+    file.wait_for_worker();
+
+    let rotated = rotated_files(file.path());
+    assert_eq!(
+        names(&rotated),
+        [
+            "server.log.19700101-000142.gz",
+            "server.log.19700101-000143.gz"
+        ]
+    );
+    let mut text = String::new();
+    GzDecoder::new(File::open(&rotated[1]).unwrap())
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, "102\n");
+}
+
+#[test]
+fn only_rotated_files_are_pruned() {
+    let dir = TempDir::new();
+    let mut file = RollingFile::open(dir.settings(Rotation::Size(1), 1, false)).unwrap();
+    let others = [
+        "server.log.bak",
+        "server.log.old.gz",
+        "server.log.20240101-000000-x",
+        "server.log.2024-01-01",
+        "server.logs.20240101-000000",
+    ];
+    for other in others {
+        fs::write(dir.0.join(other), "keep\n").unwrap();
+    }
+    for secs in 100..103 {
+        file.write_at(b"x\n", at(secs)).unwrap();
+    }
+    file.wait_for_worker();
+
+    assert_eq!(
+        names(&rotated_files(file.path())),
+        ["server.log.19700101-000142"]
+    );
+    for other in others {
+        assert!(dir.0.join(other).exists(), "{other}");
+    }
+}
+
+#[test]
+fn recognises_rotated_file_names() {
+    for rotated in [
+        "server.log.20240101-000000",
+        "server.log.20240101-000000-12",
+        "server.log.20240101-000000.gz",
+        "server.log.20240101-000000-1.gz",
+    ] {
+        assert!(is_rotated(rotated, "server.log"), "{rotated}");
+    }
+    for other in [
+        "server.log",
+        "server.log.",
+        "server.log.gz",
+        "server.log.2024010-000000",
+        "server.log.20240101-000000-",
+        "server.log.20240101-000000-1-2",
+        "server.log.20240101-000000.gz.gz",
+        "server.log2.20240101-000000",
+    ] {
+        assert!(!is_rotated(other, "server.log"), "{other}");
+    }
+}
+
+#[test]
+fn reopening_appends_to_existing_file() {
+    let dir = TempDir::new();
+    let settings = dir.settings(Rotation::Size(100), 0, false);
+    RollingFile::open(settings.clone())
+        .unwrap()
+        .write_all(b"one\n")
+        .unwrap();
+    let mut file = RollingFile::open(settings).unwrap();
+    file.write_all(b"two\n").unwrap();
+    assert_eq!(fs::read_to_string(file.path()).unwrap(), "one\ntwo\n");
+}
+
+#[test]
+fn from_config_requires_log_file() {
+    let mut config = Config {
+        log_file_rotation: "size".to_owned(),
+        log_file_max_size_mb: 5,
+        log_file_max_files: 3,
+        log_file_compress: true,
+        ..Config::for_tests()
+    };
+    assert!(FileSettings::from_config(&config).is_none());
+
+    config.log_file = Some("/var/log/midnight/server.log".to_owned());
+    let settings = FileSettings::from_config(&config).unwrap();
+    assert_eq!(settings.path, PathBuf::from("/var/log/midnight/server.log"));
+    assert_eq!(settings.rotation, Rotation::Size(5 * 1024 * 1024));
+    assert_eq!(settings.max_files, 3);
+    assert!(settings.compress);
+}
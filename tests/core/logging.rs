@@ -57,3 +57,113 @@ fn parse_empty_string_uses_build_default() {
         assert!(matches!(style, LogStyle::Plain));
     }
 }
+
+/// Installs the filter as this thread's subscriber until the guard drops.
+fn installed(directive: &str) -> (tracing::subscriber::DefaultGuard, LogFilter) {
+    let (layer, filter) = LogFilter::new(directive);
+    let guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+    (guard, filter)
+}
+
+fn debug_enabled() -> bool {
+    tracing::enabled!(tracing::Level::DEBUG)
+}
+
+#[test]
+fn log_filter_starts_with_directive() {
+    let (_layer, filter) = LogFilter::new("info");
+    assert_eq!(
+        filter.state(),
+        FilterState {
+            directive: "info".into(),
+            expires_at: None,
+            revert_to: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn set_applies_new_filter() {
+    let (_guard, filter) = installed("info");
+    assert!(!debug_enabled());
+
+    let state = filter.set("debug", None).unwrap();
+    assert_eq!(state.directive, "debug");
+    assert!(state.expires_at.is_none());
+    assert!(debug_enabled());
+}
+
+#[tokio::test]
+async fn set_rejects_invalid_directive() {
+    let (_layer, filter) = LogFilter::new("info");
+    for directive in ["", "  ", "foo=bar=baz"] {
+        assert!(matches!(
+            filter.set(directive, None),
+            Err(AppError::InvalidArgument(_))
+        ));
+    }
+    assert_eq!(filter.state().directive, "info");
+}
+
+#[tokio::test]
+async fn temporary_filter_reverts_after_ttl() {
+    let (_guard, filter) = installed("info");
+    let state = filter
+        .set("debug", Some(Duration::from_millis(50)))
+        .unwrap();
+    assert_eq!(state.directive, "debug");
+    assert!(state.expires_at.is_some());
+    assert_eq!(state.revert_to.as_deref(), Some("info"));
+    assert!(debug_enabled());
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(filter.state().directive, "info");
+    assert!(filter.state().expires_at.is_none());
+    assert!(!debug_enabled());
+}
+
+#[tokio::test]
+async fn repeated_temporary_filters_revert_to_original() {
+    let (_layer, filter) = LogFilter::new("info");
+    filter
+        .set("debug", Some(Duration::from_millis(50)))
+        .unwrap();
+    let state = filter
+        .set("trace", Some(Duration::from_millis(200)))
+        .unwrap();
+    assert_eq!(state.revert_to.as_deref(), Some("info"));
+
+    // The first ttl passing doesn't cut the second one short.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(filter.state().directive, "trace");
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(filter.state().directive, "info");
+}
+
+#[tokio::test]
+async fn permanent_set_cancels_pending_revert() {
+    let (_layer, filter) = LogFilter::new("info");
+    filter
+        .set("debug", Some(Duration::from_millis(50)))
+        .unwrap();
+    filter.set("warn", None).unwrap();
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(filter.state().directive, "warn");
+    assert!(filter.state().revert_to.is_none());
+}
+
+#[tokio::test]
+async fn set_base_waits_for_temporary_filter() {
+    let (_layer, filter) = LogFilter::new("info");
+    filter
+        .set("debug", Some(Duration::from_millis(50)))
+        .unwrap();
+    filter.set_base("warn").unwrap();
+    assert_eq!(filter.state().directive, "debug");
+    assert_eq!(filter.state().revert_to.as_deref(), Some("warn"));
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(filter.state().directive, "warn");
+}
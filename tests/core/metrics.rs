@@ -0,0 +1,130 @@
+use super::*;
+use crate::core::health::HealthCheckFn;
+
+fn test_pool() -> PgPool {
+    PgPool::connect_lazy("postgres://localhost/test").unwrap()
+}
+
+fn ok_check() -> HealthCheckFn {
+    Box::new(|| Box::pin(async { Ok(()) }))
+}
+
+fn failing_check() -> HealthCheckFn {
+    Box::new(|| Box::pin(async { Err("down".to_owned()) }))
+}
+
+#[tokio::test]
+async fn observed_requests_are_labelled_by_method_and_code() {
+    let metrics = Metrics::new();
+    metrics.observe_request(
+        Some("/midnight.HealthService/ListHealthServices"),
+        tonic::Code::Ok,
+        Duration::from_millis(5),
+    );
+    metrics.observe_request(
+        Some("/midnight.HealthService/GetHealthService"),
+        tonic::Code::NotFound,
+        Duration::from_millis(1),
+    );
+
+    let text = metrics.render(&test_pool(), &HealthRegistry::new()).await;
+    assert!(text.contains(
+        r#"grpc_server_handled_total{grpc_code="Ok",grpc_method="ListHealthServices",grpc_service="midnight.HealthService"} 1"#
+    ));
+    assert!(text.contains(
+        r#"grpc_server_handled_total{grpc_code="NotFound",grpc_method="GetHealthService",grpc_service="midnight.HealthService"} 1"#
+    ));
+    assert!(text.contains(
+        r#"grpc_server_handling_seconds_count{grpc_code="Ok",grpc_method="ListHealthServices",grpc_service="midnight.HealthService"} 1"#
+    ));
+}
+
+#[tokio::test]
+async fn unknown_methods_share_one_series() {
+    let metrics = Metrics::new();
+    metrics.observe_request(None, tonic::Code::Unimplemented, Duration::ZERO);
+    metrics.observe_request(None, tonic::Code::Unimplemented, Duration::ZERO);
+    metrics.observe_request(None, tonic::Code::Unauthenticated, Duration::ZERO);
+
+    let text = metrics.render(&test_pool(), &HealthRegistry::new()).await;
+    assert!(text.contains(
+        r#"grpc_server_handled_total{grpc_code="Unimplemented",grpc_method="unknown",grpc_service="unknown"} 2"#
+    ));
+    assert!(text.contains(
+        r#"grpc_server_handled_total{grpc_code="Unauthenticated",grpc_method="unknown",grpc_service="unknown"} 1"#
+    ));
+}
+
+#[tokio::test]
+async fn in_flight_tracks_open_requests() {
+    let metrics = Metrics::new();
+    let first = metrics.start_request();
+    let second = metrics.start_request();
+    assert_eq!(metrics.grpc_in_flight.get(), 2);
+
+    drop(first);
+    assert_eq!(metrics.grpc_in_flight.get(), 1);
+    drop(second);
+    assert_eq!(metrics.grpc_in_flight.get(), 0);
+}
+
+#[tokio::test]
+async fn render_includes_pool_gauges() {
+    let metrics = Metrics::new();
+    let text = metrics.render(&test_pool(), &HealthRegistry::new()).await;
+    assert!(text.contains("midnight_db_pool_connections 0"));
+    assert!(text.contains("midnight_db_pool_idle_connections 0"));
+    assert!(text.contains("midnight_db_pool_acquired_connections 0"));
+    assert!(text.contains("midnight_db_pool_max_connections 10"));
+}
+
+#[tokio::test]
+async fn render_includes_health_gauges() {
+    let metrics = Metrics::new();
+    let registry = HealthRegistry::new();
+    let db = registry
+        .register("database", Duration::from_secs(60), None, failing_check())
+        .await;
+
+    let text = metrics.render(&test_pool(), &registry).await;
+    let series = |name: &str, status: &str| {
+        format!(r#"{name}{{id="{db}",service="database",status="{status}"}}"#)
+    };
+    assert!(text.contains(&format!(
+        "{} 1",
+        series("midnight_health_status", "not_serving")
+    )));
+    assert!(text.contains(&format!(
+        "{} 0",
+        series("midnight_health_status", "serving")
+    )));
+    assert!(text.contains(&format!(
+        r#"midnight_health_flapping{{id="{db}",service="database"}} 0"#
+    )));
+    assert!(text.contains(&format!(
+        r#"midnight_health_probe_latency_seconds{{id="{db}",service="database"}}"#
+    )));
+}
+
+#[tokio::test]
+async fn deregistered_services_drop_out_of_health_gauges() {
+    let metrics = Metrics::new();
+    let registry = HealthRegistry::new();
+    let id = registry
+        .register("cache", Duration::from_secs(60), None, ok_check())
+        .await;
+    assert!(
+        metrics
+            .render(&test_pool(), &registry)
+            .await
+            .contains("cache")
+    );
+
+    registry.deregister(&id).await;
+    assert!(
+        !metrics
+            .render(&test_pool(), &registry)
+            .await
+            .contains("cache")
+    );
+}
@@ -0,0 +1,325 @@
+use super::*;
+use crate::core::health::HealthCheckFn;
+use crate::core::redact::Secret;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant as StdInstant;
+
+fn settings(min_interval: Duration) -> NotifierSettings {
+    NotifierSettings {
+        webhooks: vec![],
+        services: vec![],
+        min_interval,
+        max_retries: 0,
+        retry_backoff: Duration::from_millis(10),
+    }
+}
+
+fn health(id: Uuid, name: &str, status: ServiceStatus) -> ServiceHealth {
+    ServiceHealth {
+        id,
+        name: name.to_owned(),
+        status,
+        interval: Duration::from_secs(30),
+        registered_at: StdInstant::now(),
+        version: None,
+        message: (status != ServiceStatus::Serving).then(|| "down".to_owned()),
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: SystemTime::now(),
+        manual_override: None,
+    }
+}
+
+fn changed(id: Uuid, name: &str, status: ServiceStatus) -> HealthEvent {
+    HealthEvent::Changed(health(id, name, status))
+}
+
+#[derive(Clone, Default)]
+struct StandIn {
+    received: Arc<Mutex<Vec<serde_json::Value>>>,
+    /// Requests to reject with 500 before accepting.
+    failures: Arc<AtomicUsize>,
+}
+
+async fn receive(
+    State(stand_in): State<StandIn>,
+    Json(body): Json<serde_json::Value>,
+) -> StatusCode {
+    let remaining = stand_in.failures.load(Ordering::SeqCst);
+    if remaining > 0 {
+        stand_in.failures.store(remaining - 1, Ordering::SeqCst);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    stand_in.received.lock().unwrap().push(body);
+    StatusCode::NO_CONTENT
+}
+
+/// Local webhook receiver; returns its URL.
+async fn stand_in(state: StandIn) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = axum::Router::new()
+        .route("/hook", post(receive))
+        .with_state(state);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{addr}/hook")
+}
+
+fn notification() -> Notification {
+    Notification {
+        id: Uuid::new_v4(),
+        service: "database".to_owned(),
+        previous_status: "serving",
+        status: "not_serving",
+        message: Some("down".to_owned()),
+        timestamp_ms: 0,
+    }
+}
+
+#[test]
+fn status_change_is_notified() {
+    let mut notifier = Notifier::new(settings(Duration::ZERO));
+    let id = Uuid::new_v4();
+    let now = Instant::now();
+    notifier.handle(
+        &HealthEvent::Registered(health(id, "database", ServiceStatus::Serving)),
+        now,
+    );
+
+    let sent = notifier
+        .handle(&changed(id, "database", ServiceStatus::NotServing), now)
+        .unwrap();
+    assert_eq!(sent.service, "database");
+    assert_eq!(sent.previous_status, "serving");
+    assert_eq!(sent.status, "not_serving");
+    assert_eq!(sent.message.as_deref(), Some("down"));
+}
+
+#[test]
+fn message_only_change_is_not_notified() {
+    let mut notifier = Notifier::new(settings(Duration::ZERO));
+    let id = Uuid::new_v4();
+    let now = Instant::now();
+    notifier.handle(
+        &HealthEvent::Registered(health(id, "database", ServiceStatus::Serving)),
+        now,
+    );
+
+    let mut same = health(id, "database", ServiceStatus::Serving);
+    same.flapping = true;
+    assert!(notifier.handle(&HealthEvent::Changed(same), now).is_none());
+}
+
+#[test]
+fn filtered_services_are_ignored() {
+    let mut s = settings(Duration::ZERO);
+    s.services = vec!["database".to_owned()];
+    let mut notifier = Notifier::new(s);
+    let now = Instant::now();
+    let cache = Uuid::new_v4();
+    notifier.handle(
+        &HealthEvent::Registered(health(cache, "cache", ServiceStatus::Serving)),
+        now,
+    );
+
+    assert!(
+        notifier
+            .handle(&changed(cache, "cache", ServiceStatus::NotServing), now)
+            .is_none()
+    );
+    assert!(notifier.next_due(now).is_none());
+    assert!(notifier.flush(now).is_empty());
+}
+
+#[test]
+fn changes_within_min_interval_are_coalesced() {
+    let mut notifier = Notifier::new(settings(Duration::from_secs(60)));
+    let id = Uuid::new_v4();
+    let start = Instant::now();
+    notifier.handle(
+        &HealthEvent::Registered(health(id, "database", ServiceStatus::Serving)),
+        start,
+    );
+    assert!(
+        notifier
+            .handle(&changed(id, "database", ServiceStatus::NotServing), start)
+            .is_some()
+    );
+
+    let later = start + Duration::from_secs(10);
+    assert!(
+        notifier
+            .handle(&changed(id, "database", ServiceStatus::Degraded), later)
+            .is_none()
+    );
+    assert_eq!(
+        notifier.next_due(later),
+        Some(start + Duration::from_secs(60))
+    );
+    assert!(notifier.flush(later).is_empty());
+
+    let due = start + Duration::from_secs(60);
+    let sent = notifier.flush(due);
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].previous_status, "not_serving");
+    assert_eq!(sent[0].status, "degraded");
+    assert!(notifier.next_due(due).is_none());
+}
+
+#[test]
+fn change_reverted_within_min_interval_is_dropped() {
+    let mut notifier = Notifier::new(settings(Duration::from_secs(60)));
+    let id = Uuid::new_v4();
+    let start = Instant::now();
+    notifier.handle(
+        &HealthEvent::Registered(health(id, "database", ServiceStatus::Serving)),
+        start,
+    );
+    notifier.handle(&changed(id, "database", ServiceStatus::NotServing), start);
+
+    let later = start + Duration::from_secs(5);
+    notifier.handle(&changed(id, "database", ServiceStatus::Serving), later);
+    notifier.handle(&changed(id, "database", ServiceStatus::NotServing), later);
+    assert!(notifier.flush(start + Duration::from_secs(60)).is_empty());
+}
+
+#[test]
+fn deregistered_services_are_forgotten() {
+    let mut notifier = Notifier::new(settings(Duration::from_secs(60)));
+    let id = Uuid::new_v4();
+    let now = Instant::now();
+    notifier.handle(
+        &HealthEvent::Registered(health(id, "database", ServiceStatus::Serving)),
+        now,
+    );
+    notifier.handle(&changed(id, "database", ServiceStatus::NotServing), now);
+    notifier.handle(&changed(id, "database", ServiceStatus::Serving), now);
+    notifier.handle(
+        &HealthEvent::Deregistered(health(id, "database", ServiceStatus::Serving)),
+        now,
+    );
+    assert!(notifier.next_due(now).is_none());
+}
+
+#[test]
+fn settings_from_config() {
+    let mut config = Config::for_tests();
+    config.notify_webhooks = vec![Secret::new("http://hooks/a".to_owned())];
+    config.notify_services = vec!["database".to_owned()];
+
+    let s = NotifierSettings::from_config(&config);
+    assert_eq!(s.webhooks, vec!["http://hooks/a"]);
+    assert_eq!(s.min_interval, Duration::from_secs(60));
+    assert_eq!(s.max_retries, 3);
+    assert_eq!(s.retry_backoff, Duration::from_millis(500));
+    assert!(s.wants("database"));
+    assert!(!s.wants("server"));
+}
+
+#[tokio::test]
+async fn deliver_posts_json() {
+    let state = StandIn::default();
+    let url = stand_in(state.clone()).await;
+
+    let n = notification();
+    assert!(deliver(reqwest::Client::new(), url, n.clone(), 0, Duration::ZERO).await);
+
+    let received = state.received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0]["service"], "database");
+    assert_eq!(received[0]["previous_status"], "serving");
+    assert_eq!(received[0]["status"], "not_serving");
+    assert_eq!(received[0]["id"], n.id.to_string());
+}
+
+#[tokio::test]
+async fn deliver_retries_until_accepted() {
+    let state = StandIn::default();
+    state.failures.store(2, Ordering::SeqCst);
+    let url = stand_in(state.clone()).await;
+
+    let delivered = deliver(
+        reqwest::Client::new(),
+        url,
+        notification(),
+        2,
+        Duration::from_millis(10),
+    )
+    .await;
+    assert!(delivered);
+    assert_eq!(state.received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn deliver_gives_up_after_max_retries() {
+    let state = StandIn::default();
+    state.failures.store(5, Ordering::SeqCst);
+    let url = stand_in(state.clone()).await;
+
+    let delivered = deliver(
+        reqwest::Client::new(),
+        url,
+        notification(),
+        1,
+        Duration::from_millis(10),
+    )
+    .await;
+    assert!(!delivered);
+    assert_eq!(state.failures.load(Ordering::SeqCst), 3);
+    assert!(state.received.lock().unwrap().is_empty());
+}
+
+#[test]
+fn retry_delays_double_up_to_a_cap() {
+    let backoff = Duration::from_millis(500);
+    assert_eq!(retry_delay(backoff, 1), backoff);
+    assert_eq!(retry_delay(backoff, 3), Duration::from_secs(2));
+    assert_eq!(retry_delay(backoff, 20), MAX_RETRY_DELAY);
+    assert_eq!(retry_delay(Duration::MAX, u32::MAX), MAX_RETRY_DELAY);
+}
+
+#[tokio::test]
+async fn spawned_notifier_posts_transitions() {
+    let hook = StandIn::default();
+    let url = stand_in(hook.clone()).await;
+
+    let config = Config::for_tests();
+    let state = AppState::new(
+        config,
+        sqlx::PgPool::connect_lazy("postgres://localhost/test").unwrap(),
+    );
+    let mut s = settings(Duration::ZERO);
+    s.webhooks = vec![url];
+    spawn(Arc::clone(&state), s);
+
+    let fail = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&fail);
+    let check: HealthCheckFn = Box::new(move || {
+        let flag = Arc::clone(&flag);
+        Box::pin(async move {
+            if flag.load(Ordering::Relaxed) {
+                Err("connection refused".to_owned())
+            } else {
+                Ok(())
+            }
+        })
+    });
+    state
+        .health()
+        .register("database", Duration::from_millis(20), None, check)
+        .await;
+
+    fail.store(true, Ordering::Relaxed);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let received = hook.received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0]["service"], "database");
+    assert_eq!(received[0]["status"], "not_serving");
+    assert_eq!(received[0]["message"], "connection refused");
+}
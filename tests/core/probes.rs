@@ -0,0 +1,192 @@
+use super::*;
+use crate::core::config::Config;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+fn test_pool() -> PgPool {
+    PgPool::connect_lazy("postgres://localhost/test").unwrap()
+}
+
+fn spec(kind: ProbeKind) -> ProbeSpec {
+    ProbeSpec {
+        name: "sidecar".to_owned(),
+        kind,
+        interval: Duration::from_secs(10),
+        timeout: Duration::from_secs(5),
+        failure_threshold: 1,
+        success_threshold: 1,
+    }
+}
+
+fn tcp(address: &str) -> ProbeKind {
+    ProbeKind::Tcp {
+        address: address.to_owned(),
+    }
+}
+
+fn http(url: &str, expected_status: u16) -> ProbeKind {
+    ProbeKind::Http {
+        url: url.to_owned(),
+        expected_status,
+    }
+}
+
+/// Answers every connection with a bare HTTP response of `status`.
+async fn http_server(status: u16) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let response =
+                format!("HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+    format!("http://{addr}/health")
+}
+
+#[test]
+fn valid_specs_pass() {
+    for kind in [
+        tcp("localhost:5432"),
+        http("https://example.com/health", 204),
+        ProbeKind::Dns {
+            host: "example.com".to_owned(),
+        },
+        ProbeKind::Sql {
+            query: "SELECT 1".to_owned(),
+            database_url: Some(Secret::new("postgres://localhost/other".to_owned())),
+        },
+    ] {
+        spec(kind).validate().unwrap();
+    }
+}
+
+#[test]
+fn tcp_address_requires_port() {
+    assert!(matches!(
+        spec(tcp("localhost")).validate(),
+        Err(AppError::InvalidArgument(_))
+    ));
+    assert!(spec(tcp("localhost:http")).validate().is_err());
+}
+
+#[test]
+fn http_requires_http_url_and_valid_status() {
+    assert!(spec(http("ftp://example.com", 200)).validate().is_err());
+    assert!(spec(http("not a url", 200)).validate().is_err());
+    assert!(spec(http("http://example.com", 99)).validate().is_err());
+    assert!(spec(http("http://example.com", 600)).validate().is_err());
+}
+
+#[test]
+fn empty_dns_host_and_sql_query_are_rejected() {
+    let dns = ProbeKind::Dns {
+        host: " ".to_owned(),
+    };
+    let sql = ProbeKind::Sql {
+        query: String::new(),
+        database_url: None,
+    };
+    assert!(spec(dns).validate().is_err());
+    assert!(spec(sql).validate().is_err());
+}
+
+#[test]
+fn spec_requires_name_interval_timeout_and_thresholds() {
+    let mut s = spec(tcp("localhost:80"));
+    s.name = String::new();
+    assert!(s.validate().is_err());
+
+    let mut s = spec(tcp("localhost:80"));
+    s.interval = Duration::ZERO;
+    assert!(s.validate().is_err());
+
+    let mut s = spec(tcp("localhost:80"));
+    s.timeout = Duration::ZERO;
+    assert!(s.validate().is_err());
+
+    let mut s = spec(tcp("localhost:80"));
+    s.interval = Duration::from_micros(999);
+    assert!(matches!(s.validate(), Err(AppError::InvalidArgument(_))));
+
+    let mut s = spec(tcp("localhost:80"));
+    s.timeout = Duration::from_nanos(1);
+    assert!(matches!(s.validate(), Err(AppError::InvalidArgument(_))));
+
+    let mut s = spec(tcp("localhost:80"));
+    s.interval = Duration::from_millis(1);
+    s.timeout = Duration::from_millis(1);
+    s.validate().unwrap();
+
+    let mut s = spec(tcp("localhost:80"));
+    s.failure_threshold = 0;
+    assert!(s.validate().is_err());
+}
+
+#[test]
+fn kind_is_stored_as_tagged_json() {
+    let kind = http("http://localhost/health", 200);
+    let json = serde_json::to_value(&kind).unwrap();
+    assert_eq!(json["type"], "http");
+    assert_eq!(json["expected_status"], 200);
+    assert_eq!(serde_json::from_value::<ProbeKind>(json).unwrap(), kind);
+}
+
+#[test]
+fn sql_database_url_is_stored_but_not_shown() {
+    let kind = ProbeKind::Sql {
+        query: "SELECT 1".to_owned(),
+        database_url: Some(Secret::new("postgres://u:hunter2@db/app".to_owned())),
+    };
+    let json = serde_json::to_value(&kind).unwrap();
+    assert_eq!(json["database_url"], "postgres://u:hunter2@db/app");
+    assert_eq!(serde_json::from_value::<ProbeKind>(json).unwrap(), kind);
+    assert!(!format!("{kind:?}").contains("hunter2"));
+}
+
+#[tokio::test]
+async fn tcp_check_connects() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let check = tcp(&addr).check(test_pool());
+    assert!(check().await.is_ok());
+
+    drop(listener);
+    let err = check().await.unwrap_err();
+    assert!(err.contains(&addr), "{err}");
+}
+
+#[tokio::test]
+async fn http_check_compares_status() {
+    let url = http_server(204).await;
+    assert!(http(&url, 204).check(test_pool())().await.is_ok());
+
+    let err = http(&url, 200).check(test_pool())().await.unwrap_err();
+    assert!(err.contains("204"), "{err}");
+}
+
+#[tokio::test]
+async fn dns_check_resolves_localhost() {
+    let check = ProbeKind::Dns {
+        host: "localhost".to_owned(),
+    }
+    .check(test_pool());
+    assert!(check().await.is_ok());
+}
+
+#[tokio::test]
+async fn dns_check_fails_for_unknown_host() {
+    let check = ProbeKind::Dns {
+        host: "does-not-exist.invalid".to_owned(),
+    }
+    .check(test_pool());
+    assert!(check().await.is_err());
+}
+
+#[tokio::test]
+async fn create_rejects_invalid_spec_before_storing() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    let err = create(&state, spec(tcp("nope"))).await.unwrap_err();
+    assert!(matches!(err, AppError::InvalidArgument(_)));
+}
@@ -0,0 +1,249 @@
+use super::*;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::layer::SubscriberExt;
+
+fn redactor() -> Redactor {
+    Redactor::new(&["password", "api_key"])
+}
+
+/// Collects everything the fmt layer writes.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Buffer {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[test]
+fn secret_hides_value() {
+    let secret = Secret::new("hunter2".to_owned());
+    assert_eq!(format!("{secret:?}"), REDACTED);
+    assert_eq!(secret.to_string(), REDACTED);
+    assert_eq!(secret.expose(), "hunter2");
+}
+
+#[test]
+fn masks_url_password() {
+    assert_eq!(
+        redactor().redact("connecting to postgres://midnight:hunter2@db:5432/app"),
+        "connecting to postgres://midnight:[REDACTED]@db:5432/app"
+    );
+}
+
+#[test]
+fn masks_url_userinfo_without_password() {
+    assert_eq!(
+        redactor().redact("url=https://ghp_abc123@github.com/repo"),
+        "url=https://[REDACTED]@github.com/repo"
+    );
+}
+
+#[test]
+fn leaves_urls_without_credentials() {
+    let text = "calling https://example.com/a@b and mailto:x@y.z";
+    assert!(matches!(redactor().redact(text), Cow::Borrowed(_)));
+}
+
+#[test]
+fn masks_text_fields() {
+    assert_eq!(
+        redactor().redact(r#"login user=bob password="hunter2" API_KEY=abc,next=1"#),
+        r#"login user=bob password="[REDACTED]" API_KEY=[REDACTED],next=1"#
+    );
+    assert_eq!(
+        redactor().redact(r#"api_key = "abc""#),
+        r#"api_key = "[REDACTED]""#
+    );
+}
+
+#[test]
+fn masks_keys_ending_in_a_field_name() {
+    let redactor = Redactor::new(&["password", "token"]);
+    assert_eq!(
+        redactor.redact("db_password=hunter2 auth_token=abc x.token=def"),
+        "db_password=[REDACTED] auth_token=[REDACTED] x.token=[REDACTED]"
+    );
+    assert!(redactor.is_sensitive("db_password"));
+    assert!(redactor.is_sensitive("Auth-Token"));
+    assert!(redactor.is_sensitive("token"));
+}
+
+#[test]
+fn matches_whole_field_names_only() {
+    let text = "passwords=3 old_password_hint=x tokenizer=y";
+    assert_eq!(redactor().redact(text), text);
+    let redactor = Redactor::new(&["password", "token"]);
+    assert!(!redactor.is_sensitive("passwords"));
+    assert!(!redactor.is_sensitive("password_hint"));
+    assert!(!redactor.is_sensitive("tokens"));
+}
+
+#[test]
+fn leaves_prose_about_fields_alone() {
+    let redactor = Redactor::new(&DEFAULT_FIELDS.split(',').collect::<Vec<_>>());
+    for text in [
+        "invalid token: ExpiredSignature",
+        "missing bearer token",
+        "authorization header is not a bearer token",
+    ] {
+        assert_eq!(redactor.redact(text), text);
+    }
+}
+
+#[test]
+fn no_fields_only_masks_urls() {
+    let redactor = Redactor::new::<&str>(&[]);
+    assert_eq!(redactor.redact("password=hunter2"), "password=hunter2");
+    assert_eq!(
+        redactor.redact("redis://:hunter2@cache"),
+        "redis://:[REDACTED]@cache"
+    );
+}
+
+#[test]
+fn layer_redacts_events_and_spans() {
+    let buffer = Buffer::default();
+    let out = buffer.clone();
+    let subscriber = tracing_subscriber::registry().with(
+        redactor().layer(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_writer(move || out.clone()),
+        ),
+    );
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("login", password = "span-secret", user = "bob");
+        let _entered = span.enter();
+        tracing::info!(
+            password = "event-secret",
+            url = "postgres://u:url-secret@db/app",
+            "logged in"
+        );
+    });
+
+    let text = buffer.text();
+    assert!(text.contains("logged in"));
+    assert!(text.contains(r#""user":"bob""#), "{text}");
+    for secret in ["span-secret", "event-secret", "url-secret"] {
+        assert!(!text.contains(secret), "{secret} leaked: {text}");
+    }
+}
+
+#[test]
+fn layer_redacts_ansi_output() {
+    for pretty in [false, true] {
+        let buffer = Buffer::default();
+        let out = buffer.clone();
+        let layer = tracing_subscriber::fmt::layer()
+            .with_ansi(true)
+            .with_writer(move || out.clone());
+        let layer = if pretty {
+            layer.pretty().boxed()
+        } else {
+            layer.compact().boxed()
+        };
+        let subscriber = tracing_subscriber::registry().with(redactor().layer(layer));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(password = "event-secret", api_key = %"plain-secret", "logged in");
+        });
+        let text = buffer.text();
+        assert!(!text.contains("secret"), "leaked: {text:?}");
+    }
+}
+
+/// Records every field value a layer is given, as the OTLP exporter does.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl tracing::field::Visit for Recorder {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{}={value:?}", field.name()));
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for Recorder {
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        _: &tracing::span::Id,
+        _: Context<'_, S>,
+    ) {
+        attrs.record(&mut self.clone());
+    }
+
+    fn on_record(
+        &self,
+        _: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        _: Context<'_, S>,
+    ) {
+        values.record(&mut self.clone());
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, _: Context<'_, S>) {
+        event.record(&mut self.clone());
+    }
+}
+
+#[test]
+fn every_layer_gets_masked_values() {
+    let recorder = Recorder::default();
+    let subscriber = tracing_subscriber::registry().with(redactor().layer(recorder.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("call", password = tracing::field::Empty, attempt = 1);
+        span.record("password", "recorded-secret");
+        let _entered = span.enter();
+        tracing::info!(api_key = 42, "calling postgres://u:url-secret@db/app");
+        tracing::info!(count = 3, "nothing to mask");
+    });
+
+    let values = recorder.0.lock().unwrap().clone();
+    assert_eq!(
+        values,
+        [
+            "attempt=1",
+            "password=\"[REDACTED]\"",
+            "message=calling postgres://u:[REDACTED]@db/app",
+            "api_key=\"[REDACTED]\"",
+            "message=nothing to mask",
+            "count=3",
+        ]
+    );
+}
+
+#[test]
+fn without_password_keeps_the_rest_of_the_url() {
+    assert_eq!(
+        without_password("postgres://app:hunter2@db:5432/app?sslmode=require"),
+        "postgres://app@db:5432/app?sslmode=require"
+    );
+    assert_eq!(without_password("postgres://db/app"), "postgres://db/app");
+    assert_eq!(without_password("not a url"), REDACTED);
+}
+
+#[test]
+fn scheme_and_host_drops_credentials_path_and_query() {
+    assert_eq!(
+        scheme_and_host("https://user:pw@hooks.example.com:8443/T000/B000/secret?token=abc"),
+        "https://hooks.example.com"
+    );
+    assert_eq!(scheme_and_host("not a url"), REDACTED);
+}
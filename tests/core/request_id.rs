@@ -0,0 +1,171 @@
+use super::*;
+use axum::Router;
+use axum::extract::Extension;
+use axum::routing::get;
+use http_body::Body as _;
+use tower::ServiceExt;
+
+fn headers(value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(REQUEST_ID_HEADER, value.parse().unwrap());
+    headers
+}
+
+/// A body that only sends trailers, like a gRPC response.
+struct TrailersOnly(Option<HeaderMap>);
+
+impl http_body::Body for TrailersOnly {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+        Poll::Ready(self.0.take().map(|t| Ok(Frame::trailers(t))))
+    }
+}
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(|Extension(id): Extension<RequestId>| async move {
+                assert_eq!(RequestId::current(), Some(id.clone()));
+                id.to_string()
+            }),
+        )
+        .route(
+            "/grpc",
+            get(|| async {
+                let mut trailers = HeaderMap::new();
+                trailers.insert("grpc-status", HeaderValue::from_static("0"));
+                Response::new(Body::new(TrailersOnly(Some(trailers))))
+            }),
+        )
+        .layer(axum::middleware::from_fn(propagate))
+}
+
+async fn send(path: &str, request_id: Option<&str>) -> Response {
+    let mut req = http::Request::get(path);
+    if let Some(id) = request_id {
+        req = req.header(REQUEST_ID_HEADER, id);
+    }
+    app()
+        .oneshot(assign(req.body(Body::empty()).unwrap()))
+        .await
+        .unwrap()
+}
+
+async fn body_text(resp: Response) -> String {
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[test]
+fn parse_accepts_common_formats() {
+    for id in [
+        "abc",
+        "3f2a1c9e-8d7b-4e5f-a6b1-0c9d8e7f6a5b",
+        "req_01.a:b",
+        "a".repeat(128).as_str(),
+    ] {
+        assert_eq!(RequestId::parse(id).unwrap().as_str(), id);
+    }
+}
+
+#[test]
+fn parse_rejects_malformed() {
+    for id in [
+        "",
+        " ",
+        "a b",
+        "a/b",
+        "\u{e9}",
+        "x\n",
+        "a".repeat(129).as_str(),
+    ] {
+        assert!(RequestId::parse(id).is_none(), "{id:?} should be rejected");
+    }
+}
+
+#[test]
+fn from_headers_keeps_valid_id() {
+    assert_eq!(
+        RequestId::from_headers(&headers("client-123")).as_str(),
+        "client-123"
+    );
+}
+
+#[test]
+fn from_headers_generates_when_missing_or_malformed() {
+    let missing = RequestId::from_headers(&HeaderMap::new());
+    assert!(Uuid::parse_str(missing.as_str()).is_ok());
+
+    let malformed = RequestId::from_headers(&headers("not valid!"));
+    assert!(Uuid::parse_str(malformed.as_str()).is_ok());
+    assert_ne!(missing, malformed);
+}
+
+#[test]
+fn assign_replaces_malformed_header() {
+    let req = http::Request::get("/")
+        .header(REQUEST_ID_HEADER, "not valid!")
+        .body(())
+        .unwrap();
+    let req = assign(req);
+    let id = req.extensions().get::<RequestId>().unwrap();
+    assert_eq!(req.headers()[REQUEST_ID_HEADER], id.as_str());
+    assert_ne!(id.as_str(), "not valid!");
+}
+
+#[test]
+fn current_is_none_outside_request() {
+    assert!(RequestId::current().is_none());
+}
+
+#[tokio::test]
+async fn supplied_id_is_echoed_and_visible_to_handler() {
+    let resp = send("/", Some("client-123")).await;
+    assert_eq!(resp.headers()[REQUEST_ID_HEADER], "client-123");
+    assert_eq!(body_text(resp).await, "client-123");
+}
+
+#[tokio::test]
+async fn generated_id_is_echoed_and_visible_to_handler() {
+    let resp = send("/", None).await;
+    let header = resp.headers()[REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert!(Uuid::parse_str(&header).is_ok());
+    assert_eq!(body_text(resp).await, header);
+}
+
+#[tokio::test]
+async fn id_is_added_to_trailers() {
+    let resp = send("/grpc", Some("client-123")).await;
+    let mut body = resp.into_body();
+    let frame = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx))
+        .await
+        .unwrap()
+        .unwrap();
+    let trailers = frame.into_trailers().unwrap();
+    assert_eq!(trailers[REQUEST_ID_HEADER], "client-123");
+    assert_eq!(trailers["grpc-status"], "0");
+}
+
+#[tokio::test]
+async fn propagate_assigns_id_without_assign_layer() {
+    let resp = app()
+        .oneshot(http::Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let header = resp.headers()[REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert_eq!(body_text(resp).await, header);
+}
@@ -0,0 +1,36 @@
+use super::*;
+
+fn temp_file(contents: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("midnight-secret-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn read_file_drops_trailing_newline() {
+    let path = temp_file("hunter2 \n");
+    let secret = read_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(secret.unwrap().expose(), "hunter2");
+}
+
+#[test]
+fn read_file_reports_missing_file() {
+    let err = read_file(Path::new("/nonexistent/secret")).unwrap_err();
+    assert!(
+        err.starts_with("cannot read /nonexistent/secret: "),
+        "{err}"
+    );
+}
+
+#[test]
+fn file_secrets_use_the_env_name_of_each_key() {
+    assert_eq!(
+        FileSecrets.describe("database_url"),
+        "environment variable DATABASE_URL_FILE"
+    );
+    assert_eq!(
+        FileSecrets.describe("otlp_endpoint"),
+        "environment variable OTEL_EXPORTER_OTLP_ENDPOINT_FILE"
+    );
+}
@@ -0,0 +1,184 @@
+use super::*;
+use crate::core::redact::Secret;
+use axum::Router;
+use axum::routing::get;
+use opentelemetry_proto::tonic::collector::trace::v1::trace_service_server::{
+    TraceService, TraceServiceServer,
+};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use opentelemetry_proto::tonic::common::v1::any_value::Value;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tower_http::trace::TraceLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.parse().unwrap(), HeaderValue::from_static(v)))
+        .collect()
+}
+
+fn grpc_span(headers: &HeaderMap) -> tracing::Span {
+    let span = tracing::info_span!("grpc", trace_id = tracing::field::Empty);
+    link_span(&span, headers);
+    span
+}
+
+#[test]
+fn exports_nothing_without_endpoint() {
+    let telemetry = Telemetry::init(&Config::for_tests()).unwrap();
+    assert!(!telemetry.is_exporting());
+}
+
+#[test]
+fn span_joins_incoming_traceparent() {
+    let telemetry = Telemetry::init(&Config::for_tests()).unwrap();
+    let subscriber = tracing_subscriber::registry().with(telemetry.layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let span = grpc_span(&headers(&[
+            ("traceparent", TRACEPARENT),
+            ("tracestate", "vendor=value"),
+        ]));
+        let cx = span.context();
+        let span_context = cx.span().span_context().clone();
+        assert_eq!(span_context.trace_id().to_string(), TRACE_ID);
+        assert_eq!(
+            span_context.trace_state().get("vendor"),
+            Some("value"),
+            "tracestate is carried over"
+        );
+    });
+}
+
+#[test]
+fn span_starts_new_trace_without_traceparent() {
+    let telemetry = Telemetry::init(&Config::for_tests()).unwrap();
+    let subscriber = tracing_subscriber::registry().with(telemetry.layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let first = trace_id(&grpc_span(&HeaderMap::new())).unwrap();
+        let second = trace_id(&grpc_span(&HeaderMap::new())).unwrap();
+        assert_ne!(first, second);
+        assert_ne!(first.to_string(), TRACE_ID);
+    });
+}
+
+#[test]
+fn malformed_traceparent_is_ignored() {
+    let telemetry = Telemetry::init(&Config::for_tests()).unwrap();
+    let subscriber = tracing_subscriber::registry().with(telemetry.layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let span = grpc_span(&headers(&[("traceparent", "00-not-a-trace-01")]));
+        assert!(trace_id(&span).is_some());
+    });
+}
+
+#[test]
+fn no_trace_id_without_telemetry_layer() {
+    let span = grpc_span(&headers(&[("traceparent", TRACEPARENT)]));
+    assert!(trace_id(&span).is_none());
+}
+
+#[tokio::test]
+async fn response_carries_trace_id() {
+    let telemetry = Telemetry::init(&Config::for_tests()).unwrap();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(telemetry.layer()));
+
+    let app = Router::new()
+        .route("/", get(|| async { "ok" }))
+        .layer(axum::middleware::from_fn(trace_id_header))
+        .layer(TraceLayer::new_for_http().make_span_with(|req: &Request| grpc_span(req.headers())));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("http://{addr}/"))
+        .header("traceparent", TRACEPARENT)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()[TRACE_ID_HEADER], TRACE_ID);
+
+    let resp = client.get(format!("http://{addr}/")).send().await.unwrap();
+    let fresh = resp.headers()[TRACE_ID_HEADER].to_str().unwrap();
+    assert_eq!(fresh.len(), 32);
+    assert_ne!(fresh, TRACE_ID);
+}
+
+struct Collector(mpsc::UnboundedSender<ExportTraceServiceRequest>);
+
+#[tonic::async_trait]
+impl TraceService for Collector {
+    async fn export(
+        &self,
+        request: tonic::Request<ExportTraceServiceRequest>,
+    ) -> Result<tonic::Response<ExportTraceServiceResponse>, tonic::Status> {
+        let _ = self.0.send(request.into_inner());
+        Ok(tonic::Response::new(ExportTraceServiceResponse::default()))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn exports_spans_to_otlp_collector() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(TraceServiceServer::new(Collector(tx)))
+            .serve_with_incoming(tonic::transport::server::TcpIncoming::from(listener)),
+    );
+
+    let config = Config {
+        otlp_endpoint: Some(Secret::new(format!("http://{addr}"))),
+        otel_service_name: "midnight-test".into(),
+        ..Config::for_tests()
+    };
+    let telemetry = Telemetry::init(&config).unwrap();
+    assert!(telemetry.is_exporting());
+
+    let subscriber = tracing_subscriber::registry().with(telemetry.layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let span = grpc_span(&headers(&[("traceparent", TRACEPARENT)]));
+        span.in_scope(|| tracing::info!("handled"));
+    });
+    // Shutdown blocks until the batch is exported.
+    tokio::task::spawn_blocking(move || telemetry.shutdown())
+        .await
+        .unwrap();
+
+    let request = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("collector received no spans")
+        .unwrap();
+    let resource_spans = &request.resource_spans[0];
+    let service_name = resource_spans
+        .resource
+        .as_ref()
+        .unwrap()
+        .attributes
+        .iter()
+        .find(|kv| kv.key == "service.name")
+        .and_then(|kv| kv.value.as_ref()?.value.clone());
+    assert_eq!(
+        service_name,
+        Some(Value::StringValue("midnight-test".into()))
+    );
+
+    let span = &resource_spans.scope_spans[0].spans[0];
+    assert_eq!(span.name, "grpc");
+    assert_eq!(hex(&span.trace_id), TRACE_ID);
+    assert_eq!(hex(&span.parent_span_id), "00f067aa0ba902b7");
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,277 @@
+use super::*;
+use std::net::SocketAddr;
+
+use axum::Router;
+use axum::body::Body;
+use axum::routing::get;
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedIssuer, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair,
+};
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::ClientConfig;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_stream::StreamExt;
+use tonic::transport::server::Connected;
+use tower::ServiceExt;
+
+/// A CA with a server and a client certificate, written as PEM files to a
+/// temp directory that is removed on drop.
+struct Pki {
+    dir: PathBuf,
+    ca: CertifiedIssuer<'static, KeyPair>,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("midnight-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Midnight Test CA");
+        let ca = CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap();
+        let pki = Self { dir, ca };
+        std::fs::write(pki.path("ca.pem"), pki.ca.pem()).unwrap();
+        pki.issue("server", "localhost", ExtendedKeyUsagePurpose::ServerAuth);
+        pki.issue("client", "test client", ExtendedKeyUsagePurpose::ClientAuth);
+        pki
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Writes `<name>.pem` and `<name>.key`.
+    fn issue(&self, name: &str, common_name: &str, usage: ExtendedKeyUsagePurpose) {
+        let mut params = CertificateParams::new(vec!["localhost".to_owned()]).unwrap();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, common_name);
+        params.extended_key_usages = vec![usage];
+        let key = KeyPair::generate().unwrap();
+        let cert = params.signed_by(&key, &self.ca).unwrap();
+        std::fs::write(self.path(&format!("{name}.pem")), cert.pem()).unwrap();
+        std::fs::write(self.path(&format!("{name}.key")), key.serialize_pem()).unwrap();
+    }
+
+    fn settings(&self, mtls: bool) -> TlsSettings {
+        TlsSettings {
+            cert: self.path("server.pem"),
+            key: self.path("server.key"),
+            client_ca: mtls.then(|| self.path("ca.pem")),
+            min_version: "1.2".to_owned(),
+        }
+    }
+
+    fn client(
+        &self,
+        with_cert: bool,
+        versions: &'static [&'static rustls::SupportedProtocolVersion],
+    ) -> TlsConnector {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.ca.der().clone()).unwrap();
+        let builder =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_protocol_versions(versions)
+                .unwrap()
+                .with_root_certificates(roots);
+        let config = if with_cert {
+            builder
+                .with_client_auth_cert(
+                    read_certs(&self.path("client.pem")).unwrap(),
+                    PrivateKeyDer::from_pem_file(self.path("client.key")).unwrap(),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+        TlsConnector::from(Arc::new(config))
+    }
+}
+
+impl Drop for Pki {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Moves a file's modification time forward, since a rewrite within the
+/// same clock tick might not change it.
+fn touch(path: &Path, secs: u64) {
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(secs))
+        .unwrap();
+}
+
+async fn serve(tls: Arc<Tls>) -> (SocketAddr, ReceiverStream<io::Result<TlsStream<TcpStream>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    (addr, tls.incoming(listener))
+}
+
+async fn connect(
+    client: &TlsConnector,
+    addr: SocketAddr,
+) -> io::Result<tokio_rustls::client::TlsStream<TcpStream>> {
+    let stream = TcpStream::connect(addr).await?;
+    let name = ServerName::try_from("localhost").unwrap();
+    client.connect(name, stream).await
+}
+
+#[test]
+fn from_config_needs_cert_and_key() {
+    let mut config = Config::for_tests();
+    assert_eq!(TlsSettings::from_config(&config), None);
+
+    config.tls_cert = Some("/etc/midnight/server.pem".to_owned());
+    config.tls_key = Some("/etc/midnight/server.key".to_owned());
+    config.tls_min_version = "1.3".to_owned();
+    assert_eq!(
+        TlsSettings::from_config(&config),
+        Some(TlsSettings {
+            cert: "/etc/midnight/server.pem".into(),
+            key: "/etc/midnight/server.key".into(),
+            client_ca: None,
+            min_version: "1.3".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn loads_certificate_key_and_client_ca() {
+    let pki = Pki::new();
+    let config = pki.settings(true).load().unwrap();
+    assert_eq!(
+        config.alpn_protocols,
+        [b"h2".to_vec(), b"http/1.1".to_vec()]
+    );
+}
+
+#[test]
+fn load_reports_bad_files() {
+    let pki = Pki::new();
+    let mut settings = pki.settings(false);
+    settings.key = pki.path("client.key");
+    let err = settings.load().unwrap_err();
+    assert_eq!(err.to_string(), "TLS certificate and key don't match");
+
+    settings.cert = pki.path("missing.pem");
+    let err = settings.load().unwrap_err();
+    assert!(err.to_string().contains("missing.pem"), "{err}");
+
+    let mut settings = pki.settings(true);
+    settings.client_ca = Some(pki.path("server.key"));
+    let err = settings.load().unwrap_err();
+    assert!(err.to_string().contains("no certificates in"), "{err}");
+}
+
+#[test]
+fn reloads_only_when_files_change() {
+    let pki = Pki::new();
+    let tls = Tls::new(pki.settings(false)).unwrap();
+    let before = tls.config.load_full();
+    assert!(!tls.reload_if_changed().unwrap());
+
+    pki.issue("server", "localhost", ExtendedKeyUsagePurpose::ServerAuth);
+    touch(&pki.path("server.pem"), 10);
+    assert!(tls.reload_if_changed().unwrap());
+    assert!(!Arc::ptr_eq(&before, &tls.config.load_full()));
+    assert!(!tls.reload_if_changed().unwrap());
+}
+
+#[test]
+fn failed_reload_keeps_current_config() {
+    let pki = Pki::new();
+    let tls = Tls::new(pki.settings(false)).unwrap();
+    let before = tls.config.load_full();
+
+    std::fs::write(pki.path("server.pem"), "not a certificate").unwrap();
+    touch(&pki.path("server.pem"), 10);
+    assert!(tls.reload_if_changed().is_err());
+    assert!(Arc::ptr_eq(&before, &tls.config.load_full()));
+    // Not retried until the files change again.
+    assert!(!tls.reload_if_changed().unwrap());
+}
+
+#[tokio::test]
+async fn mtls_client_subject_becomes_principal() {
+    let pki = Pki::new();
+    let (addr, mut incoming) = serve(Tls::new(pki.settings(true)).unwrap()).await;
+
+    let _client = connect(&pki.client(true, rustls::ALL_VERSIONS), addr)
+        .await
+        .unwrap();
+    let stream = incoming.next().await.unwrap().unwrap();
+
+    let app = Router::new()
+        .route(
+            "/",
+            get(|req: Request| async move {
+                req.extensions()
+                    .get::<Principal>()
+                    .map(|p| p.0.clone())
+                    .unwrap_or_default()
+            }),
+        )
+        .layer(axum::middleware::from_fn(client_principal));
+    let mut req = http::Request::get("/").body(Body::empty()).unwrap();
+    req.extensions_mut().insert(stream.connect_info());
+    let resp = app.oneshot(req).await.unwrap();
+    let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+    assert_eq!(body, "x509:CN=test client");
+}
+
+#[tokio::test]
+async fn mtls_rejects_client_without_certificate() {
+    let pki = Pki::new();
+    let (addr, mut incoming) = serve(Tls::new(pki.settings(true)).unwrap()).await;
+
+    let _client = connect(&pki.client(false, rustls::ALL_VERSIONS), addr).await;
+    let accepted = tokio::time::timeout(Duration::from_millis(500), incoming.next()).await;
+    assert!(
+        accepted.is_err(),
+        "connection without a client certificate was accepted"
+    );
+}
+
+#[tokio::test]
+async fn min_version_rejects_older_clients() {
+    let pki = Pki::new();
+    let mut settings = pki.settings(false);
+    settings.min_version = "1.3".to_owned();
+    let (addr, mut incoming) = serve(Tls::new(settings).unwrap()).await;
+
+    static TLS12_ONLY: &[&rustls::SupportedProtocolVersion] = &[&rustls::version::TLS12];
+    let old = pki.client(false, TLS12_ONLY);
+    assert!(connect(&old, addr).await.is_err());
+
+    let current = pki.client(false, rustls::ALL_VERSIONS);
+    connect(&current, addr).await.unwrap();
+    incoming.next().await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn open_connections_survive_reload() {
+    let pki = Pki::new();
+    let tls = Tls::new(pki.settings(false)).unwrap();
+    let (addr, mut incoming) = serve(Arc::clone(&tls)).await;
+    let client = pki.client(false, rustls::ALL_VERSIONS);
+
+    let mut first = connect(&client, addr).await.unwrap();
+    let mut accepted = incoming.next().await.unwrap().unwrap();
+
+    pki.issue("server", "localhost", ExtendedKeyUsagePurpose::ServerAuth);
+    touch(&pki.path("server.pem"), 10);
+    assert!(tls.reload_if_changed().unwrap());
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    first.write_all(b"ping").await.unwrap();
+    let mut buf = [0; 4];
+    accepted.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"ping");
+
+    connect(&client, addr).await.unwrap();
+    incoming.next().await.unwrap().unwrap();
+}
@@ -0,0 +1,71 @@
+use super::*;
+use crate::core::config::Config;
+use axum::Router;
+use axum::body::Body;
+use axum::routing::post;
+use std::time::Duration;
+use tower::ServiceExt;
+
+/// Fails fast, so audit writes error out instead of waiting on a database.
+fn unreachable_pool() -> sqlx::PgPool {
+    sqlx::postgres::PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(100))
+        .connect_lazy("postgres://localhost:1/test")
+        .unwrap()
+}
+
+const SET_LOG_FILTER: &str = "/midnight.AdminService/SetLogFilter";
+const LIST_HEALTH: &str = "/midnight.HealthService/ListHealthServices";
+
+fn app() -> Router {
+    let state = AppState::new(Config::for_tests(), unreachable_pool());
+    let has_scope =
+        |req: Request| async move { req.extensions().get::<AuditScope>().is_some().to_string() };
+    Router::new()
+        .route(SET_LOG_FILTER, post(has_scope))
+        .route(LIST_HEALTH, post(has_scope))
+        .layer(axum::middleware::from_fn_with_state(state, record))
+}
+
+async fn call(path: &str) -> String {
+    let resp = app()
+        .oneshot(
+            http::Request::post(path)
+                .header("content-type", "application/grpc")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[test]
+fn admin_mutations_are_mutating() {
+    for method in admin::MUTATING_METHODS {
+        assert!(is_mutating(&format!("/midnight.AdminService/{method}")));
+    }
+}
+
+#[test]
+fn reads_are_not_mutating() {
+    assert!(!is_mutating("/midnight.AdminService/ListHealthProbes"));
+    assert!(!is_mutating("/midnight.AdminService/ListAuditEvents"));
+    assert!(!is_mutating(LIST_HEALTH));
+    assert!(!is_mutating("/midnight.HealthService/SetLogFilter"));
+    assert!(!is_mutating("/livez"));
+    assert!(!is_mutating(""));
+}
+
+#[tokio::test]
+async fn mutating_calls_get_audit_scope() {
+    assert_eq!(call(SET_LOG_FILTER).await, "true");
+}
+
+#[tokio::test]
+async fn other_calls_pass_through() {
+    assert_eq!(call(LIST_HEALTH).await, "false");
+}
@@ -0,0 +1,175 @@
+use super::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::Router;
+use axum::body::Body;
+use axum::routing::post;
+use jsonwebtoken::{EncodingKey, Header};
+use tower::ServiceExt;
+
+use crate::core::auth::Claims;
+use crate::core::config::Config;
+use crate::core::redact::Secret;
+
+const SECRET: &str = "an HS256 test secret of 32+ bytes";
+
+fn state(auth: bool) -> Arc<AppState> {
+    let mut config = Config::for_tests();
+    if auth {
+        config.auth_jwt_secret = Some(Secret::new(SECRET.to_owned()));
+        config.auth_public = vec!["midnight.HealthService".to_owned()];
+    }
+    let db = sqlx::PgPool::connect_lazy("postgres://localhost/test").unwrap();
+    AppState::new(config, db)
+}
+
+/// Answers with the principal and subject the handler sees.
+async fn whoami(req: Request) -> String {
+    let principal = req.extensions().get::<Principal>().map(|p| p.0.clone());
+    let subject = req
+        .extensions()
+        .get::<Claims>()
+        .and_then(|c| c.subject().map(str::to_owned));
+    format!("{principal:?} {subject:?}")
+}
+
+fn app(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/midnight.AdminService/SetLogFilter", post(whoami))
+        .route("/midnight.HealthService/List", post(whoami))
+        .layer(axum::middleware::from_fn_with_state(state, authenticate))
+}
+
+fn token(sub: &str) -> String {
+    let exp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 300;
+    jsonwebtoken::encode(
+        &Header::default(),
+        &serde_json::json!({ "sub": sub, "exp": exp }),
+        &EncodingKey::from_secret(SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+async fn call(
+    state: &Arc<AppState>,
+    path: &str,
+    authorization: Option<&str>,
+    principal: Option<&str>,
+) -> (Option<String>, String) {
+    let mut req = http::Request::post(path);
+    if let Some(value) = authorization {
+        req = req.header(AUTHORIZATION, value);
+    }
+    let mut req = req.body(Body::empty()).unwrap();
+    if let Some(principal) = principal {
+        req.extensions_mut().insert(Principal(principal.to_owned()));
+    }
+    req.extensions_mut().insert(RequestId::new());
+    let resp = app(Arc::clone(state)).oneshot(req).await.unwrap();
+    let status = resp
+        .headers()
+        .get("grpc-status")
+        .map(|v| v.to_str().unwrap().to_owned());
+    let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn open_without_auth_config() {
+    let (status, body) = call(
+        &state(false),
+        "/midnight.AdminService/SetLogFilter",
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(status, None);
+    assert_eq!(body, "None None");
+}
+
+#[tokio::test]
+async fn valid_token_sets_principal_and_claims() {
+    let bearer = format!("Bearer {}", token("alice"));
+    let (status, body) = call(
+        &state(true),
+        "/midnight.AdminService/SetLogFilter",
+        Some(&bearer),
+        None,
+    )
+    .await;
+    assert_eq!(status, None);
+    assert_eq!(body, "Some(\"jwt:alice\") Some(\"alice\")");
+}
+
+#[tokio::test]
+async fn rejects_missing_and_invalid_tokens() {
+    let state = state(true);
+    let path = "/midnight.AdminService/SetLogFilter";
+    for authorization in [
+        None,
+        Some("Basic YWxpY2U6aHVudGVyMg=="),
+        Some("Bearer nope"),
+    ] {
+        let (status, _) = call(&state, path, authorization, None).await;
+        assert_eq!(status.as_deref(), Some("16"), "{authorization:?}");
+    }
+}
+
+#[tokio::test]
+async fn public_methods_need_no_token_but_check_one_sent() {
+    let state = state(true);
+    let path = "/midnight.HealthService/List";
+    let (status, body) = call(&state, path, None, None).await;
+    assert_eq!(status, None);
+    assert_eq!(body, "None None");
+
+    let (status, _) = call(&state, path, Some("Bearer nope"), None).await;
+    assert_eq!(status.as_deref(), Some("16"));
+}
+
+#[tokio::test]
+async fn transport_principal_counts_as_authenticated() {
+    let (status, body) = call(
+        &state(true),
+        "/midnight.AdminService/SetLogFilter",
+        None,
+        Some("uid:1000"),
+    )
+    .await;
+    assert_eq!(status, None);
+    assert_eq!(body, "Some(\"uid:1000\") None");
+}
+
+#[tokio::test]
+async fn follows_config_updates() {
+    let state = state(true);
+    let path = "/midnight.AdminService/SetLogFilter";
+    assert_eq!(
+        call(&state, path, None, None).await.0.as_deref(),
+        Some("16")
+    );
+
+    let mut config = Config::for_tests();
+    config.auth_public = vec!["midnight.AdminService".to_owned()];
+    config.auth_jwt_secret = Some(Secret::new(SECRET.to_owned()));
+    state.update_config(config);
+    assert_eq!(call(&state, path, None, None).await.0, None);
+}
+
+#[test]
+fn parses_bearer_header() {
+    let headers = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, value.parse().unwrap());
+        headers
+    };
+    assert_eq!(bearer(&HeaderMap::new()), Ok(None));
+    assert_eq!(bearer(&headers("Bearer abc")), Ok(Some("abc".to_owned())));
+    assert_eq!(bearer(&headers("bearer  abc ")), Ok(Some("abc".to_owned())));
+    assert!(bearer(&headers("Bearer ")).is_err());
+    assert!(bearer(&headers("Token abc")).is_err());
+}
@@ -0,0 +1,159 @@
+use super::*;
+use std::time::Duration;
+
+use axum::Router;
+use axum::body::Body;
+use axum::routing::post;
+use tower::ServiceExt;
+
+use crate::core::authz::Policy;
+use crate::core::config::Config;
+
+/// Fails fast, so audit writes error out instead of waiting on a database.
+fn unreachable_pool() -> sqlx::PgPool {
+    sqlx::postgres::PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(100))
+        .connect_lazy("postgres://localhost:1/test")
+        .unwrap()
+}
+
+fn state(source: &str) -> Arc<AppState> {
+    let mut config = Config::for_tests();
+    config.authz_source = source.to_owned();
+    config.authz_rules = vec![
+        "midnight.HealthService=*".to_owned(),
+        "midnight.AdminService=admin".to_owned(),
+    ];
+    AppState::new(config, unreachable_pool())
+}
+
+fn app(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/midnight.AdminService/SetLogFilter",
+            post(|| async { "ok" }),
+        )
+        .route(
+            "/midnight.HealthService/ListHealthServices",
+            post(|| async { "ok" }),
+        )
+        .route("/other.Service/Method", post(|| async { "ok" }))
+        .layer(axum::middleware::from_fn_with_state(state, authorize))
+}
+
+/// The grpc-status of a call by `principal` with `roles` in its token.
+async fn call(
+    state: &Arc<AppState>,
+    path: &str,
+    principal: Option<&str>,
+    roles: &[&str],
+) -> Option<String> {
+    let mut req = http::Request::post(path).body(Body::empty()).unwrap();
+    if let Some(principal) = principal {
+        req.extensions_mut().insert(Principal(principal.to_owned()));
+    }
+    let claims = serde_json::json!({ "sub": principal, "roles": roles });
+    req.extensions_mut()
+        .insert(Claims(claims.as_object().unwrap().clone()));
+    let resp = app(Arc::clone(state)).oneshot(req).await.unwrap();
+    resp.headers()
+        .get("grpc-status")
+        .map(|v| v.to_str().unwrap().to_owned())
+}
+
+const SET_LOG_FILTER: &str = "/midnight.AdminService/SetLogFilter";
+
+#[tokio::test]
+async fn open_when_off() {
+    let state = state("off");
+    assert_eq!(call(&state, "/other.Service/Method", None, &[]).await, None);
+}
+
+#[tokio::test]
+async fn allows_callers_with_a_required_role() {
+    let state = state("config");
+    assert_eq!(
+        call(&state, SET_LOG_FILTER, Some("alice"), &["admin"]).await,
+        None
+    );
+    assert_eq!(
+        call(
+            &state,
+            "/midnight.HealthService/ListHealthServices",
+            None,
+            &[]
+        )
+        .await,
+        None
+    );
+}
+
+#[tokio::test]
+async fn denies_missing_roles_and_unlisted_methods() {
+    let state = state("config");
+    assert_eq!(
+        call(&state, SET_LOG_FILTER, Some("alice"), &["viewer"])
+            .await
+            .as_deref(),
+        Some("7")
+    );
+    assert_eq!(
+        call(&state, SET_LOG_FILTER, None, &[]).await.as_deref(),
+        Some("7")
+    );
+    assert_eq!(
+        call(&state, "/other.Service/Method", Some("alice"), &["admin"])
+            .await
+            .as_deref(),
+        Some("7")
+    );
+}
+
+#[tokio::test]
+async fn denial_names_caller_and_reason() {
+    let state = state("config");
+    let mut req = http::Request::post(SET_LOG_FILTER)
+        .body(Body::empty())
+        .unwrap();
+    req.extensions_mut()
+        .insert(Principal("uid:1000".to_owned()));
+    let resp = app(state).oneshot(req).await.unwrap();
+    let status = tonic::Status::from_header_map(resp.headers()).unwrap();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert_eq!(
+        status.message(),
+        "permission denied: uid:1000 may not call /midnight.AdminService/SetLogFilter: it needs one of the roles admin"
+    );
+}
+
+#[tokio::test]
+async fn database_policy_starts_empty() {
+    let state = state("database");
+    assert_eq!(*state.policy(), Policy::default());
+    assert_eq!(
+        call(&state, SET_LOG_FILTER, Some("alice"), &["admin"])
+            .await
+            .as_deref(),
+        Some("7")
+    );
+}
+
+#[tokio::test]
+async fn follows_config_updates() {
+    let state = state("config");
+    assert_eq!(
+        call(&state, SET_LOG_FILTER, Some("alice"), &["ops"])
+            .await
+            .as_deref(),
+        Some("7")
+    );
+
+    let mut config = Config::for_tests();
+    config.authz_source = "config".to_owned();
+    config.authz_rules = vec!["midnight.AdminService=ops".to_owned()];
+    state.update_config(config);
+    assert_eq!(
+        call(&state, SET_LOG_FILTER, Some("alice"), &["ops"]).await,
+        None
+    );
+}
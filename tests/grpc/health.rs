@@ -1,4 +1,5 @@
 use super::*;
+use crate::core::config::Config;
 use crate::core::health::{ServiceHealth, ServiceStatus};
 use std::time::{Duration, Instant};
 use uuid::Uuid;
@@ -13,6 +14,10 @@ fn to_proto_serving_status() {
         registered_at: Instant::now(),
         version: Some("1.0.0".to_owned()),
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
@@ -29,6 +34,10 @@ fn to_proto_not_serving_status() {
         registered_at: Instant::now(),
         version: None,
         message: Some("down".to_owned()),
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
@@ -36,6 +45,26 @@ fn to_proto_not_serving_status() {
     assert_eq!(proto.message.as_deref(), Some("down"));
 }
 
+#[test]
+fn to_proto_degraded_status() {
+    let health = ServiceHealth {
+        id: Uuid::new_v4(),
+        name: "test".to_owned(),
+        status: ServiceStatus::Degraded,
+        interval: Duration::from_secs(30),
+        registered_at: Instant::now(),
+        version: None,
+        message: Some("slow".to_owned()),
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
+    };
+
+    let proto = to_proto(&health);
+    assert_eq!(proto.status(), ServingStatus::Degraded);
+}
+
 #[test]
 fn to_proto_preserves_id_as_string() {
     let id = Uuid::new_v4();
@@ -47,12 +76,40 @@ fn to_proto_preserves_id_as_string() {
         registered_at: Instant::now(),
         version: None,
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
     assert_eq!(proto.id, id.to_string());
 }
 
+#[test]
+fn to_proto_timeout_and_next_probe() {
+    let next_probe_at = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let health = ServiceHealth {
+        id: Uuid::new_v4(),
+        name: "svc".to_owned(),
+        status: ServiceStatus::Serving,
+        interval: Duration::from_secs(60),
+        registered_at: Instant::now(),
+        version: None,
+        message: None,
+        flapping: false,
+        timeout: Duration::from_millis(2500),
+        next_probe_at,
+        manual_override: None,
+    };
+
+    let proto = to_proto(&health);
+    let timeout = proto.timeout.unwrap();
+    assert_eq!(timeout.seconds, 2);
+    assert_eq!(timeout.nanos, 500_000_000);
+    assert_eq!(proto.next_probe_at.unwrap().seconds, 1_700_000_000);
+}
+
 #[test]
 fn to_proto_preserves_name() {
     let health = ServiceHealth {
@@ -63,6 +120,10 @@ fn to_proto_preserves_name() {
         registered_at: Instant::now(),
         version: None,
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
@@ -79,6 +140,10 @@ fn to_proto_interval_conversion() {
         registered_at: Instant::now(),
         version: None,
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
@@ -97,6 +162,10 @@ fn to_proto_uptime_is_positive() {
         registered_at: Instant::now(),
         version: None,
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
@@ -115,6 +184,10 @@ fn to_proto_version_present() {
         registered_at: Instant::now(),
         version: Some("2.5.1".to_owned()),
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
@@ -131,6 +204,10 @@ fn to_proto_version_absent() {
         registered_at: Instant::now(),
         version: None,
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
@@ -147,22 +224,44 @@ fn to_proto_message_absent_when_healthy() {
         registered_at: Instant::now(),
         version: None,
         message: None,
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
     };
 
     let proto = to_proto(&health);
     assert!(proto.message.is_none());
 }
 
-fn test_config() -> crate::core::config::Config {
-    crate::core::config::Config {
-        listen_addr: "127.0.0.1:50051".to_owned(),
-        log_level: "info".to_owned(),
-        log_style: "plain".to_owned(),
-        cors_origins: vec!["*".to_owned()],
-        database_url: "postgres://localhost/test".to_owned(),
-        db_max_connections: 5,
-        request_timeout_secs: 30,
-    }
+#[test]
+fn to_proto_includes_manual_override() {
+    use crate::core::health::HealthOverride;
+
+    let manual = HealthOverride::new(
+        ServiceStatus::NotServing,
+        "maintenance",
+        Some(Duration::from_secs(60)),
+    );
+    let health = ServiceHealth {
+        id: Uuid::new_v4(),
+        name: "svc".to_owned(),
+        status: ServiceStatus::NotServing,
+        interval: Duration::from_secs(60),
+        registered_at: Instant::now(),
+        version: None,
+        message: Some(manual.message()),
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: Some(manual.clone()),
+    };
+
+    let proto = to_proto(&health).manual_override.unwrap();
+    assert_eq!(proto.status(), ServingStatus::NotServing);
+    assert_eq!(proto.reason, "maintenance");
+    assert_eq!(proto.set_at, Some(manual.set_at.into()));
+    assert_eq!(proto.expires_at, manual.expires_at.map(Into::into));
 }
 
 fn test_pool() -> sqlx::PgPool {
@@ -171,7 +270,7 @@ fn test_pool() -> sqlx::PgPool {
 
 #[tokio::test]
 async fn list_health_services_returns_all() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     state
         .health()
         .register(
@@ -207,7 +306,7 @@ async fn list_health_services_returns_all() {
 
 #[tokio::test]
 async fn list_health_services_empty() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     let handler = HealthServiceImpl::new(Arc::clone(&state));
 
     let response = handler
@@ -219,7 +318,7 @@ async fn list_health_services_empty() {
 
 #[tokio::test]
 async fn get_health_service_by_uuid() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     let id = state
         .health()
         .register(
@@ -245,7 +344,7 @@ async fn get_health_service_by_uuid() {
 
 #[tokio::test]
 async fn get_health_service_empty_id_returns_server() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     state
         .health()
         .register(
@@ -267,7 +366,7 @@ async fn get_health_service_empty_id_returns_server() {
 
 #[tokio::test]
 async fn get_health_service_no_id_field_returns_server() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     state
         .health()
         .register(
@@ -291,7 +390,7 @@ async fn get_health_service_no_id_field_returns_server() {
 
 #[tokio::test]
 async fn get_health_service_invalid_uuid_returns_error() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     let handler = HealthServiceImpl::new(Arc::clone(&state));
 
     let result = handler
@@ -306,7 +405,7 @@ async fn get_health_service_invalid_uuid_returns_error() {
 
 #[tokio::test]
 async fn get_health_service_unknown_uuid_returns_not_found() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     let handler = HealthServiceImpl::new(Arc::clone(&state));
 
     let result = handler
@@ -321,7 +420,7 @@ async fn get_health_service_unknown_uuid_returns_not_found() {
 
 #[tokio::test]
 async fn get_health_service_no_server_registered_returns_not_found() {
-    let state = AppState::new(test_config(), test_pool());
+    let state = AppState::new(Config::for_tests(), test_pool());
     let handler = HealthServiceImpl::new(Arc::clone(&state));
 
     let result = handler
@@ -331,3 +430,267 @@ async fn get_health_service_no_server_registered_returns_not_found() {
     let status = result.unwrap_err();
     assert_eq!(status.code(), tonic::Code::NotFound);
 }
+
+#[test]
+fn to_proto_event_maps_variants() {
+    let health = ServiceHealth {
+        id: Uuid::new_v4(),
+        name: "svc".to_owned(),
+        status: ServiceStatus::NotServing,
+        interval: Duration::from_secs(60),
+        registered_at: Instant::now(),
+        version: None,
+        message: Some("down".to_owned()),
+        flapping: false,
+        timeout: Duration::from_secs(5),
+        next_probe_at: std::time::SystemTime::now(),
+        manual_override: None,
+    };
+
+    let event = to_proto_event(&HealthEvent::Changed(health.clone())).unwrap();
+    match event.event.unwrap() {
+        Event::Changed(svc) => {
+            assert_eq!(svc.name, "svc");
+            assert_eq!(svc.status(), ServingStatus::NotServing);
+        }
+        other => panic!("unexpected event: {other:?}"),
+    }
+
+    let event = to_proto_event(&HealthEvent::Deregistered(health)).unwrap();
+    assert!(matches!(event.event, Some(Event::Deregistered(_))));
+
+    assert!(to_proto_event(&HealthEvent::ServerOverride(None)).is_none());
+}
+
+#[tokio::test]
+async fn watch_health_services_sends_snapshot_first() {
+    use tokio_stream::StreamExt;
+
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register(
+            "svc1",
+            Duration::from_secs(60),
+            None,
+            Box::new(|| Box::pin(async { Ok(()) })),
+        )
+        .await;
+
+    let handler = HealthServiceImpl::new(Arc::clone(&state));
+    let mut stream = handler
+        .watch_health_services(Request::new(()))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let first = stream.next().await.unwrap().unwrap();
+    match first.event.unwrap() {
+        Event::Snapshot(list) => {
+            assert_eq!(list.services.len(), 1);
+            assert_eq!(list.services[0].name, "svc1");
+        }
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn watch_health_services_pushes_transitions() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio_stream::StreamExt;
+
+    let should_fail = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&should_fail);
+
+    let state = AppState::new(Config::for_tests(), test_pool());
+    let id = state
+        .health()
+        .register(
+            "flaky",
+            Duration::from_millis(50),
+            None,
+            Box::new(move || {
+                let flag = Arc::clone(&flag);
+                Box::pin(async move {
+                    if flag.load(Ordering::Relaxed) {
+                        Err("went down".to_owned())
+                    } else {
+                        Ok(())
+                    }
+                })
+            }),
+        )
+        .await;
+
+    let handler = HealthServiceImpl::new(Arc::clone(&state));
+    let mut stream = handler
+        .watch_health_services(Request::new(()))
+        .await
+        .unwrap()
+        .into_inner();
+    stream.next().await.unwrap().unwrap();
+
+    should_fail.store(true, Ordering::Relaxed);
+    let next = tokio::time::timeout(Duration::from_secs(1), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    match next.event.unwrap() {
+        Event::Changed(svc) => {
+            assert_eq!(svc.id, id.to_string());
+            assert_eq!(svc.status(), ServingStatus::NotServing);
+            assert_eq!(svc.message.as_deref(), Some("went down"));
+        }
+        other => panic!("unexpected event: {other:?}"),
+    }
+
+    state.health().deregister(&id).await;
+    let next = tokio::time::timeout(Duration::from_secs(1), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(matches!(next.event, Some(Event::Deregistered(_))));
+}
+
+#[tokio::test]
+async fn get_health_history_returns_results() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    let id = state
+        .health()
+        .register(
+            "svc",
+            Duration::from_secs(60),
+            None,
+            Box::new(|| Box::pin(async { Err("refused".to_owned()) })),
+        )
+        .await;
+
+    let handler = HealthServiceImpl::new(Arc::clone(&state));
+    let response = handler
+        .get_health_history(Request::new(OptionalIdRequest {
+            id: Some(id.to_string()),
+        }))
+        .await
+        .unwrap();
+
+    let history = response.get_ref();
+    assert_eq!(history.id, id.to_string());
+    assert_eq!(history.name, "svc");
+    assert!(!history.flapping);
+    assert_eq!(history.results.len(), 1);
+    assert_eq!(history.results[0].outcome(), Outcome::Failure);
+    assert_eq!(history.results[0].error.as_deref(), Some("refused"));
+    assert!(history.results[0].timestamp.is_some());
+}
+
+#[tokio::test]
+async fn get_health_history_unknown_uuid_returns_not_found() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    let handler = HealthServiceImpl::new(Arc::clone(&state));
+
+    let status = handler
+        .get_health_history(Request::new(OptionalIdRequest {
+            id: Some(Uuid::new_v4().to_string()),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[test]
+fn to_proto_probe_maps_outcome_and_latency() {
+    let record = ProbeRecord {
+        timestamp: std::time::SystemTime::now(),
+        latency: Duration::from_millis(1500),
+        outcome: ProbeOutcome::Timeout,
+        error: Some("probe timed out".to_owned()),
+    };
+
+    let proto = to_proto_probe(&record);
+    assert_eq!(proto.outcome(), Outcome::Timeout);
+    let latency = proto.latency.unwrap();
+    assert_eq!(latency.seconds, 1);
+    assert_eq!(latency.nanos, 500_000_000);
+}
+
+#[tokio::test]
+async fn list_health_services_includes_graph_and_root_cause() {
+    use crate::core::health::{Dependency, RegisterOptions};
+
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register(
+            "database",
+            Duration::from_secs(60),
+            None,
+            Box::new(|| Box::pin(async { Err("refused".to_owned()) })),
+        )
+        .await;
+    state
+        .health()
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            Box::new(|| Box::pin(async { Ok(()) })),
+            RegisterOptions {
+                dependencies: vec![Dependency::critical("database")],
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let handler = HealthServiceImpl::new(Arc::clone(&state));
+    let response = handler
+        .list_health_services(Request::new(()))
+        .await
+        .unwrap();
+    let list = response.get_ref();
+
+    assert_eq!(list.overall_status(), ServingStatus::NotServing);
+    assert_eq!(list.root_cause.as_deref(), Some("database"));
+    assert_eq!(list.dependencies.len(), 1);
+    assert_eq!(list.dependencies[0].service, "server");
+    assert_eq!(list.dependencies[0].dependency, "database");
+    assert!(list.dependencies[0].critical);
+
+    let server = list.services.iter().find(|s| s.name == "server").unwrap();
+    assert_eq!(server.status(), ServingStatus::Serving);
+    assert_eq!(server.aggregated_status(), ServingStatus::NotServing);
+    assert_eq!(server.root_cause.as_deref(), Some("database"));
+}
+
+#[tokio::test]
+async fn get_health_service_reports_aggregated_status() {
+    use crate::core::health::{Dependency, RegisterOptions};
+
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            Box::new(|| Box::pin(async { Ok(()) })),
+            RegisterOptions {
+                dependencies: vec![Dependency::optional("cache")],
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let handler = HealthServiceImpl::new(Arc::clone(&state));
+    let response = handler
+        .get_health_service(Request::new(OptionalIdRequest { id: None }))
+        .await
+        .unwrap();
+
+    let svc = response.get_ref();
+    assert_eq!(svc.aggregated_status(), ServingStatus::Degraded);
+    assert_eq!(svc.root_cause.as_deref(), Some("cache"));
+}
@@ -0,0 +1,201 @@
+use super::*;
+use crate::core::config::Config;
+use crate::core::health::HealthCheckFn;
+use crate::core::lifecycle::Lifecycle;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+fn test_pool() -> sqlx::PgPool {
+    sqlx::PgPool::connect_lazy("postgres://localhost/test").unwrap()
+}
+
+fn ok_check() -> HealthCheckFn {
+    Box::new(|| Box::pin(async { Ok(()) }))
+}
+
+fn failing_check() -> HealthCheckFn {
+    Box::new(|| Box::pin(async { Err("down".to_owned()) }))
+}
+
+fn check_request(service: &str) -> Request<HealthCheckRequest> {
+    Request::new(HealthCheckRequest {
+        service: service.to_owned(),
+    })
+}
+
+#[tokio::test]
+async fn check_named_service_serving() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register("database", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::new(Lifecycle::new()));
+    let response = handler.check(check_request("database")).await.unwrap();
+    assert_eq!(response.get_ref().status(), ServingStatus::Serving);
+}
+
+#[tokio::test]
+async fn check_named_service_not_serving() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register("database", Duration::from_secs(60), None, failing_check())
+        .await;
+
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::new(Lifecycle::new()));
+    let response = handler.check(check_request("database")).await.unwrap();
+    assert_eq!(response.get_ref().status(), ServingStatus::NotServing);
+}
+
+#[tokio::test]
+async fn check_unknown_service_returns_not_found() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::new(Lifecycle::new()));
+
+    let status = handler.check(check_request("missing")).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn check_empty_name_reports_combined_status() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register("server", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::new(Lifecycle::new()));
+    let response = handler.check(check_request("")).await.unwrap();
+    assert_eq!(response.get_ref().status(), ServingStatus::Serving);
+
+    state
+        .health()
+        .register("database", Duration::from_secs(60), None, failing_check())
+        .await;
+
+    let response = handler.check(check_request("")).await.unwrap();
+    assert_eq!(response.get_ref().status(), ServingStatus::NotServing);
+}
+
+#[tokio::test]
+async fn check_empty_name_not_serving_while_draining() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register("server", Duration::from_secs(60), None, ok_check())
+        .await;
+    let lifecycle = Arc::new(Lifecycle::new());
+    lifecycle.set_ready();
+
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::clone(&lifecycle));
+    let response = handler.check(check_request("")).await.unwrap();
+    assert_eq!(response.get_ref().status(), ServingStatus::Serving);
+
+    let mut stream = handler.watch(check_request("")).await.unwrap().into_inner();
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.status(), ServingStatus::Serving);
+
+    lifecycle.set_draining();
+
+    let response = handler.check(check_request("")).await.unwrap();
+    assert_eq!(response.get_ref().status(), ServingStatus::NotServing);
+    let next = tokio::time::timeout(Duration::from_secs(1), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(next.status(), ServingStatus::NotServing);
+
+    // Named services keep reporting their own status.
+    let response = handler.check(check_request("server")).await.unwrap();
+    assert_eq!(response.get_ref().status(), ServingStatus::Serving);
+}
+
+#[tokio::test]
+async fn watch_sends_current_status_first() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    state
+        .health()
+        .register("database", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::new(Lifecycle::new()));
+    let mut stream = handler
+        .watch(check_request("database"))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.status(), ServingStatus::Serving);
+}
+
+#[tokio::test]
+async fn watch_unknown_service_reports_unknown_then_serving() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::new(Lifecycle::new()));
+    let mut stream = handler
+        .watch(check_request("cache"))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(first.status(), ServingStatus::ServiceUnknown);
+
+    state
+        .health()
+        .register("cache", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let next = tokio::time::timeout(Duration::from_secs(1), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(next.status(), ServingStatus::Serving);
+}
+
+#[tokio::test]
+async fn watch_reports_deregistration_as_unknown() {
+    let state = AppState::new(Config::for_tests(), test_pool());
+    let id = state
+        .health()
+        .register("cache", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let handler = StandardHealthImpl::new(Arc::clone(&state), Arc::new(Lifecycle::new()));
+    let mut stream = handler
+        .watch(check_request("cache"))
+        .await
+        .unwrap()
+        .into_inner();
+    stream.next().await.unwrap().unwrap();
+
+    state.health().deregister(&id).await;
+
+    let next = tokio::time::timeout(Duration::from_secs(1), stream.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(next.status(), ServingStatus::ServiceUnknown);
+}
+
+#[test]
+fn serving_status_mapping() {
+    assert_eq!(
+        to_serving_status(ServiceStatus::Serving),
+        ServingStatus::Serving
+    );
+    assert_eq!(
+        to_serving_status(ServiceStatus::Degraded),
+        ServingStatus::Serving
+    );
+    assert_eq!(
+        to_serving_status(ServiceStatus::NotServing),
+        ServingStatus::NotServing
+    );
+}
@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn knows_every_registered_service() {
+    for path in [
+        "/midnight.HealthService/ListHealthServices",
+        "/midnight.AdminService/SetLogFilter",
+        "/grpc.health.v1.Health/Check",
+        "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+        "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+    ] {
+        assert!(is_known(path), "{path}");
+    }
+}
+
+#[test]
+fn unknown_paths_are_not_known() {
+    for path in [
+        "/midnight.AdminService/DropTables",
+        "/evil.Service/Method",
+        "/midnight.AdminService",
+        "/",
+        "",
+    ] {
+        assert!(!is_known(path), "{path}");
+    }
+}
@@ -0,0 +1,68 @@
+use super::*;
+use crate::core::config::Config;
+use axum::Router;
+use axum::body::Body;
+use axum::routing::post;
+use tower::ServiceExt;
+
+fn app(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/slow",
+            post(|| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                "done"
+            }),
+        )
+        .route("/fast", post(|| async { "done" }))
+        .layer(axum::middleware::from_fn_with_state(state, enforce))
+}
+
+async fn call(state: &Arc<AppState>, path: &str) -> Response {
+    app(Arc::clone(state))
+        .oneshot(http::Request::post(path).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+fn state(timeout_secs: u64) -> Arc<AppState> {
+    let mut config = Config::for_tests();
+    config.request_timeout_secs = timeout_secs;
+    let db = sqlx::PgPool::connect_lazy("postgres://localhost/test").unwrap();
+    AppState::new(config, db)
+}
+
+#[tokio::test]
+async fn slow_call_is_cancelled() {
+    let state = state(0);
+    let resp = call(&state, "/slow").await;
+    assert_eq!(resp.headers()["grpc-status"], "1");
+    assert_eq!(resp.headers()["grpc-message"], "Timeout%20expired");
+}
+
+#[tokio::test]
+async fn call_within_limit_passes() {
+    let resp = call(&state(0), "/fast").await;
+    assert!(resp.headers().get("grpc-status").is_none());
+}
+
+#[tokio::test]
+async fn limit_follows_config_updates() {
+    let state = state(0);
+    assert!(
+        call(&state, "/slow")
+            .await
+            .headers()
+            .contains_key("grpc-status")
+    );
+
+    let mut config = Config::for_tests();
+    config.request_timeout_secs = 5;
+    state.update_config(config);
+    assert!(
+        !call(&state, "/slow")
+            .await
+            .headers()
+            .contains_key("grpc-status")
+    );
+}
@@ -0,0 +1,104 @@
+use super::*;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::body::Body;
+use jsonwebtoken::{EncodingKey, Header};
+use tower::ServiceExt;
+
+use crate::core::authz::Policy;
+use crate::core::redact::Secret;
+
+const SECRET: &str = "an HS256 test secret of 32+ bytes";
+
+fn state() -> Arc<AppState> {
+    let mut config = Config::for_tests();
+    config.auth_jwt_secret = Some(Secret::new(SECRET.to_owned()));
+    config.authz_source = "config".to_owned();
+    config.authz_rules = vec!["midnight.AdminService=admin".to_owned()];
+    let db = sqlx::postgres::PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(100))
+        .connect_lazy("postgres://localhost:1/test")
+        .unwrap();
+    AppState::new(config, db)
+}
+
+fn token(subject: &str) -> String {
+    let exp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 300;
+    jsonwebtoken::encode(
+        &Header::default(),
+        &serde_json::json!({ "sub": subject, "exp": exp }),
+        &EncodingKey::from_secret(SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+/// The grpc-status of a call through every layer a listener has.
+async fn call(state: &Arc<AppState>, path: &str, token: Option<&str>) -> Option<String> {
+    let web_state = web::WebState::new(Arc::new(Lifecycle::new()));
+    let app = routes(state, &web_state, &"127.0.0.1:0".parse().unwrap()).unwrap();
+    let mut req = http::Request::post(path).header("content-type", "application/grpc");
+    if let Some(token) = token {
+        req = req.header("authorization", format!("Bearer {token}"));
+    }
+    let resp = app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+    resp.headers()
+        .get("grpc-status")
+        .map(|v| v.to_str().unwrap().to_owned())
+}
+
+#[tokio::test]
+async fn unknown_paths_share_one_metric_series() {
+    let state = state();
+    let token = token("alice");
+    for i in 0..3 {
+        let path = format!("/junk{i}.Service/Method");
+        assert_eq!(call(&state, &path, None).await.as_deref(), Some("16"));
+        assert_eq!(
+            call(&state, &path, Some(&token)).await.as_deref(),
+            Some("7")
+        );
+    }
+    assert_eq!(
+        call(&state, "/midnight.AdminService/SetLogFilter", Some(&token))
+            .await
+            .as_deref(),
+        Some("7")
+    );
+
+    let text = state
+        .metrics()
+        .render(state.db(), &crate::core::health::HealthRegistry::new())
+        .await;
+    assert!(!text.contains("junk"), "{text}");
+    assert!(text.contains(
+        r#"grpc_server_handled_total{grpc_code="Unauthenticated",grpc_method="unknown",grpc_service="unknown"} 3"#
+    ));
+    assert!(text.contains(
+        r#"grpc_server_handled_total{grpc_code="PermissionDenied",grpc_method="unknown",grpc_service="unknown"} 3"#
+    ));
+    assert!(text.contains(
+        r#"grpc_server_handled_total{grpc_code="PermissionDenied",grpc_method="SetLogFilter",grpc_service="midnight.AdminService"} 1"#
+    ));
+}
+
+#[tokio::test]
+async fn token_subjects_cannot_pass_for_unix_peers() {
+    let state = state();
+    let bind = |principal: &str| {
+        let config = state.config();
+        let rules = config.authz_rules.iter().filter_map(|r| r.parse().ok());
+        Policy::new(rules, [(principal.to_owned(), "admin".to_owned())])
+    };
+    let path = "/midnight.AdminService/GetLogFilter";
+    let token = token("uid:0");
+
+    state.set_policy(bind("uid:0"));
+    assert_eq!(call(&state, path, Some(&token)).await.as_deref(), Some("7"));
+
+    state.set_policy(bind("jwt:uid:0"));
+    assert_eq!(call(&state, path, Some(&token)).await.as_deref(), None);
+}
@@ -0,0 +1,178 @@
+use super::*;
+use crate::core::config::Config;
+use crate::core::health::{Dependency, HealthCheckFn, RegisterOptions};
+use crate::core::lifecycle::Lifecycle;
+use crate::core::state::AppState;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn test_pool() -> sqlx::PgPool {
+    sqlx::PgPool::connect_lazy("postgres://localhost/test").unwrap()
+}
+
+fn ok_check() -> HealthCheckFn {
+    Box::new(|| Box::pin(async { Ok(()) }))
+}
+
+fn failing_check() -> HealthCheckFn {
+    Box::new(|| Box::pin(async { Err("down".to_owned()) }))
+}
+
+fn ready_state() -> (WebState, Arc<AppState>) {
+    let lifecycle = Arc::new(Lifecycle::new());
+    lifecycle.set_ready();
+    let web = WebState::new(lifecycle);
+    let app = AppState::new(Config::for_tests(), test_pool());
+    web.attach(Arc::clone(&app));
+    (web, app)
+}
+
+#[tokio::test]
+async fn livez_is_ok_while_starting() {
+    let web = WebState::new(Arc::new(Lifecycle::new()));
+    let (code, Json(body)) = livez(State(web)).await;
+    assert_eq!(code, StatusCode::OK);
+    assert_eq!(body.status, "alive");
+}
+
+#[tokio::test]
+async fn readyz_unavailable_while_starting() {
+    let web = WebState::new(Arc::new(Lifecycle::new()));
+    let (code, Json(body)) = readyz(State(web)).await;
+    assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(!body.ready);
+    assert_eq!(body.phase, "starting");
+    assert!(body.status.is_none());
+}
+
+#[tokio::test]
+async fn readyz_ok_when_ready_and_healthy() {
+    let (web, app) = ready_state();
+    app.health()
+        .register("server", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let (code, Json(body)) = readyz(State(web)).await;
+    assert_eq!(code, StatusCode::OK);
+    assert!(body.ready);
+    assert_eq!(body.phase, "ready");
+    assert_eq!(body.status, Some("serving"));
+}
+
+#[tokio::test]
+async fn readyz_unavailable_when_critical_dependency_down() {
+    let (web, app) = ready_state();
+    app.health()
+        .register("database", Duration::from_secs(60), None, failing_check())
+        .await;
+    app.health()
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            RegisterOptions {
+                dependencies: vec![Dependency::critical("database")],
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let (code, Json(body)) = readyz(State(web)).await;
+    assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body.status, Some("not_serving"));
+    assert_eq!(body.root_cause.as_deref(), Some("database"));
+}
+
+#[tokio::test]
+async fn readyz_ok_when_degraded() {
+    let (web, app) = ready_state();
+    app.health()
+        .register_with_options(
+            "server",
+            Duration::from_secs(60),
+            None,
+            ok_check(),
+            RegisterOptions {
+                dependencies: vec![Dependency::optional("cache")],
+                ..RegisterOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let (code, Json(body)) = readyz(State(web)).await;
+    assert_eq!(code, StatusCode::OK);
+    assert_eq!(body.status, Some("degraded"));
+}
+
+#[tokio::test]
+async fn readyz_unavailable_while_draining() {
+    let (web, app) = ready_state();
+    app.health()
+        .register("server", Duration::from_secs(60), None, ok_check())
+        .await;
+    web.lifecycle().set_draining();
+
+    let (code, Json(body)) = readyz(State(web)).await;
+    assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body.phase, "draining");
+}
+
+#[tokio::test]
+async fn healthz_lists_services() {
+    let (web, app) = ready_state();
+    app.health()
+        .register("database", Duration::from_secs(60), None, failing_check())
+        .await;
+
+    let (code, Json(body)) = healthz(State(web)).await;
+    assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body.services.len(), 1);
+    assert_eq!(body.services[0].name, "database");
+    assert_eq!(body.services[0].status, "not_serving");
+    assert_eq!(body.services[0].message.as_deref(), Some("down"));
+}
+
+#[tokio::test]
+async fn healthz_json_shape() {
+    let (web, app) = ready_state();
+    app.health()
+        .register("server", Duration::from_secs(60), None, ok_check())
+        .await;
+
+    let (_, Json(body)) = healthz(State(web)).await;
+    let json = serde_json::to_value(&body).unwrap();
+    assert_eq!(json["ready"], true);
+    assert_eq!(json["phase"], "ready");
+    assert_eq!(json["status"], "serving");
+    assert_eq!(json["services"][0]["name"], "server");
+    assert_eq!(json["services"][0]["aggregated_status"], "serving");
+}
+
+#[tokio::test]
+async fn readyz_unavailable_under_server_override() {
+    use crate::core::health::{HealthOverride, ServiceStatus};
+
+    let (web, app) = ready_state();
+    app.health()
+        .register("server", Duration::from_secs(60), None, ok_check())
+        .await;
+    app.health()
+        .set_server_override(HealthOverride::new(
+            ServiceStatus::NotServing,
+            "db maintenance",
+            None,
+        ))
+        .await;
+
+    let (code, Json(body)) = readyz(State(web)).await;
+    assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(!body.ready);
+    assert_eq!(body.status, Some("not_serving"));
+    assert_eq!(
+        body.message.as_deref(),
+        Some("manual override: db maintenance")
+    );
+}
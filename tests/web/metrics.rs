@@ -0,0 +1,28 @@
+use super::*;
+use crate::core::config::Config;
+use crate::core::lifecycle::Lifecycle;
+use crate::core::state::AppState;
+use axum::body::to_bytes;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn metrics_unavailable_before_state_is_attached() {
+    let web = WebState::new(Arc::new(Lifecycle::new()));
+    let response = metrics(State(web)).await.into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn metrics_renders_prometheus_text() {
+    let web = WebState::new(Arc::new(Lifecycle::new()));
+    let pool = sqlx::PgPool::connect_lazy("postgres://localhost/test").unwrap();
+    web.attach(AppState::new(Config::for_tests(), pool));
+
+    let response = metrics(State(web)).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains("# TYPE midnight_db_pool_connections gauge"));
+}